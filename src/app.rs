@@ -2,12 +2,11 @@
 
 use std::sync::{atomic::AtomicUsize, Arc, Mutex};
 
-use actix_web::{web::Data, App};
+use actix_web::web::Data;
 
 use crate::server::ChatServer;
 
 pub struct AppState {
-    pub app_name: String,
     pub chat_server: Arc<Mutex<ChatServer>>,
 }
 
@@ -19,7 +18,6 @@ pub fn new_app_state() -> Data<AppState> {
     let server = ChatServer::new(count);
 
     Data::new(AppState {
-        app_name: "Chat Server".to_string(),
         chat_server: Arc::new(Mutex::new(server)),
     })
 }
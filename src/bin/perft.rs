@@ -0,0 +1,76 @@
+//! Count the leaf nodes of the move tree and report the speed of
+//! move generation, eg. `cargo run --release --bin perft -- 5`
+//!
+//! usage: perft <depth> [fen] [--variant <name>] [--divide]
+
+use std::env;
+use std::process;
+use std::time::Instant;
+
+use chess_api::chess::{Position, Variant};
+
+fn main() {
+    let mut depth = None;
+    let mut fen = Vec::new();
+    let mut variant = Variant::Standard;
+    let mut divide = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--divide" => divide = true,
+            "--variant" => {
+                let name = args.next().unwrap_or_default();
+                variant = Variant::parse(&name)
+                    .unwrap_or_else(|| exit(&format!("unknown variant {name}")));
+            }
+            _ if depth.is_none() => {
+                depth = Some(
+                    arg.parse::<u32>()
+                        .unwrap_or_else(|_| exit(&format!("invalid depth {arg}"))),
+                );
+            }
+            _ => fen.push(arg),
+        }
+    }
+
+    let depth =
+        depth.unwrap_or_else(|| exit("usage: perft <depth> [fen] [--variant <name>] [--divide]"));
+
+    let position = match fen.is_empty() {
+        true => Position::starting(),
+        false => Position::from_fen(&fen.join(" ")).unwrap_or_else(|err| exit(&err.to_string())),
+    };
+    let position = match variant {
+        Variant::Standard => position,
+        variant => position.with_variant(variant),
+    };
+
+    let started = Instant::now();
+    let nodes = if divide {
+        let mut total = 0;
+        for (mv, count) in position.divide(depth) {
+            println!("{}: {count}", position.uci(mv));
+            total += count;
+        }
+        println!();
+        total
+    } else {
+        position.perft(depth)
+    };
+    let elapsed = started.elapsed();
+
+    println!(
+        "depth {depth}: {nodes} nodes in {:.3}s",
+        elapsed.as_secs_f64()
+    );
+    println!(
+        "{:.0} nodes per second",
+        nodes as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{message}");
+    process::exit(1);
+}
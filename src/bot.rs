@@ -0,0 +1,311 @@
+use derive_more::Display;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::chess::search::{Search, SearchLimits};
+use crate::chess::{Color, Game};
+use crate::game::GameAnalysis;
+use crate::report::GameReport;
+use crate::server::ChatServer;
+use crate::session::SessionId;
+use crate::uci::{UciEngine, UciError, UciInfo, UciLimits, UciSearch};
+use crate::unlock;
+
+/// Thinking time per move of the UCI engine if `UCI_ENGINE_MOVETIME` is not set
+const ENGINE_MOVETIME: Duration = Duration::from_millis(1000);
+
+/// Thinking time per position when a finished game is analysed for its report
+const REPORT_MOVETIME: Duration = Duration::from_millis(300);
+
+static ENGINE_CONFIG: OnceLock<Option<EngineConfig>> = OnceLock::new();
+
+/// Strength of the built-in computer opponent, from 1 for
+/// beginners up to 8 which searches as deep as time allows
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[display(fmt = "{_0}")]
+pub struct BotLevel(u8);
+
+impl BotLevel {
+    pub const MAX: u8 = 8;
+
+    pub fn new(level: u8) -> Option<BotLevel> {
+        (1..=Self::MAX).contains(&level).then_some(BotLevel(level))
+    }
+
+    /// search depth, time budget and evaluation noise of the level
+    pub fn limits(self) -> SearchLimits {
+        let (depth, millis, randomness) = match self.0 {
+            1 => (1, 100, 300),
+            2 => (2, 200, 200),
+            3 => (3, 400, 120),
+            4 => (4, 700, 60),
+            5 => (5, 1000, 30),
+            6 => (6, 1500, 15),
+            7 => (8, 2500, 5),
+            _ => (64, 4000, 0),
+        };
+
+        SearchLimits {
+            depth,
+            time: Duration::from_millis(millis),
+            randomness,
+        }
+    }
+}
+
+impl Default for BotLevel {
+    fn default() -> Self {
+        BotLevel(3)
+    }
+}
+
+/// Engine a computer opponent plays with
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum BotEngine {
+    /// the server's own search at the given strength
+    #[display(fmt = "Computer level {_0}")]
+    BuiltIn(BotLevel),
+    /// the external engine configured with `UCI_ENGINE`
+    #[display(fmt = "UCI engine")]
+    Uci,
+}
+
+/// Computer opponent of a game, playing the pieces of `color`
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[display(fmt = "{engine}")]
+pub struct BotOpponent {
+    pub engine: BotEngine,
+    pub color: Color,
+}
+
+/// External UCI engine of the server, `UCI_ENGINE` is the command
+/// starting it, eg. `/usr/bin/stockfish`, and `UCI_ENGINE_MOVETIME`
+/// its thinking time per move in milliseconds
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    program: String,
+    args: Vec<String>,
+    limits: UciLimits,
+}
+
+impl EngineConfig {
+    /// the configured engine, `None` if `UCI_ENGINE` is not set
+    pub fn get() -> Option<&'static EngineConfig> {
+        ENGINE_CONFIG.get_or_init(EngineConfig::from_env).as_ref()
+    }
+
+    fn from_env() -> Option<EngineConfig> {
+        let command = std::env::var("UCI_ENGINE").ok()?;
+        let mut words = command.split_whitespace().map(str::to_string);
+        let program = words.next()?;
+
+        let movetime = std::env::var("UCI_ENGINE_MOVETIME")
+            .ok()
+            .and_then(|millis| millis.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(ENGINE_MOVETIME);
+
+        Some(EngineConfig {
+            program,
+            args: words.collect(),
+            limits: UciLimits {
+                movetime,
+                depth: None,
+            },
+        })
+    }
+
+    fn start(&self) -> Result<UciEngine, UciError> {
+        UciEngine::start(&self.program, &self.args)
+    }
+
+    /// search the current position of the game with a new engine
+    /// process, so a crashed or hung engine never outlives its search
+    fn search(&self, game: &Game, on_info: impl FnMut(&UciInfo)) -> Result<UciSearch, UciError> {
+        self.start()?.go(game, self.limits, on_info)
+    }
+}
+
+/// Search the current position of the game with the configured UCI
+/// engine, or the built-in search at its strongest when there is none
+/// or the engine fails, `on_info` is called as the search progresses
+pub fn analyse(game: &Game, mut on_info: impl FnMut(&UciInfo)) -> UciSearch {
+    if let Some(config) = EngineConfig::get() {
+        match config.search(game, &mut on_info) {
+            Ok(search) => return search,
+            Err(err) => log::warn!("engine failed, using the built-in search: {err}"),
+        }
+    }
+
+    built_in_search(game, BotLevel(BotLevel::MAX).limits(), on_info)
+}
+
+/// Search every position of a finished game for its report with one
+/// engine process for the whole game, or the built-in search when
+/// there is none or the engine fails, positions ending the game
+/// are not searched
+fn analyse_positions(game: &Game) -> Vec<UciSearch> {
+    let engine_limits = UciLimits {
+        movetime: REPORT_MOVETIME,
+        depth: None,
+    };
+    let built_in_limits = SearchLimits {
+        depth: BotLevel(BotLevel::MAX).limits().depth,
+        time: REPORT_MOVETIME,
+        randomness: 0,
+    };
+
+    let mut engine = EngineConfig::get().and_then(|config| match config.start() {
+        Ok(engine) => Some(engine),
+        Err(err) => {
+            log::warn!("engine failed, using the built-in search: {err}");
+            None
+        }
+    });
+
+    let mut replay = Game::new(*game.start());
+    let mut searches = Vec::new();
+    for ply in 0..=game.moves().len() {
+        if ply > 0 {
+            replay.push(game.moves()[ply - 1]);
+        }
+
+        if replay.position().outcome().is_some() {
+            searches.push(UciSearch {
+                best_move: None,
+                info: None,
+            });
+            continue;
+        }
+
+        let search = match engine
+            .as_mut()
+            .map(|engine| engine.go(&replay, engine_limits, |_| {}))
+        {
+            Some(Ok(search)) => search,
+            Some(Err(err)) => {
+                log::warn!("engine failed, using the built-in search: {err}");
+                engine = None;
+                built_in_search(&replay, built_in_limits, |_| {})
+            }
+            None => built_in_search(&replay, built_in_limits, |_| {}),
+        };
+        searches.push(search);
+    }
+
+    searches
+}
+
+/// search with the server's own engine, reporting its iterations as UCI `info`
+fn built_in_search(
+    game: &Game,
+    limits: SearchLimits,
+    mut on_info: impl FnMut(&UciInfo),
+) -> UciSearch {
+    let position = game.position();
+    let mut info = None;
+    let result = Search::new(limits).run(game, |result| {
+        let iteration = UciInfo::from_search(position, result);
+        on_info(&iteration);
+        info = Some(iteration);
+    });
+
+    UciSearch {
+        best_move: result.best_move,
+        info,
+    }
+}
+
+/// Background work once a game moved on, the computer answers,
+/// the engine evaluates the new position for the followers of
+/// the game and a game which is over gets its analysis report
+pub fn after_move(chat_server: Arc<Mutex<ChatServer>>, game_id: String) {
+    play_bot_move(chat_server.clone(), game_id.clone());
+    stream_eval(chat_server.clone(), game_id.clone());
+    report_game(chat_server, game_id);
+}
+
+/// If the bot is to move in the game, search its move in a
+/// background thread so the server is not blocked, then play it
+pub fn play_bot_move(chat_server: Arc<Mutex<ChatServer>>, game_id: String) {
+    let (game, bot) = match unlock!(chat_server).bot_turn(&game_id) {
+        Some(turn) => turn,
+        None => return,
+    };
+
+    thread::spawn(move || {
+        let best_move = match bot.engine {
+            BotEngine::BuiltIn(level) => Search::new(level.limits()).run(&game, |_| {}).best_move,
+            BotEngine::Uci => analyse(&game, |_| {}).best_move,
+        };
+
+        if let Some(mv) = best_move {
+            let result = unlock!(chat_server).send_bot_move(&game_id, game.position(), mv);
+            match result {
+                Ok(()) => after_move(chat_server, game_id),
+                Err(err) => log::warn!("bot move in game {game_id} not played: {err}"),
+            }
+        }
+    });
+}
+
+/// Stream the engine evaluation of the current position of the game to
+/// the followers of its evaluation from a background thread, a game is
+/// evaluated by one engine at a time which moves on to the new position
+/// if the game moved on while it was searching
+pub fn stream_eval(chat_server: Arc<Mutex<ChatServer>>, game_id: String) {
+    let mut game = match unlock!(chat_server).start_eval(&game_id) {
+        Some(game) => game,
+        None => return,
+    };
+
+    thread::spawn(move || loop {
+        let ply = game.moves().len();
+        let search = analyse(&game, |info| {
+            unlock!(chat_server).send_eval(&game_id, ply, info, false);
+        });
+
+        // the deepest evaluation is sent whatever the throttling
+        if let Some(info) = &search.info {
+            unlock!(chat_server).send_eval(&game_id, ply, info, true);
+        }
+
+        game = match unlock!(chat_server).next_eval(&game_id, ply) {
+            Some(game) => game,
+            None => break,
+        };
+    });
+}
+
+/// Once the game is over, analyse every position in a background
+/// thread and keep the report with the game
+pub fn report_game(chat_server: Arc<Mutex<ChatServer>>, game_id: String) {
+    let game = match unlock!(chat_server).start_report(&game_id) {
+        Some(game) => game,
+        None => return,
+    };
+
+    thread::spawn(move || {
+        let searches = analyse_positions(&game);
+        let report = GameReport::new(&game, &searches);
+
+        unlock!(chat_server).finish_report(&game_id, report);
+    });
+}
+
+/// Analyse the current position of the game in a background thread
+/// and send the engine's verdict to the session which asked for it
+pub fn send_analysis(
+    chat_server: Arc<Mutex<ChatServer>>,
+    game_id: String,
+    session_id: SessionId,
+    game: Game,
+) {
+    thread::spawn(move || {
+        let search = analyse(&game, |_| {});
+        let analysis = GameAnalysis::new(game_id, game.position(), &search);
+
+        unlock!(chat_server).send_analysis(session_id, &analysis);
+    });
+}
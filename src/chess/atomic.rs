@@ -0,0 +1,65 @@
+use super::movegen::KING_OFFSETS;
+use super::moves::{Move, MoveKind};
+use super::position::Position;
+use super::types::{Color, PieceKind, Square};
+
+impl Position {
+    /// a capture explodes the capturing piece along with every
+    /// piece other than a pawn on the squares around the capture
+    pub(super) fn explode(&mut self, center: Square) {
+        let mut exploded = vec![center];
+        exploded.extend(
+            KING_OFFSETS
+                .iter()
+                .filter_map(|(files, ranks)| center.offset(*files, *ranks))
+                .filter(|square| {
+                    matches!(self.piece_at(*square), Some(piece) if piece.kind != PieceKind::Pawn)
+                }),
+        );
+
+        for square in exploded {
+            if let Some(piece) = self.piece_at(square) {
+                if piece.kind == PieceKind::King {
+                    self.castling.clear(piece.color);
+                }
+            }
+            self.set_piece(square, None);
+            self.remove_castling_rook(square);
+        }
+    }
+
+    pub(super) fn kings_touching(&self) -> bool {
+        match (
+            self.king_square(Color::White),
+            self.king_square(Color::Black),
+        ) {
+            (Some(white), Some(black)) => {
+                white.file().abs_diff(black.file()) <= 1 && white.rank().abs_diff(black.rank()) <= 1
+            }
+            _ => false,
+        }
+    }
+
+    /// kings can not capture as they would explode themselves, exploding
+    /// the opponent's king wins even if the own king is left in check
+    pub(super) fn is_legal_atomic(&self, mv: Move) -> bool {
+        let color = self.side_to_move();
+
+        let king_captures = mv.kind != MoveKind::Castle
+            && matches!(self.piece_at(mv.from), Some(piece) if piece.kind == PieceKind::King)
+            && self.piece_at(mv.to).is_some();
+        if king_captures {
+            return false;
+        }
+
+        let next = self.make_move(mv);
+        if next.king_square(color).is_none() {
+            return false;
+        }
+        if next.king_square(color.opposite()).is_none() {
+            return true;
+        }
+
+        !next.is_in_check(color)
+    }
+}
@@ -0,0 +1,87 @@
+use super::position::{CastleSide, CastlingRights, Position};
+use super::types::{Color, Piece, PieceKind, Square};
+use super::variant::Variant;
+
+/// Number of Chess960 starting positions
+pub const CHESS960_POSITIONS: u16 = 960;
+
+/// Files of the two knights among the five squares left
+/// once the bishops and queen are placed
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+impl Position {
+    /// Chess960 starting position from its index in Scharnagl's
+    /// numbering, `0..960` with 518 the standard starting position
+    pub fn chess960(index: u16) -> Option<Position> {
+        if index >= CHESS960_POSITIONS {
+            return None;
+        }
+
+        let mut back_rank: [Option<PieceKind>; 8] = [None; 8];
+        let mut n = index as usize;
+
+        // bishops on opposite colored squares
+        back_rank[(n % 4) * 2 + 1] = Some(PieceKind::Bishop);
+        n /= 4;
+        back_rank[(n % 4) * 2] = Some(PieceKind::Bishop);
+        n /= 4;
+
+        let empty_files = |rank: &[Option<PieceKind>; 8]| -> Vec<usize> {
+            (0..8).filter(|file| rank[*file].is_none()).collect()
+        };
+
+        let queen = empty_files(&back_rank)[n % 6];
+        back_rank[queen] = Some(PieceKind::Queen);
+        n /= 6;
+
+        let empty = empty_files(&back_rank);
+        let (first, second) = KNIGHT_PLACEMENTS[n];
+        back_rank[empty[first]] = Some(PieceKind::Knight);
+        back_rank[empty[second]] = Some(PieceKind::Knight);
+
+        // the king always stands between the two rooks
+        let empty = empty_files(&back_rank);
+        back_rank[empty[0]] = Some(PieceKind::Rook);
+        back_rank[empty[1]] = Some(PieceKind::King);
+        back_rank[empty[2]] = Some(PieceKind::Rook);
+
+        let mut position = Position::empty();
+        for color in [Color::White, Color::Black] {
+            let pawn_rank = (color.back_rank() as i8 + color.forward()) as u8;
+            for (file, kind) in back_rank.iter().enumerate() {
+                let file = file as u8;
+                if let Some(kind) = kind {
+                    position.set_piece(
+                        Square::new(file, color.back_rank()),
+                        Some(Piece::new(color, *kind)),
+                    );
+                }
+                position.set_piece(
+                    Square::new(file, pawn_rank),
+                    Some(Piece::new(color, PieceKind::Pawn)),
+                );
+            }
+        }
+
+        let mut castling = CastlingRights::default();
+        for color in [Color::White, Color::Black] {
+            castling.set(color, CastleSide::Queen, Some(empty[0] as u8));
+            castling.set(color, CastleSide::King, Some(empty[2] as u8));
+        }
+        position.castling = castling;
+        position.variant = Variant::Chess960;
+
+        Some(position)
+    }
+}
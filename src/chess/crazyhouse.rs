@@ -0,0 +1,124 @@
+use super::moves::Move;
+use super::position::Position;
+use super::types::{Color, Piece, PieceKind, Square};
+
+/// Pieces which can be held in a pocket and dropped
+pub const DROP_PIECES: [PieceKind; 5] = [
+    PieceKind::Pawn,
+    PieceKind::Knight,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+    PieceKind::Queen,
+];
+
+/// Pieces other than the kings a game starts with, the most there
+/// can be on the board and in the pockets together
+pub const MAX_PIECES: usize = 30;
+
+/// Captured pieces held by each side in Crazyhouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Pockets {
+    counts: [[u8; 5]; 2],
+}
+
+impl Pockets {
+    pub fn count(&self, color: Color, kind: PieceKind) -> u8 {
+        match kind {
+            PieceKind::King => 0,
+            _ => self.counts[color as usize][kind as usize],
+        }
+    }
+
+    pub fn add(&mut self, color: Color, kind: PieceKind) {
+        if kind != PieceKind::King {
+            let count = &mut self.counts[color as usize][kind as usize];
+            *count = count.saturating_add(1);
+        }
+    }
+
+    pub fn remove(&mut self, color: Color, kind: PieceKind) {
+        if kind != PieceKind::King {
+            let count = &mut self.counts[color as usize][kind as usize];
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    /// pieces in both pockets
+    pub fn total(&self) -> usize {
+        self.counts
+            .iter()
+            .flatten()
+            .map(|count| *count as usize)
+            .sum()
+    }
+
+    /// pocket in FEN, white pieces first, eg. `[QNpp]`
+    pub fn to_fen(self) -> String {
+        let mut pocket = String::from("[");
+        for color in [Color::White, Color::Black] {
+            for kind in DROP_PIECES.iter().rev() {
+                for _ in 0..self.count(color, *kind) {
+                    pocket.push(Piece::new(color, *kind).to_char());
+                }
+            }
+        }
+        pocket.push(']');
+        pocket
+    }
+}
+
+impl Position {
+    /// drops of every piece in the pocket of the side to move on
+    /// every empty square, pawns can not be dropped on the back ranks
+    pub(super) fn drop_moves(&self, moves: &mut Vec<Move>) {
+        let color = self.side_to_move();
+
+        for kind in DROP_PIECES {
+            if self.pockets.count(color, kind) == 0 {
+                continue;
+            }
+
+            for to in Square::all() {
+                let back_rank = to.rank() == 0 || to.rank() == 7;
+                if self.piece_at(to).is_none() && !(kind == PieceKind::Pawn && back_rank) {
+                    moves.push(Move::drop(kind, to));
+                }
+            }
+        }
+    }
+
+    /// called before the move is made, a captured piece goes to the pocket
+    /// of the capturing side, as a pawn if it was promoted, and promoted
+    /// pieces are followed to their new square
+    pub(super) fn update_pockets(
+        &mut self,
+        mv: Move,
+        captured_on: Option<Square>,
+        captured: Option<Piece>,
+    ) {
+        let color = self.side_to_move();
+
+        if let (Some(square), Some(piece)) = (captured_on, captured) {
+            let kind = match self.is_promoted(square) {
+                true => PieceKind::Pawn,
+                false => piece.kind,
+            };
+            self.pockets.add(color, kind);
+        }
+
+        let was_promoted = self.is_promoted(mv.from);
+        for square in [Some(mv.from), Some(mv.to), captured_on]
+            .into_iter()
+            .flatten()
+        {
+            self.promoted &= !(1 << square.index());
+        }
+        if was_promoted || mv.promotion.is_some() {
+            self.promoted |= 1 << mv.to.index();
+        }
+    }
+
+    pub fn is_promoted(&self, square: Square) -> bool {
+        self.promoted & (1 << square.index()) != 0
+    }
+}
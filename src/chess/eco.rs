@@ -0,0 +1,69 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::position::Position;
+use super::variant::Variant;
+
+/// Encyclopaedia of Chess Openings table, a header line then
+/// the ECO code, name and moves in SAN of each opening
+pub const ECO_TABLE: &str = include_str!("eco.tsv");
+
+/// Openings of the table by the hash of the position they reach
+static OPENINGS: OnceLock<HashMap<u64, Opening>> = OnceLock::new();
+
+/// A named opening of the Encyclopaedia of Chess Openings
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Opening {
+    pub eco: String,
+    pub name: String,
+}
+
+impl Opening {
+    /// the opening a standard chess position is known as, openings are
+    /// looked up by position so move orders which transpose are named
+    pub fn of(position: &Position) -> Option<&'static Opening> {
+        if position.variant() != Variant::Standard {
+            return None;
+        }
+
+        OPENINGS.get_or_init(load).get(&position.hash())
+    }
+}
+
+/// play out the moves of every opening of the table, an opening
+/// with a move that does not parse is left out
+fn load() -> HashMap<u64, Opening> {
+    let mut openings = HashMap::new();
+
+    for line in ECO_TABLE.lines().skip(1) {
+        let mut fields = line.split('\t');
+        let (Some(eco), Some(name), Some(moves)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+
+        let mut position = Position::starting();
+        let played = moves
+            .split_whitespace()
+            // skip move numbers
+            .filter(|token| !token.ends_with('.'))
+            .all(|san| match position.parse_san(san) {
+                Ok(mv) => {
+                    position = position.make_move(mv);
+                    true
+                }
+                Err(_) => false,
+            });
+
+        if played {
+            let opening = Opening {
+                eco: eco.to_string(),
+                name: name.to_string(),
+            };
+            openings.insert(position.hash(), opening);
+        }
+    }
+
+    openings
+}
@@ -0,0 +1,185 @@
+use super::crazyhouse::DROP_PIECES;
+use super::position::Position;
+use super::types::{Color, PieceKind, Square};
+use super::variant::Variant;
+
+/// Value of each piece kind in centipawns, indexed by `PieceKind`
+pub const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+/// Piece-square tables from white's point of view, the first entry
+/// is a8 so the tables read like a board, mirrored for black
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+/// king stays sheltered while there is material to attack it
+#[rustfmt::skip]
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+/// king moves to the center once most pieces are traded
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+/// material left, excluding pawns and kings, below which
+/// the king uses the endgame table
+const ENDGAME_MATERIAL: i32 = 1300;
+
+/// bonus for every check given in Three-check
+const CHECK_BONUS: i32 = 200;
+
+impl Position {
+    /// static evaluation in centipawns from the side to move's point
+    /// of view, material and piece-square tables plus variant terms
+    pub fn evaluate(&self) -> i32 {
+        let mut score = 0;
+        let mut non_pawn_material = 0;
+
+        for square in Square::all() {
+            if let Some(piece) = self.piece_at(square) {
+                if !matches!(piece.kind, PieceKind::Pawn | PieceKind::King) {
+                    non_pawn_material += PIECE_VALUES[piece.kind as usize];
+                }
+            }
+        }
+        let endgame = non_pawn_material <= ENDGAME_MATERIAL;
+
+        for square in Square::all() {
+            let piece = match self.piece_at(square) {
+                Some(piece) => piece,
+                None => continue,
+            };
+
+            let value = PIECE_VALUES[piece.kind as usize]
+                + piece_square_value(piece.kind, piece.color, square, endgame);
+            score += match piece.color {
+                Color::White => value,
+                Color::Black => -value,
+            };
+        }
+
+        for color in [Color::White, Color::Black] {
+            let sign = match color {
+                Color::White => 1,
+                Color::Black => -1,
+            };
+
+            score += sign
+                * match self.variant() {
+                    Variant::Crazyhouse => DROP_PIECES
+                        .iter()
+                        .map(|kind| {
+                            self.pockets().count(color, *kind) as i32 * PIECE_VALUES[*kind as usize]
+                        })
+                        .sum::<i32>(),
+                    Variant::ThreeCheck => self.checks(color) as i32 * CHECK_BONUS,
+                    // the king wants the center whatever material is left
+                    Variant::KingOfTheHill => self
+                        .king_square(color)
+                        .map(|king| {
+                            piece_square_value(PieceKind::King, color, king, true)
+                                - piece_square_value(PieceKind::King, color, king, endgame)
+                        })
+                        .unwrap_or(0),
+                    _ => 0,
+                };
+        }
+
+        match self.side_to_move() {
+            Color::White => score,
+            Color::Black => -score,
+        }
+    }
+}
+
+fn piece_square_value(kind: PieceKind, color: Color, square: Square, endgame: bool) -> i32 {
+    // tables start at a8, so white squares are flipped vertically
+    let rank = match color {
+        Color::White => 7 - square.rank(),
+        Color::Black => square.rank(),
+    };
+    let index = rank as usize * 8 + square.file() as usize;
+
+    match kind {
+        PieceKind::Pawn => PAWN_TABLE[index],
+        PieceKind::Knight => KNIGHT_TABLE[index],
+        PieceKind::Bishop => BISHOP_TABLE[index],
+        PieceKind::Rook => ROOK_TABLE[index],
+        PieceKind::Queen => QUEEN_TABLE[index],
+        PieceKind::King if endgame => KING_ENDGAME_TABLE[index],
+        PieceKind::King => KING_MIDDLEGAME_TABLE[index],
+    }
+}
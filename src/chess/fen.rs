@@ -0,0 +1,378 @@
+use derive_more::Display;
+
+use super::crazyhouse::MAX_PIECES;
+use super::position::{CastleSide, CastlingRights, Position};
+use super::types::{Color, Piece, PieceKind, Square};
+use super::variant::{Variant, CHECKS_TO_WIN};
+
+/// Largest move counter a FEN may give, far beyond any game played
+const MAX_MOVE_COUNTER: u32 = 10_000;
+
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum FenError {
+    #[display(fmt = "FEN must have 6 fields, found {_0}")]
+    FieldCount(usize),
+    #[display(fmt = "Invalid piece placement in FEN: {_0}")]
+    Board(String),
+    #[display(fmt = "Invalid side to move in FEN: {_0}")]
+    SideToMove(String),
+    #[display(fmt = "Invalid castling rights in FEN: {_0}")]
+    Castling(String),
+    #[display(fmt = "Invalid en passant square in FEN: {_0}")]
+    EnPassant(String),
+    #[display(fmt = "Invalid move counter in FEN: {_0}")]
+    MoveCounter(String),
+    #[display(fmt = "Illegal position in FEN: {_0}")]
+    IllegalPosition(String),
+    #[display(fmt = "Invalid pocket in FEN: {_0}")]
+    Pocket(String),
+    #[display(fmt = "Invalid check counter in FEN: {_0}")]
+    Checks(String),
+}
+
+impl Position {
+    /// parse a position from Forsyth-Edwards Notation, the move
+    /// counters may be left out and default to `0 1`, castling rights
+    /// may be given as X-FEN or Shredder-FEN rook files for Chess960,
+    /// Crazyhouse pockets follow the board in brackets, `[Qp]`, and
+    /// Three-check counters are given as a last `+1+0` field, at most
+    /// the checks it takes to win
+    pub fn from_fen(fen: &str) -> Result<Position, FenError> {
+        let mut fields: Vec<&str> = fen.split_whitespace().collect();
+
+        let mut position = Position::empty();
+
+        // checks given by white and black
+        if let Some(checks) = fields.last().filter(|field| field.starts_with('+')) {
+            let counts: Vec<u8> = checks[1..]
+                .split('+')
+                .map(|count| count.parse::<u8>().ok().filter(|n| *n <= CHECKS_TO_WIN))
+                .collect::<Option<_>>()
+                .ok_or_else(|| FenError::Checks(checks.to_string()))?;
+            match counts.as_slice() {
+                [white, black] => position.checks = [*white, *black],
+                _ => return Err(FenError::Checks(checks.to_string())),
+            }
+            fields.pop();
+        }
+
+        if fields.len() == 4 {
+            fields.extend(["0", "1"]);
+        }
+        if fields.len() != 6 {
+            return Err(FenError::FieldCount(fields.len()));
+        }
+
+        // pieces in hand, after the board in brackets
+        let board = match fields[0].split_once('[') {
+            Some((board, pocket)) => {
+                let pocket = pocket
+                    .strip_suffix(']')
+                    .ok_or_else(|| FenError::Pocket(pocket.to_string()))?;
+                if pocket.len() > MAX_PIECES {
+                    return Err(FenError::Pocket(pocket.to_string()));
+                }
+                for c in pocket.chars() {
+                    match Piece::from_char(c) {
+                        Some(piece) if piece.kind != PieceKind::King => {
+                            position.pockets.add(piece.color, piece.kind)
+                        }
+                        _ => return Err(FenError::Pocket(pocket.to_string())),
+                    }
+                }
+                board
+            }
+            None => fields[0],
+        };
+
+        // piece placement, from the eighth rank down to the first,
+        // promoted pieces in Crazyhouse are followed by a `~`
+        let ranks: Vec<&str> = board.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::Board(fields[0].to_string()));
+        }
+        for (i, rank_str) in ranks.iter().enumerate() {
+            let rank = 7 - i as u8;
+            let mut file = 0;
+            for c in rank_str.chars() {
+                if c == '~' {
+                    if !(1..=8).contains(&file) {
+                        return Err(FenError::Board(fields[0].to_string()));
+                    }
+                    position.promoted |= 1 << Square::new(file - 1, rank).index();
+                } else if let Some(empty) = c.to_digit(10) {
+                    file = file
+                        .checked_add(empty as u8)
+                        .filter(|file| *file <= 8)
+                        .ok_or_else(|| FenError::Board(fields[0].to_string()))?;
+                } else {
+                    let piece = Piece::from_char(c)
+                        .ok_or_else(|| FenError::Board(fields[0].to_string()))?;
+                    if file >= 8 {
+                        return Err(FenError::Board(fields[0].to_string()));
+                    }
+                    position.set_piece(Square::new(file, rank), Some(piece));
+                    file += 1;
+                }
+            }
+            if file != 8 {
+                return Err(FenError::Board(fields[0].to_string()));
+            }
+        }
+
+        // captured pieces only move to the pockets, there can not be
+        // more of them than pieces the game started with
+        let pieces = Square::all()
+            .filter_map(|sq| position.piece_at(sq))
+            .filter(|piece| piece.kind != PieceKind::King)
+            .count();
+        let pocketed = position.pockets.total();
+        if pocketed > 0 && pieces + pocketed > MAX_PIECES {
+            return Err(FenError::Pocket(fields[0].to_string()));
+        }
+
+        let side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(FenError::SideToMove(other.to_string())),
+        };
+
+        let mut castling = CastlingRights::default();
+        let mut rook_files_given = false;
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                let color = match c.is_ascii_uppercase() {
+                    true => Color::White,
+                    false => Color::Black,
+                };
+                let king_file = position.king_square(color).map(|sq| sq.file());
+                let (side, rook_file) = match c.to_ascii_lowercase() {
+                    'k' => (
+                        CastleSide::King,
+                        position.outer_rook(color, CastleSide::King),
+                    ),
+                    'q' => (
+                        CastleSide::Queen,
+                        position.outer_rook(color, CastleSide::Queen),
+                    ),
+                    file @ 'a'..='h' => {
+                        rook_files_given = true;
+                        let file = file as u8 - b'a';
+                        match king_file {
+                            Some(king_file) if file > king_file => (CastleSide::King, Some(file)),
+                            _ => (CastleSide::Queen, Some(file)),
+                        }
+                    }
+                    _ => return Err(FenError::Castling(fields[2].to_string())),
+                };
+                castling.set(color, side, rook_file);
+            }
+        }
+
+        let en_passant = match fields[3] {
+            "-" => None,
+            square => match Square::parse(square) {
+                Some(sq) if sq.rank() == 2 || sq.rank() == 5 => Some(sq),
+                _ => return Err(FenError::EnPassant(square.to_string())),
+            },
+        };
+
+        let halfmove_clock = fields[4]
+            .parse::<u32>()
+            .ok()
+            .filter(|clock| *clock <= MAX_MOVE_COUNTER)
+            .ok_or_else(|| FenError::MoveCounter(fields[4].to_string()))?;
+        let fullmove_number = fields[5]
+            .parse::<u32>()
+            .ok()
+            .filter(|number| *number <= MAX_MOVE_COUNTER)
+            .ok_or_else(|| FenError::MoveCounter(fields[5].to_string()))?
+            .max(1);
+
+        position.side_to_move = side_to_move;
+        position.castling = castling;
+        position.en_passant = en_passant;
+        position.halfmove_clock = halfmove_clock;
+        position.fullmove_number = fullmove_number;
+        position.validate()?;
+
+        // castling from anywhere other than the standard squares
+        // can only happen in Chess960
+        let standard = CastlingRights::standard();
+        let chess960 = rook_files_given
+            || [Color::White, Color::Black].into_iter().any(|color| {
+                let king_file = position.king_square(color).map(|sq| sq.file());
+                [CastleSide::King, CastleSide::Queen]
+                    .into_iter()
+                    .any(|side| {
+                        let rook_file = position.castling.rook_file(color, side);
+                        rook_file.is_some()
+                            && (rook_file != standard.rook_file(color, side)
+                                || king_file != Some(4))
+                    })
+            });
+        if chess960 {
+            position.variant = Variant::Chess960;
+        }
+
+        Ok(position)
+    }
+
+    /// write the position in Forsyth-Edwards Notation, Chess960
+    /// positions use X-FEN castling rights, Crazyhouse positions
+    /// include the pockets and Three-check positions the checks given
+    pub fn to_fen(self) -> String {
+        let crazyhouse = self.variant() == Variant::Crazyhouse;
+
+        let mut board = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                let square = Square::new(file, rank);
+                match self.piece_at(square) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            board.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        board.push(piece.to_char());
+                        if crazyhouse && self.is_promoted(square) {
+                            board.push('~');
+                        }
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                board.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                board.push('/');
+            }
+        }
+        if crazyhouse {
+            board.push_str(&self.pockets().to_fen());
+        }
+
+        let side_to_move = match self.side_to_move() {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let mut castling = String::new();
+        for (c, color, side) in [
+            ('K', Color::White, CastleSide::King),
+            ('Q', Color::White, CastleSide::Queen),
+            ('k', Color::Black, CastleSide::King),
+            ('q', Color::Black, CastleSide::Queen),
+        ] {
+            let rook_file = match self.castling().rook_file(color, side) {
+                Some(file) => file,
+                None => continue,
+            };
+            // X-FEN names the rook file when it is not the outermost rook
+            if self.is_chess960() && self.outer_rook(color, side) != Some(rook_file) {
+                let file = (b'a' + rook_file) as char;
+                castling.push(match color {
+                    Color::White => file.to_ascii_uppercase(),
+                    Color::Black => file,
+                });
+            } else {
+                castling.push(c);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = match self.en_passant() {
+            Some(square) => square.to_string(),
+            None => "-".to_string(),
+        };
+
+        let mut fen = format!(
+            "{board} {side_to_move} {castling} {en_passant} {} {}",
+            self.halfmove_clock(),
+            self.fullmove_number()
+        );
+        if self.variant() == Variant::ThreeCheck {
+            fen.push_str(&format!(
+                " +{}+{}",
+                self.checks(Color::White),
+                self.checks(Color::Black)
+            ));
+        }
+
+        fen
+    }
+
+    /// check the position could be reached in a game, one king per side,
+    /// no pawns on the back ranks and the side not to move is not in check,
+    /// castling rights without the king and rook at home are dropped
+    fn validate(&mut self) -> Result<(), FenError> {
+        for color in [Color::White, Color::Black] {
+            let kings = Square::all()
+                .filter(|sq| self.piece_at(*sq) == Some(Piece::new(color, PieceKind::King)))
+                .count();
+            if kings != 1 {
+                return Err(FenError::IllegalPosition(format!(
+                    "{color:?} must have exactly one king"
+                )));
+            }
+        }
+
+        let pawn_on_back_rank = Square::all().any(|sq| {
+            (sq.rank() == 0 || sq.rank() == 7)
+                && matches!(self.piece_at(sq), Some(piece) if piece.kind == PieceKind::Pawn)
+        });
+        if pawn_on_back_rank {
+            return Err(FenError::IllegalPosition(
+                "pawns can not be on the first or last rank".to_string(),
+            ));
+        }
+
+        if self.is_in_check(self.side_to_move().opposite()) {
+            return Err(FenError::IllegalPosition(
+                "the side not to move is in check".to_string(),
+            ));
+        }
+
+        for color in [Color::White, Color::Black] {
+            let rank = color.back_rank();
+            let king_file = self
+                .king_square(color)
+                .filter(|sq| sq.rank() == rank)
+                .map(|sq| sq.file());
+            for side in [CastleSide::King, CastleSide::Queen] {
+                let rook_file = self.castling.rook_file(color, side);
+                let rook_home = rook_file.is_some_and(|file| {
+                    self.piece_at(Square::new(file, rank))
+                        == Some(Piece::new(color, PieceKind::Rook))
+                });
+                let rook_on_side = match (king_file, rook_file, side) {
+                    (Some(king), Some(rook), CastleSide::King) => rook > king,
+                    (Some(king), Some(rook), CastleSide::Queen) => rook < king,
+                    _ => false,
+                };
+                if !rook_home || !rook_on_side {
+                    self.castling.set(color, side, None);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// file of the rook furthest from the king on the given side
+    /// of its back rank, which `K` and `Q` castling rights refer to
+    fn outer_rook(&self, color: Color, side: CastleSide) -> Option<u8> {
+        let rank = color.back_rank();
+        let king_file = self.king_square(color)?.file();
+        let rook = Some(Piece::new(color, PieceKind::Rook));
+        let is_rook = |file: &u8| self.piece_at(Square::new(*file, rank)) == rook;
+        match side {
+            CastleSide::King => (king_file + 1..8).rev().find(is_rook),
+            CastleSide::Queen => (0..king_file).find(is_rook),
+        }
+    }
+}
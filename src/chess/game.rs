@@ -0,0 +1,162 @@
+use super::eco::Opening;
+use super::moves::{Move, MoveError};
+use super::outcome::{GameEndReason, GameOutcome};
+use super::position::Position;
+use super::variant::Variant;
+
+/// Half moves without a capture or pawn move before a draw can be claimed
+const FIFTY_MOVE_PLIES: u32 = 100;
+
+/// Half moves without a capture or pawn move before the game is drawn
+const SEVENTY_FIVE_MOVE_PLIES: u32 = 150;
+
+/// A chess game from its starting position, keeps the played
+/// moves and the hash of every position reached so
+/// repetitions can be detected, and the positions the
+/// moves were played from so they can be taken back
+#[derive(Debug, Clone)]
+pub struct Game {
+    start: Position,
+    position: Position,
+    moves: Vec<Move>,
+    hashes: Vec<u64>,
+    /// position before each move
+    history: Vec<Position>,
+    /// the last named opening the game reached
+    opening: Option<&'static Opening>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(Position::starting())
+    }
+}
+
+impl Game {
+    pub fn new(start: Position) -> Self {
+        Self {
+            start,
+            position: start,
+            moves: Vec::new(),
+            hashes: vec![start.hash()],
+            history: Vec::new(),
+            opening: Opening::of(&start),
+        }
+    }
+
+    pub fn start(&self) -> &Position {
+        &self.start
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// hash of every position reached, the starting position first
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// opening the game is classified as, the deepest position of
+    /// the ECO table it reached, kept once the game leaves the table
+    pub fn opening(&self) -> Option<&'static Opening> {
+        self.opening
+    }
+
+    /// variant the game is played with, taken from its starting position
+    pub fn variant(&self) -> Variant {
+        self.start.variant()
+    }
+
+    /// validate a move in UCI, long or standard algebraic
+    /// notation and play it if it is legal
+    pub fn play(&mut self, notation: &str) -> Result<Move, MoveError> {
+        let before = self.position;
+        let mv = self.position.play(notation)?;
+        self.record(before, mv);
+
+        Ok(mv)
+    }
+
+    /// play a move which has already been validated
+    /// against the current position
+    pub fn push(&mut self, mv: Move) {
+        let before = self.position;
+        self.position = self.position.make_move(mv);
+        self.record(before, mv);
+    }
+
+    /// take back the last move, the game is back in the position it
+    /// was played from, `None` if no move was played
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        self.hashes.pop();
+        self.position = self.history.pop().unwrap_or(self.start);
+
+        // the opening of the deepest position left
+        self.opening = self
+            .history
+            .iter()
+            .chain([&self.position])
+            .rev()
+            .find_map(Opening::of);
+
+        Some(mv)
+    }
+
+    fn record(&mut self, before: Position, mv: Move) {
+        self.history.push(before);
+        self.moves.push(mv);
+        self.hashes.push(self.position.hash());
+
+        if let Some(opening) = Opening::of(&self.position) {
+            self.opening = Some(opening);
+        }
+    }
+
+    /// number of times the current position has occurred in the game
+    pub fn repetitions(&self) -> usize {
+        let current = self.position.hash();
+        self.hashes.iter().filter(|hash| **hash == current).count()
+    }
+
+    /// outcome which ends the game automatically, checkmate and stalemate,
+    /// fivefold repetition, the seventy-five-move rule and insufficient material
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        if let Some(outcome) = self.position.outcome() {
+            return Some(outcome);
+        }
+
+        if self.repetitions() >= 5 {
+            return Some(GameOutcome::draw(GameEndReason::FivefoldRepetition));
+        }
+
+        if self.position.halfmove_clock() >= SEVENTY_FIVE_MOVE_PLIES {
+            return Some(GameOutcome::draw(GameEndReason::SeventyFiveMoveRule));
+        }
+
+        if self.position.has_insufficient_material() {
+            return Some(GameOutcome::draw(GameEndReason::InsufficientMaterial));
+        }
+
+        None
+    }
+
+    /// draw which a player may claim in the current position,
+    /// threefold repetition or the fifty-move rule
+    pub fn claimable_draw(&self) -> Option<GameEndReason> {
+        if self.repetitions() >= 3 {
+            return Some(GameEndReason::ThreefoldRepetition);
+        }
+
+        if self.position.halfmove_clock() >= FIFTY_MOVE_PLIES {
+            return Some(GameEndReason::FiftyMoveRule);
+        }
+
+        None
+    }
+}
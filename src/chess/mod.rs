@@ -0,0 +1,26 @@
+mod atomic;
+pub mod chess960;
+pub mod crazyhouse;
+pub mod eco;
+mod eval;
+pub mod fen;
+pub mod game;
+mod movegen;
+pub mod moves;
+pub mod outcome;
+mod perft;
+pub mod pgn;
+pub mod position;
+mod san;
+pub mod search;
+pub mod types;
+pub mod variant;
+mod zobrist;
+
+pub use eco::Opening;
+pub use game::Game;
+pub use moves::{Move, MoveError, MoveKind};
+pub use outcome::{GameEndReason, GameOutcome, GameResult};
+pub use position::Position;
+pub use types::{Color, PieceKind, Square};
+pub use variant::Variant;
@@ -0,0 +1,259 @@
+use super::moves::{Move, MoveKind};
+use super::position::{CastleSide, Position};
+use super::types::{Color, Piece, PieceKind, Square};
+use super::variant::Variant;
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+pub(super) const KING_OFFSETS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+const PROMOTION_PIECES: [PieceKind; 4] = [
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+];
+
+impl Position {
+    /// all moves for the side to move, ignoring
+    /// whether the move leaves the own king in check
+    pub fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let color = self.side_to_move();
+        let mut moves = Vec::with_capacity(64);
+
+        for from in Square::all() {
+            let piece = match self.piece_at(from) {
+                Some(piece) if piece.color == color => piece,
+                _ => continue,
+            };
+
+            match piece.kind {
+                PieceKind::Pawn => self.pawn_moves(from, color, &mut moves),
+                PieceKind::Knight => self.step_moves(from, color, &KNIGHT_OFFSETS, &mut moves),
+                PieceKind::Bishop => self.slider_moves(from, color, &BISHOP_DIRECTIONS, &mut moves),
+                PieceKind::Rook => self.slider_moves(from, color, &ROOK_DIRECTIONS, &mut moves),
+                PieceKind::Queen => {
+                    self.slider_moves(from, color, &ROOK_DIRECTIONS, &mut moves);
+                    self.slider_moves(from, color, &BISHOP_DIRECTIONS, &mut moves);
+                }
+                PieceKind::King => {
+                    self.step_moves(from, color, &KING_OFFSETS, &mut moves);
+                    self.castle_moves(from, color, &mut moves);
+                }
+            }
+        }
+
+        if self.variant() == Variant::Crazyhouse {
+            self.drop_moves(&mut moves);
+        }
+
+        moves
+    }
+
+    /// is the square attacked by any piece of the given color
+    pub fn is_attacked(&self, square: Square, by: Color) -> bool {
+        let is_piece = |sq: Option<Square>, kind: PieceKind| {
+            sq.and_then(|sq| self.piece_at(sq)) == Some(Piece::new(by, kind))
+        };
+
+        // pawns attack diagonally forward, so look backwards from the target
+        let pawn_rank = -by.forward();
+        if is_piece(square.offset(-1, pawn_rank), PieceKind::Pawn)
+            || is_piece(square.offset(1, pawn_rank), PieceKind::Pawn)
+        {
+            return true;
+        }
+
+        if KNIGHT_OFFSETS
+            .iter()
+            .any(|(f, r)| is_piece(square.offset(*f, *r), PieceKind::Knight))
+        {
+            return true;
+        }
+
+        if KING_OFFSETS
+            .iter()
+            .any(|(f, r)| is_piece(square.offset(*f, *r), PieceKind::King))
+        {
+            return true;
+        }
+
+        let slider_attacks = |directions: &[(i8, i8)], kinds: [PieceKind; 2]| {
+            directions.iter().any(
+                |(f, r)| match self.first_piece_in_direction(square, *f, *r) {
+                    Some(piece) => piece.color == by && kinds.contains(&piece.kind),
+                    None => false,
+                },
+            )
+        };
+
+        slider_attacks(&ROOK_DIRECTIONS, [PieceKind::Rook, PieceKind::Queen])
+            || slider_attacks(&BISHOP_DIRECTIONS, [PieceKind::Bishop, PieceKind::Queen])
+    }
+
+    fn first_piece_in_direction(&self, from: Square, files: i8, ranks: i8) -> Option<Piece> {
+        let mut current = from;
+        while let Some(next) = current.offset(files, ranks) {
+            if let Some(piece) = self.piece_at(next) {
+                return Some(piece);
+            }
+            current = next;
+        }
+        None
+    }
+
+    fn pawn_moves(&self, from: Square, color: Color, moves: &mut Vec<Move>) {
+        let forward = color.forward();
+        let start_rank = if color == Color::White { 1 } else { 6 };
+        let last_rank = if color == Color::White { 7 } else { 0 };
+
+        let push = |to: Square, kind: MoveKind, moves: &mut Vec<Move>| {
+            if to.rank() == last_rank {
+                for promotion in PROMOTION_PIECES {
+                    moves.push(Move::promotion(from, to, promotion));
+                }
+            } else {
+                moves.push(Move::new(from, to, kind));
+            }
+        };
+
+        // single and double pushes
+        if let Some(one) = from.offset(0, forward) {
+            if self.piece_at(one).is_none() {
+                push(one, MoveKind::Normal, moves);
+
+                if from.rank() == start_rank {
+                    if let Some(two) = one.offset(0, forward) {
+                        if self.piece_at(two).is_none() {
+                            push(two, MoveKind::DoublePush, moves);
+                        }
+                    }
+                }
+            }
+        }
+
+        // captures, including en passant
+        for files in [-1, 1] {
+            let to = match from.offset(files, forward) {
+                Some(to) => to,
+                None => continue,
+            };
+
+            match self.piece_at(to) {
+                Some(target) if target.color != color => push(to, MoveKind::Normal, moves),
+                None if self.en_passant() == Some(to) => {
+                    moves.push(Move::new(from, to, MoveKind::EnPassant))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn step_moves(&self, from: Square, color: Color, offsets: &[(i8, i8)], moves: &mut Vec<Move>) {
+        for (files, ranks) in offsets {
+            if let Some(to) = from.offset(*files, *ranks) {
+                match self.piece_at(to) {
+                    Some(target) if target.color == color => {}
+                    _ => moves.push(Move::new(from, to, MoveKind::Normal)),
+                }
+            }
+        }
+    }
+
+    fn slider_moves(
+        &self,
+        from: Square,
+        color: Color,
+        directions: &[(i8, i8)],
+        moves: &mut Vec<Move>,
+    ) {
+        for (files, ranks) in directions {
+            let mut current = from;
+            while let Some(to) = current.offset(*files, *ranks) {
+                match self.piece_at(to) {
+                    Some(target) => {
+                        if target.color != color {
+                            moves.push(Move::new(from, to, MoveKind::Normal));
+                        }
+                        break;
+                    }
+                    None => moves.push(Move::new(from, to, MoveKind::Normal)),
+                }
+                current = to;
+            }
+        }
+    }
+
+    /// castling for both standard chess and Chess960, the king and rook
+    /// may start on any file and always end on the g and f files for
+    /// the king side or the c and d files for the queen side
+    fn castle_moves(&self, from: Square, color: Color, moves: &mut Vec<Move>) {
+        let rank = color.back_rank();
+        if from.rank() != rank || self.is_attacked(from, color.opposite()) {
+            return;
+        }
+
+        for side in [CastleSide::King, CastleSide::Queen] {
+            let rook_file = match self.castling().rook_file(color, side) {
+                Some(file) => file,
+                None => continue,
+            };
+            if self.piece_at(Square::new(rook_file, rank))
+                != Some(Piece::new(color, PieceKind::Rook))
+            {
+                continue;
+            }
+
+            let king_to = side.king_file();
+            let rook_to = side.rook_file();
+
+            // every square the king and rook cross or land on must
+            // be empty, apart from the castling king and rook
+            let files = [from.file(), rook_file, king_to, rook_to];
+            let lowest = *files.iter().min().unwrap_or(&0);
+            let highest = *files.iter().max().unwrap_or(&7);
+            let blocked = (lowest..=highest).any(|file| {
+                file != from.file()
+                    && file != rook_file
+                    && self.piece_at(Square::new(file, rank)).is_some()
+            });
+            if blocked {
+                continue;
+            }
+
+            // squares the king passes through must not be attacked
+            let mut passing = from.file().min(king_to)..=from.file().max(king_to);
+            if passing.any(|file| self.is_attacked(Square::new(file, rank), color.opposite())) {
+                continue;
+            }
+
+            moves.push(Move::new(
+                from,
+                Square::new(king_to, rank),
+                MoveKind::Castle,
+            ));
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use derive_more::Display;
+use std::fmt;
+
+use super::types::{PieceKind, Square};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoveKind {
+    Normal,
+    /// pawn advancing two squares, sets the en passant square
+    DoublePush,
+    EnPassant,
+    /// king move of a castle, the rook is moved along with it
+    Castle,
+    /// piece from the pocket placed on an empty square in Crazyhouse
+    Drop(PieceKind),
+}
+
+/// A single move, `to` is the destination square of the moving piece,
+/// for castling this is the square the king ends on and for drops
+/// `from` and `to` are both the square the piece is dropped on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PieceKind>,
+    pub kind: MoveKind,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square, kind: MoveKind) -> Self {
+        Self {
+            from,
+            to,
+            promotion: None,
+            kind,
+        }
+    }
+
+    pub fn drop(kind: PieceKind, to: Square) -> Self {
+        Self::new(to, to, MoveKind::Drop(kind))
+    }
+
+    pub fn promotion(from: Square, to: Square, promotion: PieceKind) -> Self {
+        Self {
+            from,
+            to,
+            promotion: Some(promotion),
+            kind: MoveKind::Normal,
+        }
+    }
+}
+
+/// Formats the move in coordinate notation, eg. `e2e4`, `e7e8q` or `N@f3`
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let MoveKind::Drop(kind) = self.kind {
+            return write!(f, "{}@{}", kind.to_char().to_ascii_uppercase(), self.to);
+        }
+
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.to_char())?;
+        }
+        Ok(())
+    }
+}
+
+/// Move written in coordinate notation before it has been
+/// resolved against a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveRequest {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PieceKind>,
+    /// moving piece, only given in long algebraic notation
+    pub piece: Option<PieceKind>,
+}
+
+impl MoveRequest {
+    /// parse UCI and long algebraic notation, `e2e4`, `e7e8q`,
+    /// `e2-e4`, `Ng1-f3`, `Bb5xc6` and `e7xd8=Q+` are accepted
+    pub fn parse(s: &str) -> Result<MoveRequest, MoveError> {
+        let invalid = || MoveError::InvalidNotation(s.to_string());
+
+        let mut text = s.trim().trim_end_matches(['+', '#']);
+        if !text.is_ascii() {
+            return Err(invalid());
+        }
+
+        let piece = match text.chars().next() {
+            Some(c) if "PNBRQK".contains(c) => {
+                text = &text[1..];
+                PieceKind::from_char(c)
+            }
+            _ => None,
+        };
+
+        let cleaned: String = text.chars().filter(|c| !"-x:=".contains(*c)).collect();
+        if cleaned.len() < 4 || cleaned.len() > 5 {
+            return Err(invalid());
+        }
+
+        let from = Square::parse(&cleaned[0..2]).ok_or_else(invalid)?;
+        let to = Square::parse(&cleaned[2..4]).ok_or_else(invalid)?;
+
+        let promotion = match cleaned[4..].chars().next() {
+            Some(c) => match PieceKind::from_char(c) {
+                Some(kind) if kind != PieceKind::Pawn && kind != PieceKind::King => Some(kind),
+                _ => return Err(invalid()),
+            },
+            None => None,
+        };
+
+        Ok(MoveRequest {
+            from,
+            to,
+            promotion,
+            piece,
+        })
+    }
+}
+
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    #[display(fmt = "Invalid move notation: {_0}")]
+    InvalidNotation(String),
+    #[display(fmt = "Illegal move: {_0}")]
+    IllegalMove(String),
+    #[display(fmt = "Ambiguous move: {_0}")]
+    AmbiguousMove(String),
+}
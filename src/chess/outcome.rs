@@ -0,0 +1,172 @@
+use derive_more::Display;
+use serde::Serialize;
+
+use super::position::Position;
+use super::types::{Color, PieceKind, Square};
+
+#[derive(Serialize, Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    #[display(fmt = "1-0")]
+    WhiteWins,
+    #[display(fmt = "0-1")]
+    BlackWins,
+    #[display(fmt = "1/2-1/2")]
+    Draw,
+}
+
+impl GameResult {
+    pub fn win_for(color: Color) -> Self {
+        match color {
+            Color::White => GameResult::WhiteWins,
+            Color::Black => GameResult::BlackWins,
+        }
+    }
+}
+
+#[derive(Serialize, Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEndReason {
+    Checkmate,
+    Stalemate,
+    #[display(fmt = "Threefold repetition")]
+    ThreefoldRepetition,
+    #[display(fmt = "Fivefold repetition")]
+    FivefoldRepetition,
+    #[display(fmt = "Fifty-move rule")]
+    FiftyMoveRule,
+    #[display(fmt = "Seventy-five-move rule")]
+    SeventyFiveMoveRule,
+    #[display(fmt = "Insufficient material")]
+    InsufficientMaterial,
+    #[display(fmt = "King reached the center")]
+    KingOfTheHill,
+    #[display(fmt = "Three checks")]
+    ThreeChecks,
+    #[display(fmt = "King exploded")]
+    KingExploded,
+    #[display(fmt = "Time forfeit")]
+    Timeout,
+    #[display(fmt = "Timeout vs insufficient material")]
+    TimeoutVsInsufficientMaterial,
+    Resignation,
+    #[display(fmt = "Draw by agreement")]
+    Agreement,
+}
+
+/// How and why a game finished
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameOutcome {
+    pub result: GameResult,
+    pub reason: GameEndReason,
+}
+
+impl GameOutcome {
+    pub fn draw(reason: GameEndReason) -> Self {
+        Self {
+            result: GameResult::Draw,
+            reason,
+        }
+    }
+
+    /// the side resigned, its opponent wins whatever the position
+    pub fn resignation(color: Color) -> Self {
+        Self {
+            result: GameResult::win_for(color.opposite()),
+            reason: GameEndReason::Resignation,
+        }
+    }
+
+    /// the side ran out of time in the position, a loss
+    /// unless its opponent has nothing left to mate with
+    pub fn timeout(position: &Position, color: Color) -> Self {
+        let winner = color.opposite();
+        if !position.has_mating_material(winner) {
+            return Self::draw(GameEndReason::TimeoutVsInsufficientMaterial);
+        }
+
+        Self {
+            result: GameResult::win_for(winner),
+            reason: GameEndReason::Timeout,
+        }
+    }
+}
+
+impl Position {
+    /// check if the game is over in this position, a variant win
+    /// condition is met or the side to move is mated or has no legal moves
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        if let Some(outcome) = self.variant_outcome() {
+            return Some(outcome);
+        }
+
+        if !self.legal_moves().is_empty() {
+            return None;
+        }
+
+        let color = self.side_to_move();
+        let outcome = if self.is_in_check(color) {
+            GameOutcome {
+                result: GameResult::win_for(color.opposite()),
+                reason: GameEndReason::Checkmate,
+            }
+        } else {
+            GameOutcome::draw(GameEndReason::Stalemate)
+        };
+
+        Some(outcome)
+    }
+
+    /// neither side can possibly checkmate, only kings remain or a
+    /// single minor piece, or bishops which are all on the same color,
+    /// never the case in variants which can be won by other means
+    pub fn has_insufficient_material(&self) -> bool {
+        if !self.variant().draws_on_insufficient_material() {
+            return false;
+        }
+
+        let mut bishop_colors = Vec::new();
+        let mut knights = 0;
+
+        for square in Square::all() {
+            let piece = match self.piece_at(square) {
+                Some(piece) => piece,
+                None => continue,
+            };
+
+            match piece.kind {
+                PieceKind::King => {}
+                PieceKind::Knight => knights += 1,
+                PieceKind::Bishop => bishop_colors.push((square.file() + square.rank()) % 2),
+                PieceKind::Pawn | PieceKind::Rook | PieceKind::Queen => return false,
+            }
+        }
+
+        match (knights, bishop_colors.len()) {
+            (0, 0) | (1, 0) | (0, 1) => true,
+            (0, _) => bishop_colors.iter().all(|c| *c == bishop_colors[0]),
+            _ => false,
+        }
+    }
+
+    /// the side could still checkmate, it has more than its king and
+    /// a single knight or bishop, so it wins when the opponent runs out
+    /// of time, always the case in variants with other win conditions
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        if !self.variant().draws_on_insufficient_material() {
+            return true;
+        }
+
+        let mut minor_pieces = 0;
+        for square in Square::all() {
+            match self.piece_at(square) {
+                Some(piece) if piece.color == color => match piece.kind {
+                    PieceKind::King => {}
+                    PieceKind::Knight | PieceKind::Bishop => minor_pieces += 1,
+                    PieceKind::Pawn | PieceKind::Rook | PieceKind::Queen => return true,
+                },
+                _ => {}
+            }
+        }
+
+        minor_pieces > 1
+    }
+}
@@ -0,0 +1,28 @@
+use super::moves::Move;
+use super::position::Position;
+
+impl Position {
+    /// number of leaf nodes of the legal move tree to the given depth,
+    /// compared against known counts to verify move generation
+    pub fn perft(&self, depth: u32) -> u64 {
+        match depth {
+            0 => 1,
+            // the moves themselves are the leaves, no need to make them
+            1 => self.legal_moves().len() as u64,
+            _ => self
+                .legal_moves()
+                .into_iter()
+                .map(|mv| self.make_move(mv).perft(depth - 1))
+                .sum(),
+        }
+    }
+
+    /// perft split by the first move, used to find which
+    /// move differs when a count does not match
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        self.legal_moves()
+            .into_iter()
+            .map(|mv| (mv, self.make_move(mv).perft(depth.saturating_sub(1))))
+            .collect()
+    }
+}
@@ -0,0 +1,585 @@
+use derive_more::Display;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::fen::FenError;
+use super::game::Game;
+use super::moves::{Move, MoveError};
+use super::position::Position;
+use super::types::Color;
+use super::variant::Variant;
+
+/// Maximum line length of exported movetext
+const PGN_LINE_LENGTH: usize = 80;
+
+/// Deepest nesting of variations an imported game may have
+const MAX_VARIATION_DEPTH: usize = 32;
+
+/// Tag pairs written at the top of an exported game,
+/// the Seven Tag Roster plus the time control, the
+/// opening tags are taken from the game itself
+#[derive(Debug, Clone)]
+pub struct PgnHeaders {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub white: String,
+    pub black: String,
+    pub result: String,
+    pub time_control: String,
+}
+
+/// Glyphs and a comment written after a move of an exported game
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PgnAnnotation {
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+}
+
+impl Game {
+    /// write the game in Portable Game Notation, the `SetUp` and
+    /// `FEN` tags are added when the game did not start from
+    /// the standard starting position, the `Variant` tag for games
+    /// which are not standard chess, and the `ECO` and `Opening`
+    /// tags once the game reached a named opening
+    pub fn to_pgn(&self, headers: &PgnHeaders) -> String {
+        self.to_annotated_pgn(headers, &[])
+    }
+
+    /// write the game in PGN with the annotation of each
+    /// move, `annotations[i]` follows the move of ply `i`
+    pub fn to_annotated_pgn(&self, headers: &PgnHeaders, annotations: &[PgnAnnotation]) -> String {
+        let mut tags = vec![
+            ("Event", headers.event.clone()),
+            ("Site", headers.site.clone()),
+            ("Date", headers.date.clone()),
+            ("Round", "-".to_string()),
+            ("White", headers.white.clone()),
+            ("Black", headers.black.clone()),
+            ("Result", headers.result.clone()),
+            ("TimeControl", headers.time_control.clone()),
+        ];
+
+        if let Some(opening) = self.opening() {
+            tags.push(("ECO", opening.eco.clone()));
+            tags.push(("Opening", opening.name.clone()));
+        }
+
+        let variant = self.variant();
+        if variant != Variant::Standard {
+            tags.push(("Variant", variant.pgn_name().to_string()));
+        }
+
+        let start = *self.start();
+        if start.with_variant(Variant::Standard) != Position::starting() {
+            tags.push(("SetUp", "1".to_string()));
+            tags.push(("FEN", start.to_fen()));
+        }
+
+        let mut pgn = String::new();
+        for (name, value) in tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+        }
+        pgn.push('\n');
+
+        // movetext tokens, move numbers, SAN moves and annotations,
+        // comments are split into words so long ones can wrap
+        let mut tokens = Vec::new();
+        let mut position = start;
+        let mut commented = false;
+        for (i, mv) in self.moves().iter().enumerate() {
+            let number = position.fullmove_number();
+            match position.side_to_move() {
+                Color::White => tokens.push(format!("{number}.")),
+                // black's move number is repeated after a comment
+                Color::Black if i == 0 || commented => tokens.push(format!("{number}...")),
+                Color::Black => {}
+            }
+            tokens.push(position.san(*mv));
+            position = position.make_move(*mv);
+
+            let annotation = annotations.get(i).cloned().unwrap_or_default();
+            tokens.extend(annotation.nags.iter().map(|nag| format!("${nag}")));
+            commented = annotation.comment.is_some();
+            if let Some(comment) = annotation.comment {
+                tokens.push("{".to_string());
+                tokens.extend(
+                    comment
+                        .replace('}', "")
+                        .split_whitespace()
+                        .map(str::to_string),
+                );
+                tokens.push("}".to_string());
+            }
+        }
+        tokens.push(headers.result.clone());
+
+        pgn.push_str(&wrap_tokens(&tokens, PGN_LINE_LENGTH));
+        pgn.push('\n');
+
+        pgn
+    }
+}
+
+/// join tokens with spaces, breaking lines before they exceed the max length
+fn wrap_tokens(tokens: &[String], max_length: usize) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > max_length {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        text.push_str(token);
+        line_length += token.len();
+    }
+
+    text
+}
+
+/// format a time as a PGN date tag value, `YYYY.MM.DD`
+pub fn pgn_date(time: SystemTime) -> String {
+    let days = match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => (elapsed.as_secs() / 86_400) as i64,
+        Err(_) => return "????.??.??".to_string(),
+    };
+
+    // convert days since the unix epoch to a civil date
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    #[display(fmt = "No games found in PGN")]
+    NoGames,
+    #[display(fmt = "Invalid tag pair in PGN: {_0}")]
+    InvalidTag(String),
+    #[display(fmt = "{_0}")]
+    InvalidFen(FenError),
+    #[display(fmt = "{_0} in PGN movetext")]
+    InvalidMove(MoveError),
+    #[display(fmt = "Unterminated comment in PGN")]
+    UnterminatedComment,
+    #[display(fmt = "Unbalanced variation in PGN")]
+    UnbalancedVariation,
+    #[display(fmt = "Variations nested deeper than {_0} levels in PGN")]
+    VariationTooDeep(usize),
+    #[display(fmt = "Unexpected token in PGN: {_0}")]
+    UnexpectedToken(String),
+    #[display(fmt = "Invalid PGN start point: {_0}")]
+    InvalidStartPoint(String),
+    #[display(fmt = "The game is already decided at PGN start point {_0}")]
+    DecidedStartPoint(String),
+    #[display(fmt = "Unsupported variant in PGN: {_0}")]
+    UnsupportedVariant(String),
+}
+
+/// A move of an imported game with its annotations, variations are
+/// alternatives to this move played from the position before it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnMove {
+    pub mv: Move,
+    pub san: String,
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+/// A single game parsed from PGN
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub start: Position,
+    pub moves: Vec<PgnMove>,
+    pub result: String,
+}
+
+impl PgnGame {
+    /// number of half moves played before the given move number and
+    /// side to move, `None` if the main line does not reach that point
+    pub fn ply_at(&self, move_number: u32, color: Color) -> Option<usize> {
+        let start_ply = self
+            .start
+            .fullmove_number()
+            .checked_mul(2)?
+            .checked_add(self.start.side_to_move() as u32)?;
+        let ply = move_number
+            .checked_mul(2)?
+            .checked_add(color as u32)?
+            .checked_sub(start_ply)? as usize;
+
+        if ply <= self.moves.len() {
+            Some(ply)
+        } else {
+            None
+        }
+    }
+
+    /// the main line of the game replayed up to the given number of half moves
+    pub fn to_game(&self, plies: usize) -> Game {
+        let mut game = Game::new(self.start);
+        for pgn_move in self.moves.iter().take(plies) {
+            game.push(pgn_move.mv);
+        }
+        game
+    }
+}
+
+/// Replay an imported game up to a start point so play can continue
+/// from there, the start point is written `[game:]move`, eg.
+/// `23` for white to play move 23, `23...` for black to play move 23,
+/// `2:end` for the final position of the second game in the file,
+/// a start point where the game is already over is not valid
+pub fn game_from_pgn(start_point: &str, text: &str) -> Result<Game, PgnError> {
+    let invalid = || PgnError::InvalidStartPoint(start_point.to_string());
+    let games = parse_pgn(text)?;
+
+    let (game_number, point) = match start_point.split_once(':') {
+        Some((number, point)) => (number.parse::<usize>().map_err(|_| invalid())?, point),
+        None => (1, start_point),
+    };
+    let pgn_game = game_number
+        .checked_sub(1)
+        .and_then(|index| games.get(index))
+        .ok_or_else(invalid)?;
+
+    let plies = if point == "end" {
+        pgn_game.moves.len()
+    } else {
+        let (number, color) = match point.strip_suffix("...") {
+            Some(number) => (number, Color::Black),
+            None => (point.trim_end_matches('.'), Color::White),
+        };
+        let number = number.parse::<u32>().map_err(|_| invalid())?;
+        pgn_game.ply_at(number, color).ok_or_else(invalid)?
+    };
+
+    // the position ended the game, or the players did at the last move
+    let game = pgn_game.to_game(plies);
+    let decided =
+        game.outcome().is_some() || (plies == pgn_game.moves.len() && pgn_game.result != "*");
+    if decided {
+        return Err(PgnError::DecidedStartPoint(start_point.to_string()));
+    }
+
+    Ok(game)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    MoveNumber,
+    Result(String),
+    San(String),
+}
+
+/// parse every game in a PGN file, moves of the main line and variations
+/// are validated against the position they are played in
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let tokens = tokenize(text)?;
+    let mut games = Vec::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let mut tags = Vec::new();
+        while let Some(Token::Tag(name, value)) = tokens.get(index) {
+            tags.push((name.clone(), value.clone()));
+            index += 1;
+        }
+
+        let variant = match tags.iter().find(|(name, _)| name == "Variant") {
+            Some((_, name)) => {
+                Variant::parse(name).ok_or_else(|| PgnError::UnsupportedVariant(name.clone()))?
+            }
+            None => Variant::Standard,
+        };
+
+        let start = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => Position::from_fen(fen).map_err(PgnError::InvalidFen)?,
+            None => Position::starting(),
+        };
+        let start = match variant {
+            Variant::Standard => start,
+            variant => start.with_variant(variant),
+        };
+
+        let moves = parse_line(&tokens, &mut index, start, 0)?;
+
+        let result = match tokens.get(index) {
+            Some(Token::Result(result)) => {
+                index += 1;
+                result.clone()
+            }
+            _ => "*".to_string(),
+        };
+
+        if tags.is_empty() && moves.is_empty() && result == "*" {
+            // nothing consumed, avoid looping forever on a stray token
+            if let Some(token) = tokens.get(index) {
+                return Err(PgnError::UnexpectedToken(format!("{token:?}")));
+            }
+            break;
+        }
+
+        games.push(PgnGame {
+            tags,
+            start,
+            moves,
+            result,
+        });
+    }
+
+    if games.is_empty() {
+        return Err(PgnError::NoGames);
+    }
+
+    Ok(games)
+}
+
+/// parse a line of moves, the main line at depth 0 or a variation,
+/// stops at the end of the variation, a result or the next game
+fn parse_line(
+    tokens: &[Token],
+    index: &mut usize,
+    start: Position,
+    depth: usize,
+) -> Result<Vec<PgnMove>, PgnError> {
+    let mut moves: Vec<PgnMove> = Vec::new();
+    let mut position = start;
+    let mut previous = start;
+
+    while let Some(token) = tokens.get(*index) {
+        match token {
+            Token::San(san) => {
+                let mv = position.parse_san(san).map_err(PgnError::InvalidMove)?;
+                previous = position;
+                position = position.make_move(mv);
+                moves.push(PgnMove {
+                    mv,
+                    san: san.clone(),
+                    nags: Vec::new(),
+                    comment: None,
+                    variations: Vec::new(),
+                });
+            }
+            Token::Nag(nag) => {
+                if let Some(last) = moves.last_mut() {
+                    last.nags.push(*nag);
+                }
+            }
+            Token::Comment(comment) => {
+                // comments before the first move have nothing to attach to
+                if let Some(last) = moves.last_mut() {
+                    last.comment = Some(match &last.comment {
+                        Some(existing) => format!("{existing} {comment}"),
+                        None => comment.clone(),
+                    });
+                }
+            }
+            Token::VariationStart => {
+                // a variation is an alternative to the last move,
+                // there has to be one before it is parsed
+                if moves.is_empty() {
+                    return Err(PgnError::UnbalancedVariation);
+                }
+                if depth >= MAX_VARIATION_DEPTH {
+                    return Err(PgnError::VariationTooDeep(MAX_VARIATION_DEPTH));
+                }
+                *index += 1;
+                let variation = parse_line(tokens, index, previous, depth + 1)?;
+                if let Some(last) = moves.last_mut() {
+                    last.variations.push(variation);
+                }
+            }
+            Token::VariationEnd => {
+                if depth == 0 {
+                    return Err(PgnError::UnbalancedVariation);
+                }
+                return Ok(moves);
+            }
+            Token::MoveNumber => {}
+            Token::Result(_) | Token::Tag(_, _) => {
+                if depth > 0 {
+                    return Err(PgnError::UnbalancedVariation);
+                }
+                return Ok(moves);
+            }
+        }
+        *index += 1;
+    }
+
+    if depth > 0 {
+        return Err(PgnError::UnbalancedVariation);
+    }
+    Ok(moves)
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            // escape mechanism, the rest of the line is ignored
+            '%' if line_start => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '[' => {
+                let mut tag = String::new();
+                let mut in_quotes = false;
+                let mut escaped = false;
+                loop {
+                    let c = chars
+                        .next()
+                        .ok_or_else(|| PgnError::InvalidTag(tag.clone()))?;
+                    if c == ']' && !in_quotes {
+                        break;
+                    }
+                    if c == '"' && !escaped {
+                        in_quotes = !in_quotes;
+                    }
+                    escaped = c == '\\' && !escaped;
+                    tag.push(c);
+                }
+                tokens.push(parse_tag(&tag)?);
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(PgnError::UnterminatedComment),
+                    }
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            ';' => {
+                let mut comment = String::new();
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                    comment.push(c);
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            '$' => {
+                let mut digits = String::new();
+                while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    digits.push(*c);
+                    chars.next();
+                }
+                let nag = digits
+                    .parse::<u8>()
+                    .map_err(|_| PgnError::UnexpectedToken(format!("${digits}")))?;
+                tokens.push(Token::Nag(nag));
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some(c) = chars
+                    .peek()
+                    .filter(|c| !c.is_whitespace() && !"{}()[];$".contains(**c))
+                {
+                    word.push(*c);
+                    chars.next();
+                }
+                push_word(&word, &mut tokens);
+            }
+        }
+        line_start = false;
+    }
+
+    Ok(tokens)
+}
+
+/// split a movetext word into move number, SAN and annotation tokens,
+/// eg. `12.Nf3!?` is a move number, a move and the NAG 5
+fn push_word(word: &str, tokens: &mut Vec<Token>) {
+    if matches!(word, "1-0" | "0-1" | "1/2-1/2" | "*") {
+        tokens.push(Token::Result(word.to_string()));
+        return;
+    }
+
+    let mut san = word;
+    if !san.starts_with("0-0") {
+        let number_end = san
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(san.len());
+        if number_end > 0 {
+            tokens.push(Token::MoveNumber);
+            san = &san[number_end..];
+        }
+    }
+
+    let annotation_start = san.find(['!', '?']).unwrap_or(san.len());
+    let (san, annotation) = san.split_at(annotation_start);
+
+    if !san.is_empty() {
+        tokens.push(Token::San(san.to_string()));
+    }
+
+    let nag = match annotation {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    };
+    if let Some(nag) = nag {
+        tokens.push(Token::Nag(nag));
+    }
+}
+
+/// parse the inside of a tag pair, `Name "value"`
+fn parse_tag(tag: &str) -> Result<Token, PgnError> {
+    let invalid = || PgnError::InvalidTag(tag.to_string());
+
+    let (name, value) = tag
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+
+    let value = value.replace("\\\"", "\"").replace("\\\\", "\\");
+
+    Ok(Token::Tag(name.to_string(), value))
+}
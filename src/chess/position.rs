@@ -0,0 +1,379 @@
+use super::crazyhouse::Pockets;
+use super::moves::{Move, MoveError, MoveKind, MoveRequest};
+use super::types::{Color, Piece, PieceKind, Square};
+use super::variant::Variant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CastleSide {
+    King,
+    Queen,
+}
+
+impl CastleSide {
+    /// side of a castling move, from the file the king ends on
+    pub fn of(mv: Move) -> CastleSide {
+        if mv.to.file() == CastleSide::King.king_file() {
+            CastleSide::King
+        } else {
+            CastleSide::Queen
+        }
+    }
+
+    /// file the king ends on after castling
+    pub fn king_file(self) -> u8 {
+        match self {
+            CastleSide::King => 6,
+            CastleSide::Queen => 2,
+        }
+    }
+
+    /// file the rook ends on after castling
+    pub fn rook_file(self) -> u8 {
+        match self {
+            CastleSide::King => 5,
+            CastleSide::Queen => 3,
+        }
+    }
+}
+
+/// Remaining castling rights for both colors, stored as the file
+/// of the rook each side may castle with so that Chess960
+/// starting positions are supported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CastlingRights {
+    rooks: [[Option<u8>; 2]; 2],
+}
+
+impl CastlingRights {
+    /// rights of the standard starting position, rooks on the a and h files
+    pub fn standard() -> Self {
+        Self {
+            rooks: [[Some(7), Some(0)]; 2],
+        }
+    }
+
+    pub fn has(&self, color: Color, side: CastleSide) -> bool {
+        self.rook_file(color, side).is_some()
+    }
+
+    pub fn rook_file(&self, color: Color, side: CastleSide) -> Option<u8> {
+        self.rooks[color as usize][side as usize]
+    }
+
+    pub fn set(&mut self, color: Color, side: CastleSide, rook_file: Option<u8>) {
+        self.rooks[color as usize][side as usize] = rook_file;
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.rooks[color as usize] = [None; 2];
+    }
+}
+
+/// Full board state, everything required to
+/// generate and validate the moves of the side to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    board: [Option<Piece>; 64],
+    pub(super) side_to_move: Color,
+    pub(super) castling: CastlingRights,
+    pub(super) en_passant: Option<Square>,
+    pub(super) halfmove_clock: u32,
+    pub(super) fullmove_number: u32,
+    /// rules the position is played under
+    pub(super) variant: Variant,
+    /// checks given by each side, counted in Three-check
+    pub(super) checks: [u8; 2],
+    /// captured pieces each side may drop in Crazyhouse
+    pub(super) pockets: Pockets,
+    /// squares holding promoted pieces, which go back
+    /// to the pocket as pawns when captured in Crazyhouse
+    pub(super) promoted: u64,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::starting()
+    }
+}
+
+impl Position {
+    /// standard chess starting position, white to move
+    pub fn starting() -> Self {
+        use PieceKind::*;
+        let back_rank = [Rook, Knight, Bishop, Queen, King, Bishop, Knight, Rook];
+
+        let mut position = Self::empty();
+        for (file, kind) in back_rank.into_iter().enumerate() {
+            let file = file as u8;
+            position.set_piece(Square::new(file, 0), Some(Piece::new(Color::White, kind)));
+            position.set_piece(Square::new(file, 1), Some(Piece::new(Color::White, Pawn)));
+            position.set_piece(Square::new(file, 6), Some(Piece::new(Color::Black, Pawn)));
+            position.set_piece(Square::new(file, 7), Some(Piece::new(Color::Black, kind)));
+        }
+        position.castling = CastlingRights::standard();
+
+        position
+    }
+
+    /// board with no pieces and no castling rights, white to move
+    pub fn empty() -> Self {
+        Self {
+            board: [None; 64],
+            side_to_move: Color::White,
+            castling: CastlingRights::default(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            variant: Variant::Standard,
+            checks: [0; 2],
+            pockets: Pockets::default(),
+            promoted: 0,
+        }
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.board[square.index()]
+    }
+
+    pub fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        self.board[square.index()] = piece;
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    pub fn castling(&self) -> CastlingRights {
+        self.castling
+    }
+
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Chess960 castling moves are written as the king capturing its own rook
+    pub fn is_chess960(&self) -> bool {
+        self.variant == Variant::Chess960
+    }
+
+    /// play the position under the rules of the given variant
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// checks given by a side, counted in Three-check
+    pub fn checks(&self, color: Color) -> u8 {
+        self.checks[color as usize]
+    }
+
+    pub fn pockets(&self) -> Pockets {
+        self.pockets
+    }
+
+    /// square of the rook a castling move is made with
+    pub fn castling_rook(&self, mv: Move) -> Option<Square> {
+        let color = self.side_to_move;
+        self.castling
+            .rook_file(color, CastleSide::of(mv))
+            .map(|file| Square::new(file, color.back_rank()))
+    }
+
+    /// write a move in UCI notation, Chess960 castling
+    /// is written as the king capturing its own rook
+    pub fn uci(&self, mv: Move) -> String {
+        match self.castling_rook(mv) {
+            Some(rook) if self.is_chess960() && mv.kind == MoveKind::Castle => {
+                format!("{}{}", mv.from, rook)
+            }
+            _ => mv.to_string(),
+        }
+    }
+
+    /// number of half moves since the last capture or pawn move
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        Square::all().find(|sq| self.piece_at(*sq) == Some(Piece::new(color, PieceKind::King)))
+    }
+
+    pub fn is_in_check(&self, color: Color) -> bool {
+        let king = match self.king_square(color) {
+            Some(king) => king,
+            None => return false,
+        };
+
+        // touching kings can not give check in Atomic,
+        // capturing one would explode the other as well
+        if self.variant == Variant::Atomic && self.kings_touching() {
+            return false;
+        }
+
+        self.is_attacked(king, color.opposite())
+    }
+
+    /// all legal moves for the side to move, there are none
+    /// once the game is won by a variant's own win condition
+    pub fn legal_moves(&self) -> Vec<Move> {
+        if self.variant_outcome().is_some() {
+            return Vec::new();
+        }
+
+        self.pseudo_legal_moves()
+            .into_iter()
+            .filter(|mv| self.is_legal(*mv))
+            .collect()
+    }
+
+    fn is_legal(&self, mv: Move) -> bool {
+        match self.variant {
+            Variant::Atomic => self.is_legal_atomic(mv),
+            _ => !self.make_move(mv).is_in_check(self.side_to_move),
+        }
+    }
+
+    /// find the legal move matching the given coordinates
+    pub fn find_move(&self, request: MoveRequest) -> Option<Move> {
+        self.legal_moves().into_iter().find(|mv| {
+            // castling may also be written as the king capturing its own rook
+            let castle_by_rook =
+                mv.kind == MoveKind::Castle && self.castling_rook(*mv) == Some(request.to);
+
+            !matches!(mv.kind, MoveKind::Drop(_))
+                && mv.from == request.from
+                && (mv.to == request.to || castle_by_rook)
+                && mv.promotion == request.promotion
+                && request
+                    .piece
+                    .is_none_or(|kind| self.piece_at(mv.from).map(|p| p.kind) == Some(kind))
+        })
+    }
+
+    /// resolve a move written in UCI, long algebraic or
+    /// standard algebraic notation against the legal moves
+    pub fn parse_move(&self, notation: &str) -> Result<Move, MoveError> {
+        match MoveRequest::parse(notation) {
+            Ok(request) => self
+                .find_move(request)
+                .ok_or_else(|| MoveError::IllegalMove(notation.to_string())),
+            Err(_) => self.parse_san(notation),
+        }
+    }
+
+    /// parse and validate a move in any supported notation,
+    /// the move is applied to the position if it is legal
+    pub fn play(&mut self, notation: &str) -> Result<Move, MoveError> {
+        let mv = self.parse_move(notation)?;
+
+        *self = self.make_move(mv);
+        Ok(mv)
+    }
+
+    /// apply a move without checking if it is legal,
+    /// returns the resulting position
+    pub fn make_move(&self, mv: Move) -> Position {
+        let mut next = *self;
+        let color = self.side_to_move;
+
+        let piece = match (mv.kind, self.piece_at(mv.from)) {
+            (MoveKind::Drop(kind), _) => Piece::new(color, kind),
+            (_, Some(piece)) => piece,
+            (_, None) => return next,
+        };
+        let captured_on = match mv.kind {
+            MoveKind::EnPassant => Some(Square::new(mv.to.file(), mv.from.rank())),
+            MoveKind::Castle | MoveKind::Drop(_) => None,
+            MoveKind::Normal | MoveKind::DoublePush => Some(mv.to),
+        };
+        let captured = captured_on.and_then(|square| self.piece_at(square));
+
+        if self.variant == Variant::Crazyhouse {
+            next.update_pockets(mv, captured_on, captured);
+        }
+
+        next.set_piece(mv.from, None);
+
+        match mv.kind {
+            MoveKind::EnPassant => {
+                next.set_piece(Square::new(mv.to.file(), mv.from.rank()), None);
+            }
+            MoveKind::Drop(kind) => {
+                next.pockets.remove(color, kind);
+            }
+            MoveKind::Castle => {
+                // in Chess960 the rook may stand on the king's destination,
+                // so the rook is lifted before the king is placed
+                if let Some(rook_from) = self.castling_rook(mv) {
+                    next.set_piece(rook_from, None);
+                }
+                let rook_to = Square::new(CastleSide::of(mv).rook_file(), color.back_rank());
+                next.set_piece(rook_to, Some(Piece::new(color, PieceKind::Rook)));
+            }
+            MoveKind::Normal | MoveKind::DoublePush => {}
+        }
+
+        next.set_piece(
+            mv.to,
+            Some(Piece::new(color, mv.promotion.unwrap_or(piece.kind))),
+        );
+
+        // castling rights are lost when the king moves or
+        // a rook moves from, or is captured on, its home square
+        if piece.kind == PieceKind::King {
+            next.castling.clear(color);
+        }
+        for square in [mv.from, mv.to] {
+            next.remove_castling_rook(square);
+        }
+
+        if self.variant == Variant::Atomic && captured.is_some() {
+            next.explode(mv.to);
+        }
+
+        next.en_passant = match mv.kind {
+            MoveKind::DoublePush => mv.from.offset(0, color.forward()),
+            _ => None,
+        };
+
+        if piece.kind == PieceKind::Pawn || captured.is_some() {
+            next.halfmove_clock = 0;
+        } else {
+            next.halfmove_clock = next.halfmove_clock.saturating_add(1);
+        }
+
+        if color == Color::Black {
+            next.fullmove_number = next.fullmove_number.saturating_add(1);
+        }
+        next.side_to_move = color.opposite();
+
+        if self.variant == Variant::ThreeCheck && next.is_in_check(color.opposite()) {
+            next.checks[color as usize] = next.checks[color as usize].saturating_add(1);
+        }
+
+        next
+    }
+
+    /// drop the castling right of a rook whose home square
+    /// was moved from, captured on or exploded
+    pub(super) fn remove_castling_rook(&mut self, square: Square) {
+        for color in [Color::White, Color::Black] {
+            for side in [CastleSide::King, CastleSide::Queen] {
+                let rook_file = self.castling.rook_file(color, side);
+                let rook_home = rook_file.map(|file| Square::new(file, color.back_rank()));
+                if rook_home == Some(square) {
+                    self.castling.set(color, side, None);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use super::moves::{Move, MoveError, MoveKind};
+use super::position::{CastleSide, Position};
+use super::types::{PieceKind, Square};
+
+impl Position {
+    /// resolve a move in Standard Algebraic Notation against the legal
+    /// moves of the position, check and annotation suffixes are ignored
+    pub fn parse_san(&self, san: &str) -> Result<Move, MoveError> {
+        let invalid = || MoveError::InvalidNotation(san.to_string());
+        let text = san.trim_end_matches(['+', '#', '!', '?']);
+
+        if matches!(text, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+            let side = match text.len() {
+                3 => CastleSide::King,
+                _ => CastleSide::Queen,
+            };
+            return self
+                .legal_moves()
+                .into_iter()
+                .find(|mv| mv.kind == MoveKind::Castle && CastleSide::of(*mv) == side)
+                .ok_or_else(|| MoveError::IllegalMove(san.to_string()));
+        }
+
+        if !text.is_ascii() || text.len() < 2 {
+            return Err(invalid());
+        }
+
+        // Crazyhouse drops, `N@f3`, `P@e4` or `@e4` for a pawn
+        if let Some((piece, square)) = text.split_once('@') {
+            let kind = match piece {
+                "" => PieceKind::Pawn,
+                piece if piece.len() == 1 => {
+                    PieceKind::from_char(piece.chars().next().unwrap_or('?')).ok_or_else(invalid)?
+                }
+                _ => return Err(invalid()),
+            };
+            let to = Square::parse(square).ok_or_else(invalid)?;
+
+            return self
+                .legal_moves()
+                .into_iter()
+                .find(|mv| mv.kind == MoveKind::Drop(kind) && mv.to == to)
+                .ok_or_else(|| MoveError::IllegalMove(san.to_string()));
+        }
+
+        // promotion piece at the end, `e8=Q` or `e8Q`
+        let mut rest = text;
+        let mut promotion = None;
+        if let Some(last) = rest.chars().last().filter(|c| c.is_ascii_uppercase()) {
+            promotion = Some(PieceKind::from_char(last).ok_or_else(invalid)?);
+            rest = rest[..rest.len() - 1].trim_end_matches('=');
+        }
+
+        if rest.len() < 2 {
+            return Err(invalid());
+        }
+        let to = Square::parse(&rest[rest.len() - 2..]).ok_or_else(invalid)?;
+        rest = &rest[..rest.len() - 2];
+
+        // piece letter, pawn moves have none
+        let kind = match rest.chars().next() {
+            Some(c) if c.is_ascii_uppercase() => {
+                rest = &rest[1..];
+                PieceKind::from_char(c).ok_or_else(invalid)?
+            }
+            _ => PieceKind::Pawn,
+        };
+
+        // disambiguation file and or rank of the moving piece
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in rest.chars().filter(|c| *c != 'x' && *c != ':') {
+            match c {
+                'a'..='h' => from_file = Some(c as u8 - b'a'),
+                '1'..='8' => from_rank = Some(c as u8 - b'1'),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let candidates: Vec<Move> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| {
+                mv.to == to
+                    && matches!(
+                        mv.kind,
+                        MoveKind::Normal | MoveKind::DoublePush | MoveKind::EnPassant
+                    )
+                    && mv.promotion == promotion
+                    && self.piece_at(mv.from).map(|p| p.kind) == Some(kind)
+                    && from_file.is_none_or(|file| mv.from.file() == file)
+                    && from_rank.is_none_or(|rank| mv.from.rank() == rank)
+            })
+            .collect();
+
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(MoveError::IllegalMove(san.to_string())),
+            _ => Err(MoveError::AmbiguousMove(san.to_string())),
+        }
+    }
+
+    /// write a legal move in Standard Algebraic Notation, eg. `Nbd7`,
+    /// `exd6`, `e8=Q+`, `O-O-O#` or `N@f3`
+    pub fn san(&self, mv: Move) -> String {
+        let mut san = match mv.kind {
+            MoveKind::Drop(_) => mv.to_string(),
+            MoveKind::Castle => match CastleSide::of(mv) {
+                CastleSide::King => "O-O".to_string(),
+                CastleSide::Queen => "O-O-O".to_string(),
+            },
+            _ => self.san_without_suffix(mv),
+        };
+
+        let next = self.make_move(mv);
+        if next.is_in_check(next.side_to_move()) {
+            if next.legal_moves().is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+
+        san
+    }
+
+    fn san_without_suffix(&self, mv: Move) -> String {
+        let kind = match self.piece_at(mv.from) {
+            Some(piece) => piece.kind,
+            None => return mv.to_string(),
+        };
+        let is_capture = self.piece_at(mv.to).is_some() || mv.kind == MoveKind::EnPassant;
+
+        let mut san = String::new();
+
+        if kind == PieceKind::Pawn {
+            if is_capture {
+                san.push(file_char(mv.from));
+                san.push('x');
+            }
+            san.push_str(&mv.to.to_string());
+
+            if let Some(promotion) = mv.promotion {
+                san.push('=');
+                san.push(promotion.to_char().to_ascii_uppercase());
+            }
+
+            return san;
+        }
+
+        san.push(kind.to_char().to_ascii_uppercase());
+
+        // other pieces of the same kind which can also reach the square
+        let others: Vec<Square> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == mv.to
+                    && other.from != mv.from
+                    && self.piece_at(other.from).map(|p| p.kind) == Some(kind)
+            })
+            .map(|other| other.from)
+            .collect();
+
+        if !others.is_empty() {
+            let same_file = others.iter().any(|sq| sq.file() == mv.from.file());
+            let same_rank = others.iter().any(|sq| sq.rank() == mv.from.rank());
+
+            if !same_file {
+                san.push(file_char(mv.from));
+            } else if !same_rank {
+                san.push(rank_char(mv.from));
+            } else {
+                san.push_str(&mv.from.to_string());
+            }
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_string());
+
+        san
+    }
+}
+
+fn file_char(square: Square) -> char {
+    (b'a' + square.file()) as char
+}
+
+fn rank_char(square: Square) -> char {
+    (b'1' + square.rank()) as char
+}
@@ -0,0 +1,394 @@
+use std::time::{Duration, Instant};
+
+use super::eval::PIECE_VALUES;
+use super::game::Game;
+use super::moves::{Move, MoveKind};
+use super::position::Position;
+use super::types::PieceKind;
+
+/// Score of a mate at the root, mates further away score lower
+pub const MATE_SCORE: i32 = 100_000;
+
+/// Scores above this are mates
+pub(crate) const MATE_THRESHOLD: i32 = MATE_SCORE - 1_000;
+
+const INFINITY: i32 = MATE_SCORE + 1;
+
+/// Number of transposition table entries, a power of two
+const TT_SIZE: usize = 1 << 18;
+
+/// Nodes searched between checks of the time budget
+const TIME_CHECK_NODES: u64 = 2048;
+
+/// Half moves without a capture or pawn move after which the game is drawn
+const FIFTY_MOVE_PLIES: u32 = 100;
+
+/// How long and how well the search plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// deepest iteration searched
+    pub depth: u32,
+    /// time after which the search stops, the move of the
+    /// last completed iteration is played
+    pub time: Duration,
+    /// noise in centipawns added to evaluations,
+    /// weaker play with more variety
+    pub randomness: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    /// score in centipawns from the side to move's point of view
+    pub score: i32,
+    /// depth of the last completed iteration
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TtEntry {
+    hash: u64,
+    depth: u32,
+    score: i32,
+    bound: Bound,
+    best_move: Option<Move>,
+}
+
+/// Alpha-beta search with iterative deepening, quiescence search
+/// and a transposition table keyed by the Zobrist hash
+pub struct Search {
+    limits: SearchLimits,
+    tt: Vec<Option<TtEntry>>,
+    /// hashes of the positions played in the game and on the
+    /// current search path, used to score repetitions as draws
+    history: Vec<u64>,
+    nodes: u64,
+    deadline: Instant,
+    /// the first iteration always completes so there is a move to play
+    can_stop: bool,
+    stopped: bool,
+    root_best_move: Option<Move>,
+    seed: u64,
+}
+
+impl Search {
+    pub fn new(limits: SearchLimits) -> Self {
+        Self {
+            limits,
+            tt: vec![None; TT_SIZE],
+            history: Vec::new(),
+            nodes: 0,
+            deadline: Instant::now() + limits.time,
+            can_stop: false,
+            stopped: false,
+            root_best_move: None,
+            seed: rand::random(),
+        }
+    }
+
+    /// search the current position of the game, `on_iteration` is
+    /// called with the result of every completed iteration
+    pub fn run(
+        &mut self,
+        game: &Game,
+        mut on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let position = *game.position();
+        self.history = game.hashes().to_vec();
+        self.history.pop();
+        self.nodes = 0;
+        self.stopped = false;
+        self.deadline = Instant::now() + self.limits.time;
+
+        let mut result = SearchResult {
+            best_move: position.legal_moves().first().copied(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+        };
+
+        for depth in 1..=self.limits.depth.max(1) {
+            self.can_stop = depth > 1;
+            self.root_best_move = None;
+            let score = self.negamax(&position, depth, 0, -INFINITY, INFINITY);
+
+            // the result of an unfinished iteration is not used
+            if self.stopped {
+                break;
+            }
+
+            result = SearchResult {
+                best_move: self.root_best_move.or(result.best_move),
+                score,
+                depth,
+                nodes: self.nodes,
+            };
+            on_iteration(&result);
+
+            if score.abs() >= MATE_THRESHOLD {
+                break;
+            }
+        }
+
+        result.nodes = self.nodes;
+        result
+    }
+
+    fn negamax(
+        &mut self,
+        position: &Position,
+        depth: u32,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if self.count_node() {
+            return 0;
+        }
+
+        // the opponent's last move won by a variant rule
+        if position.variant_outcome().is_some() {
+            return -MATE_SCORE + ply;
+        }
+
+        let hash = position.hash();
+        if ply > 0 && self.is_draw(position, hash) {
+            return 0;
+        }
+
+        if depth == 0 {
+            return self.quiescence(position, ply, alpha, beta);
+        }
+
+        let original_alpha = alpha;
+        let mut tt_move = None;
+        if let Some(entry) = self.probe(hash) {
+            tt_move = entry.best_move;
+            if ply > 0 && entry.depth >= depth {
+                let score = score_from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut moves = position.legal_moves();
+        if moves.is_empty() {
+            return match position.is_in_check(position.side_to_move()) {
+                true => -MATE_SCORE + ply,
+                false => 0,
+            };
+        }
+        order_moves(position, &mut moves, tt_move);
+
+        self.history.push(hash);
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for mv in moves {
+            let next = position.make_move(mv);
+            let score = -self.negamax(&next, depth - 1, ply + 1, -beta, -alpha);
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        self.history.pop();
+
+        if ply == 0 {
+            self.root_best_move = best_move;
+        }
+
+        if !self.stopped {
+            let bound = if best_score <= original_alpha {
+                Bound::Upper
+            } else if best_score >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            self.store(TtEntry {
+                hash,
+                depth,
+                score: score_to_tt(best_score, ply),
+                bound,
+                best_move,
+            });
+        }
+
+        best_score
+    }
+
+    /// search captures and promotions until the position is quiet,
+    /// all moves are searched when in check
+    fn quiescence(&mut self, position: &Position, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.count_node() {
+            return 0;
+        }
+
+        if position.variant_outcome().is_some() {
+            return -MATE_SCORE + ply;
+        }
+
+        let in_check = position.is_in_check(position.side_to_move());
+        let mut moves = position.legal_moves();
+        if moves.is_empty() {
+            return match in_check {
+                true => -MATE_SCORE + ply,
+                false => 0,
+            };
+        }
+
+        if !in_check {
+            let stand_pat = position.evaluate() + self.noise(position.hash());
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            moves.retain(|mv| is_tactical(position, *mv));
+        }
+        order_moves(position, &mut moves, None);
+
+        let mut best_score = if in_check { -INFINITY } else { alpha };
+        for mv in moves {
+            let score = -self.quiescence(&position.make_move(mv), ply + 1, -beta, -alpha);
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best_score
+    }
+
+    /// count a searched node and check the time budget,
+    /// true once the search has to stop
+    fn count_node(&mut self) -> bool {
+        self.nodes += 1;
+        if self.can_stop
+            && self.nodes.is_multiple_of(TIME_CHECK_NODES)
+            && Instant::now() >= self.deadline
+        {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+
+    /// drawn by the fifty-move rule or by repeating a position
+    /// played in the game or earlier in the search
+    fn is_draw(&self, position: &Position, hash: u64) -> bool {
+        if position.halfmove_clock() >= FIFTY_MOVE_PLIES || position.has_insufficient_material() {
+            return true;
+        }
+
+        // only positions since the last capture or pawn move can repeat
+        self.history
+            .iter()
+            .rev()
+            .take(position.halfmove_clock() as usize)
+            .any(|previous| *previous == hash)
+    }
+
+    /// evaluation noise which is the same every time a position
+    /// is reached during a search, so the search stays consistent
+    fn noise(&self, hash: u64) -> i32 {
+        if self.limits.randomness <= 0 {
+            return 0;
+        }
+
+        let mut z = hash ^ self.seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        let range = 2 * self.limits.randomness as u64 + 1;
+        (z % range) as i32 - self.limits.randomness
+    }
+
+    fn probe(&self, hash: u64) -> Option<TtEntry> {
+        self.tt[hash as usize % TT_SIZE].filter(|entry| entry.hash == hash)
+    }
+
+    fn store(&mut self, entry: TtEntry) {
+        let slot = &mut self.tt[entry.hash as usize % TT_SIZE];
+        // keep deeper results of the same position
+        if slot.is_none_or(|old| old.hash != entry.hash || old.depth <= entry.depth) {
+            *slot = Some(entry);
+        }
+    }
+}
+
+/// mate scores are stored relative to the node so they
+/// stay correct when the position is reached at another ply
+fn score_to_tt(score: i32, ply: i32) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score + ply
+    } else if score <= -MATE_THRESHOLD {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: i32) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score - ply
+    } else if score <= -MATE_THRESHOLD {
+        score + ply
+    } else {
+        score
+    }
+}
+
+fn is_tactical(position: &Position, mv: Move) -> bool {
+    mv.promotion.is_some()
+        || mv.kind == MoveKind::EnPassant
+        || (mv.kind != MoveKind::Castle && position.piece_at(mv.to).is_some())
+}
+
+/// transposition table move first, then captures of the most
+/// valuable piece by the least valuable attacker, then promotions
+fn order_moves(position: &Position, moves: &mut [Move], tt_move: Option<Move>) {
+    moves.sort_by_cached_key(|mv| {
+        if Some(*mv) == tt_move {
+            return i32::MIN;
+        }
+
+        let mut score = 0;
+        if is_tactical(position, *mv) {
+            let victim = match mv.kind {
+                MoveKind::EnPassant => PieceKind::Pawn,
+                _ => position
+                    .piece_at(mv.to)
+                    .map(|piece| piece.kind)
+                    .unwrap_or(PieceKind::Pawn),
+            };
+            let attacker = position
+                .piece_at(mv.from)
+                .map(|piece| piece.kind)
+                .unwrap_or(PieceKind::Pawn);
+            score -= 10 * PIECE_VALUES[victim as usize] - PIECE_VALUES[attacker as usize];
+        }
+        if let Some(promotion) = mv.promotion {
+            score -= PIECE_VALUES[promotion as usize];
+        }
+
+        score
+    });
+}
@@ -0,0 +1,107 @@
+use derive_more::Display;
+use serde::Serialize;
+
+use super::outcome::{GameEndReason, GameOutcome, GameResult};
+use super::position::Position;
+use super::types::{Color, Square};
+
+/// Checks a side has to give to win a Three-check game
+pub const CHECKS_TO_WIN: u8 = 3;
+
+/// Rule set a game is played with, the rules of each variant are
+/// plugged into move generation, move making and the game outcome
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Variant {
+    #[default]
+    #[display(fmt = "standard")]
+    Standard,
+    /// Fischer Random, shuffled back ranks with generalized castling
+    #[display(fmt = "chess960")]
+    Chess960,
+    /// bringing the king to one of the four center squares wins
+    #[display(fmt = "king-of-the-hill")]
+    KingOfTheHill,
+    /// giving check three times wins
+    #[display(fmt = "three-check")]
+    ThreeCheck,
+    /// captures explode every piece other than a pawn around
+    /// the capture square, exploding the opposing king wins
+    #[display(fmt = "atomic")]
+    Atomic,
+    /// captured pieces change sides and may be dropped back on the board
+    #[display(fmt = "crazyhouse")]
+    Crazyhouse,
+}
+
+impl Variant {
+    /// parse a variant name as given in `/new-game` or a PGN `Variant` tag
+    pub fn parse(name: &str) -> Option<Variant> {
+        match name
+            .to_ascii_lowercase()
+            .replace([' ', '-', '_'], "")
+            .as_str()
+        {
+            "standard" | "chess" => Some(Variant::Standard),
+            "chess960" | "960" | "fischerandom" | "fischerrandom" => Some(Variant::Chess960),
+            "kingofthehill" | "koth" => Some(Variant::KingOfTheHill),
+            "threecheck" | "3check" => Some(Variant::ThreeCheck),
+            "atomic" => Some(Variant::Atomic),
+            "crazyhouse" | "zh" => Some(Variant::Crazyhouse),
+            _ => None,
+        }
+    }
+
+    /// name written to the PGN `Variant` tag
+    pub fn pgn_name(self) -> &'static str {
+        match self {
+            Variant::Standard => "Standard",
+            Variant::Chess960 => "Chess960",
+            Variant::KingOfTheHill => "King of the Hill",
+            Variant::ThreeCheck => "Three-check",
+            Variant::Atomic => "Atomic",
+            Variant::Crazyhouse => "Crazyhouse",
+        }
+    }
+
+    /// the variant can only be won by checkmate, so the game is drawn
+    /// when neither side has the material left to mate
+    pub fn draws_on_insufficient_material(self) -> bool {
+        matches!(self, Variant::Standard | Variant::Chess960)
+    }
+}
+
+impl Position {
+    /// outcome decided by a variant's own win condition, checked
+    /// before checkmate and stalemate
+    pub fn variant_outcome(&self) -> Option<GameOutcome> {
+        let winner = match self.variant() {
+            Variant::Standard | Variant::Chess960 | Variant::Crazyhouse => None,
+            Variant::KingOfTheHill => [Color::White, Color::Black]
+                .into_iter()
+                .find(|color| self.king_square(*color).is_some_and(is_center)),
+            Variant::ThreeCheck => [Color::White, Color::Black]
+                .into_iter()
+                .find(|color| self.checks(*color) >= CHECKS_TO_WIN),
+            Variant::Atomic => [Color::White, Color::Black]
+                .into_iter()
+                .find(|color| self.king_square(color.opposite()).is_none()),
+        };
+
+        let reason = match self.variant() {
+            Variant::KingOfTheHill => GameEndReason::KingOfTheHill,
+            Variant::ThreeCheck => GameEndReason::ThreeChecks,
+            _ => GameEndReason::KingExploded,
+        };
+
+        winner.map(|color| GameOutcome {
+            result: GameResult::win_for(color),
+            reason,
+        })
+    }
+}
+
+/// d4, e4, d5 and e5
+fn is_center(square: Square) -> bool {
+    (3..=4).contains(&square.file()) && (3..=4).contains(&square.rank())
+}
@@ -0,0 +1,106 @@
+use super::crazyhouse::DROP_PIECES;
+use super::position::{CastleSide, Position};
+use super::types::{Color, Piece, PieceKind, Square};
+use super::variant::CHECKS_TO_WIN;
+
+const PIECE_KEYS: usize = 12 * 64;
+const CASTLING_KEYS: usize = 4;
+const EN_PASSANT_KEYS: usize = 8;
+/// most pieces of one kind a pocket can hold, 16 pawns
+const MAX_POCKET_COUNT: usize = 16;
+const POCKET_KEYS: usize = 2 * 5 * MAX_POCKET_COUNT;
+const CHECK_KEYS: usize = 2 * CHECKS_TO_WIN as usize;
+const PROMOTED_KEYS: usize = 64;
+const VARIANT_KEYS_START: usize = PIECE_KEYS + CASTLING_KEYS + EN_PASSANT_KEYS;
+const KEY_COUNT: usize = VARIANT_KEYS_START + POCKET_KEYS + CHECK_KEYS + PROMOTED_KEYS + 1;
+
+/// Random keys generated at compile time with splitmix64,
+/// fixed so hashes are stable between runs
+const KEYS: [u64; KEY_COUNT] = {
+    let mut keys = [0u64; KEY_COUNT];
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+};
+
+impl Position {
+    /// Zobrist hash of the position, two positions with the same
+    /// pieces, side to move, castling rights and en passant
+    /// possibilities have the same hash, the variant state of
+    /// pockets, promoted pieces and checks given is included
+    pub fn hash(&self) -> u64 {
+        let mut hash = 0;
+
+        for square in Square::all() {
+            if let Some(piece) = self.piece_at(square) {
+                let piece_index = piece.color as usize * 6 + piece.kind as usize;
+                hash ^= KEYS[piece_index * 64 + square.index()];
+            }
+        }
+
+        let castling = self.castling();
+        for (i, (color, side)) in [
+            (Color::White, CastleSide::King),
+            (Color::White, CastleSide::Queen),
+            (Color::Black, CastleSide::King),
+            (Color::Black, CastleSide::Queen),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if castling.has(color, side) {
+                hash ^= KEYS[PIECE_KEYS + i];
+            }
+        }
+
+        // the en passant square only matters if it can be captured on
+        if let Some(square) = self.en_passant() {
+            let color = self.side_to_move();
+            let pawn = Some(Piece::new(color, PieceKind::Pawn));
+            let can_capture = [-1, 1].into_iter().any(|files| {
+                square
+                    .offset(files, -color.forward())
+                    .map(|from| self.piece_at(from) == pawn)
+                    .unwrap_or(false)
+            });
+            if can_capture {
+                hash ^= KEYS[PIECE_KEYS + CASTLING_KEYS + square.file() as usize];
+            }
+        }
+
+        let pockets = self.pockets();
+        for color in [Color::White, Color::Black] {
+            for kind in DROP_PIECES {
+                let count = (pockets.count(color, kind) as usize).min(MAX_POCKET_COUNT);
+                if count > 0 {
+                    let pocket_index = color as usize * 5 + kind as usize;
+                    hash ^= KEYS[VARIANT_KEYS_START + pocket_index * MAX_POCKET_COUNT + count - 1];
+                }
+            }
+
+            let checks = (self.checks(color) as usize).min(CHECKS_TO_WIN as usize);
+            if checks > 0 {
+                let check_index = color as usize * CHECKS_TO_WIN as usize + checks - 1;
+                hash ^= KEYS[VARIANT_KEYS_START + POCKET_KEYS + check_index];
+            }
+        }
+
+        for square in Square::all().filter(|square| self.is_promoted(*square)) {
+            hash ^= KEYS[VARIANT_KEYS_START + POCKET_KEYS + CHECK_KEYS + square.index()];
+        }
+
+        if self.side_to_move() == Color::Black {
+            hash ^= KEYS[KEY_COUNT - 1];
+        }
+
+        hash
+    }
+}
@@ -0,0 +1,350 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::chess::Color;
+
+/// Most minutes a stage of a time control may have, a week
+const MAX_STAGE_MINUTES: u64 = 7 * 24 * 60;
+
+/// Longest increment or delay of a time control, an hour
+const MAX_INCREMENT: Duration = Duration::from_secs(60 * 60);
+
+/// Most days a correspondence game gives for a move, a month
+const MAX_DAYS_PER_MOVE: u32 = 30;
+
+/// How the bonus time of a time control is given on each move
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IncrementMode {
+    /// Fischer increment, added to the clock after every move
+    #[default]
+    Fischer,
+    /// US or simple delay, the clock only runs once the delay passed
+    Delay,
+    /// Bronstein delay, the time used is given back after the
+    /// move, never more than the delay
+    Bronstein,
+}
+
+/// A period of a time control, the time for a number of moves
+/// or for the rest of the game when the moves are not given
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeStage {
+    pub moves: Option<u32>,
+    #[serde(rename = "seconds", serialize_with = "as_seconds")]
+    pub time: Duration,
+}
+
+/// Time control of a game, its stages and the increment or delay given
+/// on every move, written as minutes and seconds, eg. `3+2` gives three
+/// minutes and adds two seconds a move, `5+3d` has a three second delay
+/// and `5+3b` a three second Bronstein delay, `40/90:30+30` is ninety
+/// minutes for forty moves then thirty for the rest of the game with
+/// thirty seconds added each move, a last stage with moves repeats
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TimeControl {
+    pub stages: Vec<TimeStage>,
+    #[serde(serialize_with = "as_seconds")]
+    pub increment: Duration,
+    pub mode: IncrementMode,
+}
+
+impl TimeControl {
+    /// parse stages separated by `:`, each `<minutes>` or `<moves>/<minutes>`,
+    /// followed by `+<seconds>` and `d` for a delay or `b` for a Bronstein
+    /// delay, a time control without time or with more than a week for
+    /// a stage or an hour of increment is not valid
+    pub fn parse(text: &str) -> Option<TimeControl> {
+        let (stages, increment) = text.split_once('+')?;
+
+        let (increment, mode) = match increment.strip_suffix('d') {
+            Some(delay) => (delay, IncrementMode::Delay),
+            None => match increment.strip_suffix('b') {
+                Some(delay) => (delay, IncrementMode::Bronstein),
+                None => (increment, IncrementMode::Fischer),
+            },
+        };
+        let increment = Some(Duration::from_secs(increment.parse().ok()?))
+            .filter(|increment| *increment <= MAX_INCREMENT)?;
+
+        let stages = stages
+            .split(':')
+            .map(|stage| {
+                let (moves, minutes) = match stage.split_once('/') {
+                    Some((moves, minutes)) => {
+                        (Some(moves.parse().ok().filter(|m| *m > 0)?), minutes)
+                    }
+                    None => (None, stage),
+                };
+                let minutes = minutes
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|m| (1..=MAX_STAGE_MINUTES).contains(m))?;
+
+                Some(TimeStage {
+                    moves,
+                    time: Duration::from_secs(minutes * 60),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(TimeControl {
+            stages,
+            increment,
+            mode,
+        })
+    }
+
+    /// value of the PGN `TimeControl` tag, the stages in seconds with the
+    /// increment of each, delays which the PGN standard has no field for
+    /// are marked with a `d` or `b` after the delay like in `parse`
+    pub fn pgn(&self) -> String {
+        self.stages
+            .iter()
+            .map(|stage| {
+                let moves = match stage.moves {
+                    Some(moves) => format!("{moves}/"),
+                    None => String::new(),
+                };
+                let seconds = stage.time.as_secs();
+                format!("{moves}{seconds}+{}", self.bonus())
+            })
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    /// the increment or delay in seconds with its mode
+    fn bonus(&self) -> String {
+        let suffix = match self.mode {
+            IncrementMode::Fischer => "",
+            IncrementMode::Delay => "d",
+            IncrementMode::Bronstein => "b",
+        };
+        format!("{}{suffix}", self.increment.as_secs())
+    }
+
+    /// stage a side plays in, the last one once all others are over
+    fn stage(&self, index: usize) -> TimeStage {
+        self.stages[index.min(self.stages.len() - 1)]
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stages = self
+            .stages
+            .iter()
+            .map(|stage| {
+                let minutes = stage.time.as_secs() / 60;
+                match stage.moves {
+                    Some(moves) => format!("{moves}/{minutes}"),
+                    None => minutes.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(":");
+
+        write!(f, "{stages}+{}", self.bonus())
+    }
+}
+
+fn as_seconds<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_secs())
+}
+
+/// Clock of one side, the time left when it last stopped and
+/// how far the side got into the stages of the time control
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SideClock {
+    left: Duration,
+    stage: usize,
+    /// moves made in the current stage
+    stage_moves: u32,
+}
+
+/// Clocks of both sides of a game, only the clock of the side to
+/// move runs, the time it used is taken off when it moves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameClock {
+    control: TimeControl,
+    white: SideClock,
+    black: SideClock,
+    /// side whose clock runs and since when
+    running: Option<(Color, Instant)>,
+}
+
+impl GameClock {
+    pub fn new(control: TimeControl) -> Self {
+        let side = SideClock {
+            left: control.stage(0).time,
+            stage: 0,
+            stage_moves: 0,
+        };
+
+        Self {
+            control,
+            white: side,
+            black: side,
+            running: None,
+        }
+    }
+
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    /// side whose clock runs, `None` before the game
+    /// started and once it is over
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// start the clock of the side to move
+    pub fn start(&mut self, color: Color, now: Instant) {
+        self.running = Some((color, now));
+    }
+
+    /// time the side has left at `now`, a delay which has not
+    /// passed yet is not taken off
+    pub fn remaining(&self, color: Color, now: Instant) -> Duration {
+        let left = self.side(color).left;
+
+        match self.running {
+            Some((running, since)) if running == color => {
+                left.saturating_sub(self.charged(now.saturating_duration_since(since)))
+            }
+            _ => left,
+        }
+    }
+
+    /// side whose clock ran out at `now`
+    pub fn flagged(&self, now: Instant) -> Option<Color> {
+        self.running()
+            .filter(|color| self.remaining(*color, now).is_zero())
+    }
+
+    /// the side to move moved, the time it used is taken off its clock
+    /// with the increment added or the Bronstein delay given back, the
+    /// time of the next stage is added once the side made the moves of
+    /// its stage, and the other clock starts
+    pub fn press(&mut self, now: Instant) {
+        let (color, since) = match self.running {
+            Some(running) => running,
+            None => return,
+        };
+
+        let used = now.saturating_duration_since(since);
+        let bonus = match self.control.mode {
+            IncrementMode::Fischer => self.control.increment,
+            IncrementMode::Delay => Duration::ZERO,
+            IncrementMode::Bronstein => used.min(self.control.increment),
+        };
+        let left = self.remaining(color, now).saturating_add(bonus);
+
+        let control = &self.control;
+        let side = match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        };
+        side.left = left;
+        side.stage_moves += 1;
+
+        if control.stage(side.stage).moves == Some(side.stage_moves) {
+            side.stage += 1;
+            side.stage_moves = 0;
+            side.left = side.left.saturating_add(control.stage(side.stage).time);
+        }
+
+        self.running = Some((color.opposite(), now));
+    }
+
+    /// stop the clocks at the end of the game
+    pub fn stop(&mut self, now: Instant) {
+        if let Some((color, _)) = self.running {
+            let left = self.remaining(color, now);
+            match color {
+                Color::White => self.white.left = left,
+                Color::Black => self.black.left = left,
+            }
+            self.running = None;
+        }
+    }
+
+    fn side(&self, color: Color) -> &SideClock {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    /// time taken off the clock for thinking `used`
+    fn charged(&self, used: Duration) -> Duration {
+        match self.control.mode {
+            IncrementMode::Delay => used.saturating_sub(self.control.increment),
+            IncrementMode::Fischer | IncrementMode::Bronstein => used,
+        }
+    }
+}
+
+/// Clock of a correspondence game, each side has a number of days
+/// for every move, the deadline runs on the wall clock so it holds
+/// while the players are away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CorrespondenceClock {
+    days_per_move: u32,
+    /// side to move and when its time for the move is up
+    deadline: Option<(Color, SystemTime)>,
+}
+
+impl CorrespondenceClock {
+    /// clock with `days_per_move` days a move, `None` for no days
+    /// or more than a month
+    pub fn new(days_per_move: u32) -> Option<Self> {
+        (1..=MAX_DAYS_PER_MOVE)
+            .contains(&days_per_move)
+            .then_some(Self {
+                days_per_move,
+                deadline: None,
+            })
+    }
+
+    pub fn days_per_move(&self) -> u32 {
+        self.days_per_move
+    }
+
+    /// time given for a move
+    pub fn time_per_move(&self) -> Duration {
+        Duration::from_secs(u64::from(self.days_per_move) * 24 * 60 * 60)
+    }
+
+    /// side to move and its deadline, `None` before the game
+    /// started and once it is over
+    pub fn deadline(&self) -> Option<(Color, SystemTime)> {
+        self.deadline
+    }
+
+    /// the side to move has its days for the move from `now`
+    pub fn start(&mut self, color: Color, now: SystemTime) {
+        self.deadline = Some((color, now + self.time_per_move()));
+    }
+
+    /// side whose time for the move was up at `now`
+    pub fn expired(&self, now: SystemTime) -> Option<Color> {
+        self.deadline
+            .filter(|(_, deadline)| now >= *deadline)
+            .map(|(color, _)| color)
+    }
+
+    /// the side to move moved, the other side has its days from `now`
+    pub fn press(&mut self, now: SystemTime) {
+        if let Some((color, _)) = self.deadline {
+            self.start(color.opposite(), now);
+        }
+    }
+
+    /// no more deadlines once the game is over
+    pub fn stop(&mut self) {
+        self.deadline = None;
+    }
+}
@@ -5,3 +5,35 @@ pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 
 /// How long before lack of client response causes a timeout
 pub const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Least time between two engine evaluations sent to a client
+pub const EVAL_THROTTLE: Duration = Duration::from_millis(500);
+
+/// Least time between two draw offers of a player
+pub const DRAW_OFFER_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// How long each side has for its first move before the game is
+/// aborted, unless `FIRST_MOVE_TIMEOUT` sets it in seconds
+pub const FIRST_MOVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often started games are checked for a first move not made in time
+pub const ABORT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the clocks of timed games are checked for a flag
+pub const CLOCK_TICK: Duration = Duration::from_millis(100);
+
+/// How often players are sent the time left on their clocks
+pub const CLOCK_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often correspondence games are checked for a passed deadline
+pub const DEADLINE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a finished game is kept for its report, PGN and rematch
+pub const FINISHED_GAME_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Most finished games kept at once, the oldest are dropped first
+pub const MAX_FINISHED_GAMES: usize = 1000;
+
+/// How long spectators wait for the engine evaluation of a game
+/// in progress, so it is no use to the players
+pub const SPECTATOR_EVAL_DELAY: Duration = Duration::from_secs(30);
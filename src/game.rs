@@ -1,34 +1,1458 @@
+use derive_more::Display;
+use rand::Rng;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant, SystemTime};
+use uuid::Uuid;
+
+use crate::bot::{BotEngine, BotLevel, BotOpponent, EngineConfig};
+use crate::chess::chess960::CHESS960_POSITIONS;
+use crate::chess::fen::FenError;
+use crate::chess::pgn::{game_from_pgn, pgn_date, PgnError, PgnHeaders};
+use crate::chess::{
+    Color, Game, GameEndReason, GameOutcome, GameResult, Move, MoveError, MoveKind, PieceKind,
+    Position, Square, Variant,
+};
+use crate::clock::{CorrespondenceClock, GameClock, TimeControl};
+use crate::constants::{
+    DRAW_OFFER_COOLDOWN, EVAL_THROTTLE, FINISHED_GAME_RETENTION, MAX_FINISHED_GAMES,
+    SPECTATOR_EVAL_DELAY,
+};
+use crate::message::Json;
+use crate::report::GameReport;
 use crate::session::SessionId;
-use std::collections::HashMap;
+use crate::uci::{UciInfo, UciScore, UciSearch};
 
 type SessionGameId = String;
 
+/// Progress of the analysis report of a finished game
+#[derive(Debug, Clone, Default)]
+enum ReportStatus {
+    #[default]
+    NotStarted,
+    Analysing,
+    Ready(GameReport),
+}
+
 #[derive(Debug, Clone)]
 pub struct SessionGame {
     game_id: SessionGameId,
     white: Option<SessionId>,
     black: Option<SessionId>,
+    /// usernames of the players, kept while they are away
+    /// from a correspondence game
+    white_name: Option<String>,
+    black_name: Option<String>,
+    /// secrets a player of a correspondence game takes
+    /// their seat back with, one for each side
+    white_token: String,
+    black_token: String,
     started: bool,
+    chess: Game,
+    outcome: Option<GameOutcome>,
+    created_at: SystemTime,
+    /// computer opponent, which plays the side without a session
+    bot: Option<BotOpponent>,
+    /// clocks of a timed game, they start with the game
+    clock: Option<GameClock>,
+    /// clocks before each move of a timed game, taking
+    /// moves back puts the clocks back as they were
+    clock_history: Vec<GameClock>,
+    /// days a move of a correspondence game
+    correspondence: Option<CorrespondenceClock>,
+    /// side offering a draw and the half moves played when it
+    /// did, the offer lapses once a move is made
+    draw_offer: Option<(Color, usize)>,
+    /// when each side last offered a draw
+    draw_offered_at: HashMap<Color, Instant>,
+    /// side asking to take back its last move and the half moves
+    /// played when it asked, the request lapses once a move is made
+    takeback: Option<(Color, usize)>,
+    /// side offering a rematch once the game is over
+    rematch_offer: Option<Color>,
+    /// the rematch once it started
+    rematch_id: Option<SessionGameId>,
+    /// name of the first game the players played, the username of
+    /// its creator, rematches are named after it
+    pairing: String,
+    /// games the players played in a row, the first one is round 1
+    round: u32,
+    /// half moves the game was seeded with, each side has to move
+    /// once after them before the game can no longer be aborted
+    start_ply: usize,
+    /// when the side to move got its turn, once the game started
+    turn_started_at: Option<Instant>,
+    /// sessions following the engine evaluation of the game,
+    /// with the time an evaluation was last sent to them
+    eval_followers: HashMap<SessionId, Option<Instant>>,
+    /// an engine is evaluating the game for its followers
+    evaluating: bool,
+    report: ReportStatus,
+}
+
+/// Canonical form of an accepted move, sent to both players
+/// whatever notation the mover used
+#[derive(Serialize, Debug, Clone)]
+pub struct GameMoveInfo {
+    pub game_id: SessionGameId,
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PieceKind>,
+    pub uci: String,
+    pub san: String,
+    /// position after the move
+    pub fen: String,
+}
+
+impl Json for GameMoveInfo {}
+
+/// Time left on the clocks of a game in milliseconds, sent
+/// to the players after every move and periodically
+#[derive(Serialize, Debug, Clone)]
+pub struct GameClockInfo {
+    pub game_id: SessionGameId,
+    pub white: u64,
+    pub black: u64,
+    /// side whose clock runs, `None` once the game is over
+    pub running: Option<Color>,
+}
+
+impl Json for GameClockInfo {}
+
+/// Deadline of the side to move in a correspondence game, sent
+/// to the players after every move and when they come back
+#[derive(Serialize, Debug, Clone)]
+pub struct GameDeadlineInfo {
+    pub game_id: SessionGameId,
+    pub days_per_move: u32,
+    /// side to move, `None` before the game started and once it is over
+    pub to_move: Option<Color>,
+    /// seconds since the Unix epoch when the time for the move is up
+    pub deadline: Option<u64>,
+}
+
+impl Json for GameDeadlineInfo {}
+
+/// Sent to both players when a game finishes
+#[derive(Serialize, Debug, Clone)]
+pub struct GameOverInfo {
+    pub game_id: SessionGameId,
+    pub result: GameResult,
+    pub reason: GameEndReason,
+}
+
+impl Json for GameOverInfo {}
+
+/// A player resigned, offered a draw or answered a draw offer,
+/// or their offer lapsed, sent to both players
+#[derive(Serialize, Debug, Clone)]
+pub struct PlayerActionInfo {
+    pub game_id: SessionGameId,
+    /// side of the player
+    pub color: Color,
+}
+
+impl Json for PlayerActionInfo {}
+
+/// A takeback asked for or answered, sent to both players
+#[derive(Serialize, Debug, Clone)]
+pub struct TakebackInfo {
+    pub game_id: SessionGameId,
+    /// side of the player asking for or answering the takeback
+    pub color: Color,
+    /// half moves taken back
+    pub plies: usize,
+    /// position the game went back to, once the moves were taken back
+    pub fen: Option<String>,
+}
+
+impl Json for TakebackInfo {}
+
+/// A rematch offered or answered, sent to both players
+#[derive(Serialize, Debug, Clone)]
+pub struct RematchInfo {
+    /// the finished game
+    pub game_id: SessionGameId,
+    /// side the player offering or answering the rematch played
+    pub color: Color,
+    /// the new game, once the rematch started
+    pub rematch_id: Option<SessionGameId>,
+}
+
+impl Json for RematchInfo {}
+
+/// Sent to both players when a game is aborted, it is dropped
+/// without a result
+#[derive(Serialize, Debug, Clone)]
+pub struct GameAbortInfo {
+    pub game_id: SessionGameId,
+    /// side of the player who aborted, `None` if the side to
+    /// move did not make its first move in time
+    pub aborted_by: Option<Color>,
+}
+
+impl Json for GameAbortInfo {}
+
+/// A game as listed in the lobby, player names are `None`
+/// for a seat nobody has taken yet
+#[derive(Serialize, Debug, Clone)]
+pub struct GameSummary {
+    pub game_id: SessionGameId,
+    /// name to show for the game, eg. `alice` or `alice #2` for a rematch
+    pub name: String,
+    pub variant: Variant,
+    pub white: Option<String>,
+    pub black: Option<String>,
+    /// stages and increment of the clocks, `None` if untimed
+    pub time_control: Option<TimeControl>,
+    /// days a move of a correspondence game
+    pub days_per_move: Option<u32>,
+    pub started: bool,
+    /// half moves played
+    pub plies: usize,
+    /// ECO code and name of the opening the game reached
+    pub eco: Option<String>,
+    pub opening: Option<String>,
+}
+
+impl Json for GameSummary {}
+impl Json for Vec<GameSummary> {}
+
+/// A legal move in the current position of a game
+#[derive(Serialize, Debug, Clone)]
+pub struct LegalMove {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PieceKind>,
+    pub uci: String,
+    pub san: String,
+}
+
+/// Answer to a legal move query, for a single square
+/// or for the whole position when no square is given
+#[derive(Serialize, Debug, Clone)]
+pub struct LegalMovesInfo {
+    pub game_id: SessionGameId,
+    pub square: Option<Square>,
+    pub side_to_move: Color,
+    pub moves: Vec<LegalMove>,
+}
+
+impl Json for LegalMovesInfo {}
+
+/// Engine verdict on the current position of a game
+#[derive(Serialize, Debug, Clone)]
+pub struct GameAnalysis {
+    pub game_id: SessionGameId,
+    pub fen: String,
+    pub best_move: Option<String>,
+    pub san: Option<String>,
+    /// score from the side to move's point of view
+    pub score: Option<UciScore>,
+    pub depth: Option<u32>,
+    /// principal variation in UCI notation
+    pub pv: Vec<String>,
+}
+
+impl GameAnalysis {
+    pub fn new(game_id: SessionGameId, position: &Position, search: &UciSearch) -> Self {
+        let info = search.info.clone().unwrap_or_default();
+
+        Self {
+            game_id,
+            fen: position.to_fen(),
+            best_move: search.best_move.map(|mv| position.uci(mv)),
+            san: search.best_move.map(|mv| position.san(mv)),
+            score: info.score,
+            depth: info.depth,
+            pv: info.pv,
+        }
+    }
+}
+
+impl Json for GameAnalysis {}
+
+/// Engine search progress on the current position of a game,
+/// streamed to the sessions following the evaluation
+#[derive(Serialize, Debug, Clone)]
+pub struct GameEval {
+    pub game_id: SessionGameId,
+    /// half moves played before the evaluated position
+    pub ply: usize,
+    pub fen: String,
+    #[serde(flatten)]
+    pub info: UciInfo,
+    /// the engine finished searching the position
+    pub done: bool,
+}
+
+impl Json for GameEval {}
+
+impl Json for GameReport {}
+
+/// Starting setup of a game requested with `/new-game`, eg. `chess960 518`,
+/// `crazyhouse`, `bot=2 color=black`, `engine`, `3+2`, `5+3d`, `40/90:30+30`,
+/// `days=3`, `atomic <fen>` or a FEN
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NewGameOptions {
+    pub variant: Variant,
+    /// clocks of the game, untimed when not given
+    pub time_control: Option<TimeControl>,
+    /// days a move of a correspondence game
+    pub correspondence: Option<CorrespondenceClock>,
+    /// computer opponent, the game starts right away against it
+    pub bot: Option<BotOpponent>,
+    /// Chess960 starting position index, random when not given
+    pub start_index: Option<u16>,
+    /// starting position, used instead of the variant's starting position
+    pub fen: Option<String>,
+}
+
+impl NewGameOptions {
+    /// parse options, `bot` or `bot=<level>` to play the computer,
+    /// `engine` to play the server's UCI engine,
+    /// `color=<white|black>` for the side played against it, a time
+    /// control such as `10+5` and a variant name, followed by a
+    /// Chess960 position index or a FEN
+    pub fn parse(args: &str) -> Result<NewGameOptions, GameError> {
+        let mut options = NewGameOptions::default();
+        let mut player_color = Color::White;
+
+        let mut rest = args.trim();
+        while let Some(token) = rest.split_whitespace().next() {
+            if token == "bot" {
+                options.bot.get_or_insert(BotOpponent {
+                    engine: BotEngine::BuiltIn(BotLevel::default()),
+                    color: Color::Black,
+                });
+            } else if let Some(level) = token.strip_prefix("bot=") {
+                let level = level
+                    .parse::<u8>()
+                    .ok()
+                    .and_then(BotLevel::new)
+                    .ok_or_else(|| GameError::InvalidOption(token.to_string()))?;
+                options.bot = Some(BotOpponent {
+                    engine: BotEngine::BuiltIn(level),
+                    color: Color::Black,
+                });
+            } else if token == "engine" {
+                if EngineConfig::get().is_none() {
+                    return Err(GameError::NoEngine);
+                }
+                options.bot = Some(BotOpponent {
+                    engine: BotEngine::Uci,
+                    color: Color::Black,
+                });
+            } else if let Some(color) = token.strip_prefix("color=") {
+                player_color = match color {
+                    "white" => Color::White,
+                    "black" => Color::Black,
+                    _ => return Err(GameError::InvalidOption(token.to_string())),
+                };
+            } else if let Some(days) = token.strip_prefix("days=") {
+                let clock = days
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(CorrespondenceClock::new)
+                    .ok_or_else(|| GameError::InvalidOption(token.to_string()))?;
+                options.correspondence = Some(clock);
+            } else if let Some(time_control) = TimeControl::parse(token) {
+                options.time_control = Some(time_control);
+            } else if let Some(variant) = Variant::parse(token) {
+                options.variant = variant;
+            } else {
+                break;
+            }
+            rest = rest[token.len()..].trim_start();
+        }
+
+        if let Some(bot) = options.bot.as_mut() {
+            bot.color = player_color.opposite();
+        }
+
+        // a game has either clocks or days a move
+        if let (Some(control), Some(_)) = (&options.time_control, options.correspondence) {
+            return Err(GameError::InvalidOption(control.to_string()));
+        }
+
+        let variant = options.variant;
+        if rest.is_empty() {
+            return Ok(options);
+        }
+
+        if rest.chars().all(|c| c.is_ascii_digit()) {
+            match rest.parse::<u16>() {
+                Ok(index) if variant == Variant::Chess960 && index < CHESS960_POSITIONS => {
+                    options.start_index = Some(index);
+                }
+                _ => return Err(GameError::InvalidOption(rest.to_string())),
+            }
+        } else {
+            options.fen = Some(rest.to_string());
+        }
+
+        Ok(options)
+    }
+
+    /// game seeded from a PGN and its options, `<start point> [options]
+    /// <pgn>`, the PGN sets the variant and the starting position
+    pub fn parse_pgn(args: &str) -> Result<(Game, NewGameOptions), GameError> {
+        let args = args.trim();
+        let (start_point, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+
+        // whatever follows the options is taken as the PGN
+        let mut options = NewGameOptions::parse(rest)?;
+        let pgn = options
+            .fen
+            .take()
+            .ok_or(GameError::InvalidPgn(PgnError::NoGames))?;
+        if options.variant != Variant::Standard || options.start_index.is_some() {
+            return Err(GameError::InvalidOption(options.variant.to_string()));
+        }
+
+        let chess = game_from_pgn(start_point, &pgn).map_err(GameError::InvalidPgn)?;
+        Ok((chess, options))
+    }
+
+    /// chess game set up from the options, a Chess960 game without
+    /// an index gets a random starting position, a position where the
+    /// game is already over, by the rules of its variant, is not valid
+    pub fn to_game(&self) -> Result<Game, GameError> {
+        let start = match (&self.fen, self.variant) {
+            (Some(fen), Variant::Standard) => {
+                Position::from_fen(fen).map_err(GameError::InvalidFen)?
+            }
+            (Some(fen), variant) => Position::from_fen(fen)
+                .map_err(GameError::InvalidFen)?
+                .with_variant(variant),
+            (None, Variant::Chess960) => {
+                let index = self
+                    .start_index
+                    .unwrap_or_else(|| rand::thread_rng().gen_range(0..CHESS960_POSITIONS));
+                Position::chess960(index)
+                    .ok_or_else(|| GameError::InvalidOption(index.to_string()))?
+            }
+            (None, variant) => Position::starting().with_variant(variant),
+        };
+
+        let game = Game::new(start);
+        if game.outcome().is_some() {
+            return Err(GameError::PositionDecided);
+        }
+
+        Ok(game)
+    }
+}
+
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum GameError {
+    #[display(fmt = "Game {_0} does not exist")]
+    GameNotFound(String),
+    #[display(fmt = "Game {_0} is already over")]
+    GameOver(String),
+    #[display(fmt = "You are not a player in game {_0}")]
+    NotAPlayer(String),
+    #[display(fmt = "Game {_0} has not started, waiting for an opponent")]
+    GameNotStarted(String),
+    #[display(fmt = "It is not your turn, {_0:?} to move")]
+    NotYourTurn(Color),
+    #[display(fmt = "There is no draw to claim in this position")]
+    NoDrawToClaim,
+    #[display(fmt = "{_0}")]
+    InvalidMove(MoveError),
+    #[display(fmt = "Invalid game option: {_0}")]
+    InvalidOption(String),
+    #[display(fmt = "{_0}")]
+    InvalidFen(FenError),
+    #[display(fmt = "{_0}")]
+    InvalidPgn(PgnError),
+    #[display(fmt = "The game is already over in this position")]
+    PositionDecided,
+    #[display(fmt = "Invalid square: {_0}")]
+    InvalidSquare(String),
+    #[display(fmt = "Game {_0} moved on while the computer was thinking")]
+    BotMoveOutdated(String),
+    #[display(fmt = "No UCI engine is configured on this server")]
+    NoEngine,
+    #[display(fmt = "Game {_0} can only be analysed once it is over")]
+    AnalysisNotAllowed(String),
+    #[display(fmt = "Game {_0} is still in progress")]
+    GameInProgress(String),
+    #[display(fmt = "The analysis report of game {_0} is not ready yet")]
+    ReportNotReady(String),
+    #[display(fmt = "{_0:?} ran out of time")]
+    OutOfTime(Color),
+    #[display(fmt = "Game {_0} is not a correspondence game")]
+    NotCorrespondence(String),
+    #[display(fmt = "Invalid resume token for game {_0}")]
+    InvalidResumeToken(String),
+    #[display(fmt = "There is no draw offer to answer")]
+    NoDrawOffer,
+    #[display(fmt = "A draw offer is already waiting for an answer")]
+    DrawOfferPending,
+    #[display(fmt = "You can offer a draw again in {_0} seconds")]
+    DrawOfferTooSoon(u64),
+    #[display(fmt = "The computer does not take draw offers")]
+    DrawOfferToBot,
+    #[display(fmt = "You have no move to take back")]
+    NoMoveToTakeBack,
+    #[display(fmt = "A takeback request is already waiting for an answer")]
+    TakebackPending,
+    #[display(fmt = "There is no takeback request to answer")]
+    NoTakebackRequest,
+    #[display(fmt = "A rematch offer is already waiting for an answer")]
+    RematchPending,
+    #[display(fmt = "There is no rematch offer to answer")]
+    NoRematchOffer,
+    #[display(fmt = "The rematch {_0} has already started")]
+    RematchStarted(String),
+    #[display(fmt = "Your opponent has left")]
+    OpponentLeft,
+    #[display(fmt = "Game {_0} can no longer be aborted, both sides have moved")]
+    AbortTooLate(String),
 }
 
 impl SessionGame {
-    pub fn new(game_id: SessionGameId, white_session_id: SessionId) -> Self {
+    /// new game with the creator playing white, the chess game
+    /// may already have moves when it is seeded from a PGN
+    pub fn new(
+        game_id: SessionGameId,
+        white_session_id: SessionId,
+        username: &str,
+        chess: Game,
+    ) -> Self {
+        let outcome = chess.outcome();
+
         Self {
+            pairing: username.to_string(),
+            round: 1,
+            start_ply: chess.moves().len(),
+            turn_started_at: None,
             game_id,
             white: Some(white_session_id),
             black: None,
+            white_name: Some(username.to_string()),
+            black_name: None,
+            white_token: Uuid::new_v4().to_string(),
+            black_token: Uuid::new_v4().to_string(),
             started: false,
+            chess,
+            outcome,
+            created_at: SystemTime::now(),
+            bot: None,
+            clock: None,
+            clock_history: Vec::new(),
+            correspondence: None,
+            draw_offer: None,
+            draw_offered_at: HashMap::new(),
+            takeback: None,
+            rematch_offer: None,
+            rematch_id: None,
+            eval_followers: HashMap::new(),
+            evaluating: false,
+            report: ReportStatus::NotStarted,
+        }
+    }
+
+    /// play against the computer, the creator takes the other
+    /// side and the game starts without waiting for an opponent
+    pub fn with_bot(mut self, bot: BotOpponent) -> Self {
+        let creator = self.white.take();
+        let name = self.white_name.take();
+        match bot.color {
+            Color::White => (self.black, self.black_name) = (creator, name),
+            Color::Black => (self.white, self.white_name) = (creator, name),
+        }
+        self.bot = Some(bot);
+        self.start();
+        self
+    }
+
+    /// play with clocks, set before the game starts
+    pub fn with_clock(mut self, control: TimeControl) -> Self {
+        self.clock = Some(GameClock::new(control));
+        self
+    }
+
+    /// play by correspondence, set before the game starts
+    pub fn with_correspondence(mut self, clock: CorrespondenceClock) -> Self {
+        self.correspondence = Some(clock);
+        self
+    }
+
+    pub fn bot(&self) -> Option<BotOpponent> {
+        self.bot
+    }
+
+    /// name to show for the game, the username of the creator of the
+    /// first game of the pairing followed by the round of a rematch
+    pub fn name(&self) -> String {
+        match self.round {
+            1 => self.pairing.clone(),
+            round => format!("{} #{round}", self.pairing),
+        }
+    }
+
+    /// the game survives its players disconnecting
+    pub fn is_correspondence(&self) -> bool {
+        self.correspondence.is_some()
+    }
+
+    /// the game starts, the clock or the deadline
+    /// of the side to move starts running
+    fn start(&mut self) {
+        self.started = true;
+
+        if self.outcome.is_some() {
+            return;
+        }
+        self.turn_started_at = Some(Instant::now());
+
+        let side_to_move = self.side_to_move();
+        if let Some(clock) = self.clock.as_mut() {
+            clock.start(side_to_move, Instant::now());
+        }
+        if let Some(clock) = self.correspondence.as_mut() {
+            clock.start(side_to_move, SystemTime::now());
+        }
+    }
+
+    /// the clocks of the game are running
+    pub fn clock_running(&self) -> bool {
+        self.clock
+            .as_ref()
+            .is_some_and(|clock| clock.running().is_some())
+    }
+
+    pub fn clock_info(&self) -> Option<GameClockInfo> {
+        let clock = self.clock.as_ref()?;
+        let now = Instant::now();
+
+        Some(GameClockInfo {
+            game_id: self.game_id.clone(),
+            white: clock.remaining(Color::White, now).as_millis() as u64,
+            black: clock.remaining(Color::Black, now).as_millis() as u64,
+            running: clock.running(),
+        })
+    }
+
+    pub fn deadline_info(&self) -> Option<GameDeadlineInfo> {
+        let clock = self.correspondence?;
+        let deadline = clock.deadline();
+
+        Some(GameDeadlineInfo {
+            game_id: self.game_id.clone(),
+            days_per_move: clock.days_per_move(),
+            to_move: deadline.map(|(color, _)| color),
+            deadline: deadline.map(|(_, deadline)| unix_seconds(deadline)),
+        })
+    }
+
+    /// end a correspondence game if the side to move let its deadline
+    /// pass, like a loss on time, `true` if the game ended
+    pub fn check_deadline(&mut self, now: SystemTime) -> bool {
+        let expired = match &self.correspondence {
+            Some(clock) if self.outcome.is_none() => clock.expired(now),
+            _ => None,
+        };
+
+        match expired {
+            Some(color) => {
+                self.outcome = Some(GameOutcome::timeout(self.chess.position(), color));
+                self.update_turn(Instant::now());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// the side to move ran out of time on its clock or let its
+    /// deadline pass, the game is over
+    fn out_of_time(&mut self, now: Instant) -> bool {
+        self.check_flag(now) || self.check_deadline(SystemTime::now())
+    }
+
+    /// end the game if the side to move ran out of time, a loss
+    /// unless the opponent cannot mate, `true` if the game ended
+    pub fn check_flag(&mut self, now: Instant) -> bool {
+        let flagged = match &mut self.clock {
+            Some(clock) if self.outcome.is_none() => clock.flagged(now),
+            _ => None,
+        };
+
+        match flagged {
+            Some(color) => {
+                self.outcome = Some(GameOutcome::timeout(self.chess.position(), color));
+                self.update_turn(now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// the clocks after a move or the end of the game, the clock of
+    /// the mover stops with the increment added and the other starts,
+    /// both stop once the game is over, deadlines likewise, and no
+    /// draw offer or takeback request outlasts the game
+    fn update_turn(&mut self, now: Instant) {
+        self.turn_started_at = Some(now);
+
+        if self.outcome.is_some() {
+            self.draw_offer = None;
+            self.takeback = None;
+        }
+
+        if let Some(clock) = &mut self.clock {
+            match self.outcome {
+                Some(_) => clock.stop(now),
+                None => {
+                    self.clock_history.push(clock.clone());
+                    clock.press(now);
+                }
+            }
+        }
+
+        if let Some(clock) = &mut self.correspondence {
+            match self.outcome {
+                Some(_) => clock.stop(),
+                None => clock.press(SystemTime::now()),
+            }
+        }
+    }
+
+    /// the bot of the game if it is its turn and the game is not over
+    pub fn bot_to_move(&self) -> Option<BotOpponent> {
+        self.bot
+            .filter(|bot| self.outcome.is_none() && bot.color == self.side_to_move())
+    }
+
+    /// the game to evaluate for the followers of its evaluation, `None`
+    /// if nobody follows it or an engine is already evaluating it
+    pub fn start_eval(&mut self) -> Option<Game> {
+        if self.eval_followers.is_empty() || self.evaluating {
+            return None;
+        }
+
+        self.evaluating = true;
+        Some(self.chess.clone())
+    }
+
+    /// the game to evaluate next once the engine finished evaluating
+    /// the position after `ply` half moves, `None` if the position has
+    /// not changed since or nobody follows the evaluation any more
+    pub fn next_eval(&mut self, ply: usize) -> Option<Game> {
+        if self.eval_followers.is_empty() || self.chess.moves().len() == ply {
+            self.evaluating = false;
+            return None;
+        }
+
+        Some(self.chess.clone())
+    }
+
+    /// followers to send an evaluation of the position after `ply` half
+    /// moves to and how long to hold it back, nobody if the game moved on,
+    /// players only get it once the game is over and spectators with a
+    /// delay while it is in progress, the final evaluation of a search
+    /// is sent whatever the throttling
+    pub fn eval_recipients(&mut self, ply: usize, done: bool) -> Vec<(SessionId, Duration)> {
+        if self.chess.moves().len() != ply {
+            return Vec::new();
+        }
+
+        let now = Instant::now();
+        let in_progress = self.outcome.is_none();
+        let players = [self.white, self.black];
+
+        let mut recipients = Vec::new();
+        for (session_id, last_sent) in self.eval_followers.iter_mut() {
+            let is_player = players.contains(&Some(*session_id));
+            if is_player && in_progress {
+                continue;
+            }
+            if !done && last_sent.is_some_and(|sent| now - sent < EVAL_THROTTLE) {
+                continue;
+            }
+
+            *last_sent = Some(now);
+            let delay = match in_progress {
+                true => SPECTATOR_EVAL_DELAY,
+                false => Duration::ZERO,
+            };
+            recipients.push((*session_id, delay));
+        }
+
+        recipients
+    }
+
+    /// listing of the game for the lobby with the names of its players
+    pub fn summary(&self, white: Option<String>, black: Option<String>) -> GameSummary {
+        let opening = self.chess.opening();
+
+        GameSummary {
+            game_id: self.game_id.clone(),
+            name: self.name(),
+            variant: self.chess.variant(),
+            white,
+            black,
+            time_control: self.clock.as_ref().map(|clock| clock.control().clone()),
+            days_per_move: self.correspondence.map(|clock| clock.days_per_move()),
+            started: self.started,
+            plies: self.chess.moves().len(),
+            eco: opening.map(|opening| opening.eco.clone()),
+            opening: opening.map(|opening| opening.name.clone()),
+        }
+    }
+
+    /// the game in PGN, the result is `*` while the game is in progress
+    pub fn pgn(&self, white: &str, black: &str) -> String {
+        let result = match self.outcome {
+            Some(outcome) => outcome.result.to_string(),
+            None => "*".to_string(),
+        };
+
+        let headers = PgnHeaders {
+            event: format!("Chess API game {}", self.name()),
+            site: "Chess API".to_string(),
+            date: pgn_date(self.created_at),
+            white: white.to_string(),
+            black: black.to_string(),
+            result,
+            time_control: match &self.clock {
+                Some(clock) => clock.control().pgn(),
+                None => "-".to_string(),
+            },
+        };
+
+        // moves are annotated once the game is analysed
+        match &self.report {
+            ReportStatus::Ready(report) => self
+                .chess
+                .to_annotated_pgn(&headers, &report.pgn_annotations()),
+            _ => self.chess.to_pgn(&headers),
+        }
+    }
+
+    /// the game to analyse for its report once it is over,
+    /// `None` if it is in progress or already analysed
+    pub fn start_report(&mut self) -> Option<Game> {
+        if self.outcome.is_none() || !matches!(self.report, ReportStatus::NotStarted) {
+            return None;
+        }
+
+        self.report = ReportStatus::Analysing;
+        Some(self.chess.clone())
+    }
+
+    pub fn finish_report(&mut self, report: GameReport) {
+        self.report = ReportStatus::Ready(report);
+    }
+
+    /// the analysis report of the game, once the game is over
+    /// and the engine has gone through all of its moves
+    pub fn report(&self) -> Result<&GameReport, GameError> {
+        match &self.report {
+            ReportStatus::Ready(report) => Ok(report),
+            _ if self.outcome.is_none() => Err(GameError::GameInProgress(self.game_id.clone())),
+            _ => Err(GameError::ReportNotReady(self.game_id.clone())),
+        }
+    }
+
+    /// current position of the game in FEN
+    pub fn fen(&self) -> String {
+        self.chess.position().to_fen()
+    }
+
+    /// validate a move in SAN, long algebraic or UCI notation against
+    /// the current position and apply it if it is legal, only the
+    /// player of the side to move may move once both players joined,
+    /// the game outcome is updated after every move, a move made once
+    /// the clock ran out loses the game on time instead
+    pub fn play_move(
+        &mut self,
+        session_id: SessionId,
+        move_str: &str,
+    ) -> Result<GameMoveInfo, GameError> {
+        if self.outcome.is_some() {
+            return Err(GameError::GameOver(self.game_id.clone()));
+        }
+
+        let now = Instant::now();
+        if self.out_of_time(now) {
+            return Err(GameError::OutOfTime(self.side_to_move()));
+        }
+
+        let color = self
+            .player_color(session_id)
+            .ok_or_else(|| GameError::NotAPlayer(self.game_id.clone()))?;
+
+        if !self.started {
+            return Err(GameError::GameNotStarted(self.game_id.clone()));
+        }
+
+        let side_to_move = self.side_to_move();
+        if color != side_to_move {
+            return Err(GameError::NotYourTurn(side_to_move));
+        }
+
+        let before = *self.chess.position();
+        let game_move = self.chess.play(move_str).map_err(GameError::InvalidMove)?;
+        self.outcome = self.chess.outcome();
+        self.update_turn(now);
+
+        Ok(self.move_info(before, game_move))
+    }
+
+    /// play the move the bot searched from `searched`, the move is
+    /// dropped if the game moved on while the bot was thinking
+    pub fn play_bot_move(
+        &mut self,
+        searched: &Position,
+        bot_move: Move,
+    ) -> Result<GameMoveInfo, GameError> {
+        if self.bot_to_move().is_none() || self.chess.position() != searched {
+            return Err(GameError::BotMoveOutdated(self.game_id.clone()));
+        }
+
+        let now = Instant::now();
+        if self.out_of_time(now) {
+            return Err(GameError::OutOfTime(self.side_to_move()));
+        }
+
+        let before = *self.chess.position();
+        self.chess.push(bot_move);
+        self.outcome = self.chess.outcome();
+        self.update_turn(now);
+
+        Ok(self.move_info(before, bot_move))
+    }
+
+    fn move_info(&self, before: Position, game_move: Move) -> GameMoveInfo {
+        GameMoveInfo {
+            game_id: self.game_id.clone(),
+            from: game_move.from,
+            to: game_move.to,
+            promotion: game_move.promotion,
+            uci: before.uci(game_move),
+            san: before.san(game_move),
+            fen: self.fen(),
+        }
+    }
+
+    /// claim a draw by threefold repetition or the fifty-move
+    /// rule, only players in the game may claim a draw
+    pub fn claim_draw(&mut self, session_id: SessionId) -> Result<GameOverInfo, GameError> {
+        if self.outcome.is_some() {
+            return Err(GameError::GameOver(self.game_id.clone()));
+        }
+
+        if !self.is_player(session_id) {
+            return Err(GameError::NotAPlayer(self.game_id.clone()));
+        }
+
+        if !self.started {
+            return Err(GameError::GameNotStarted(self.game_id.clone()));
+        }
+
+        let now = Instant::now();
+        if self.out_of_time(now) {
+            return Err(GameError::OutOfTime(self.side_to_move()));
+        }
+
+        let reason = self
+            .chess
+            .claimable_draw()
+            .ok_or(GameError::NoDrawToClaim)?;
+        self.outcome = Some(GameOutcome::draw(reason));
+        self.update_turn(now);
+
+        self.game_over_info()
+            .ok_or_else(|| GameError::GameOver(self.game_id.clone()))
+    }
+
+    /// the player resigns the game, their opponent wins
+    pub fn resign(&mut self, session_id: SessionId) -> Result<PlayerActionInfo, GameError> {
+        let color = self.playing_color(session_id)?;
+
+        self.outcome = Some(GameOutcome::resignation(color));
+        self.update_turn(Instant::now());
+
+        Ok(self.action_info(color))
+    }
+
+    /// the player offers their opponent a draw, which holds until either
+    /// side moves, a player can only offer again once the cooldown passed
+    pub fn offer_draw(&mut self, session_id: SessionId) -> Result<PlayerActionInfo, GameError> {
+        let color = self.playing_color(session_id)?;
+
+        if self.bot.is_some() {
+            return Err(GameError::DrawOfferToBot);
+        }
+
+        if self.pending_draw_offer().is_some() {
+            return Err(GameError::DrawOfferPending);
+        }
+
+        let now = Instant::now();
+        if let Some(offered_at) = self.draw_offered_at.get(&color) {
+            let wait = DRAW_OFFER_COOLDOWN.saturating_sub(now - *offered_at);
+            if !wait.is_zero() {
+                return Err(GameError::DrawOfferTooSoon(wait.as_secs_f64().ceil() as u64));
+            }
+        }
+
+        self.draw_offer = Some((color, self.chess.moves().len()));
+        self.draw_offered_at.insert(color, now);
+
+        Ok(self.action_info(color))
+    }
+
+    /// the player accepts or declines the draw their opponent
+    /// offered, the game is drawn by agreement if they accept
+    pub fn answer_draw(
+        &mut self,
+        session_id: SessionId,
+        accept: bool,
+    ) -> Result<PlayerActionInfo, GameError> {
+        let color = self.playing_color(session_id)?;
+
+        match self.pending_draw_offer() {
+            Some(offered_by) if offered_by != color => self.draw_offer = None,
+            _ => return Err(GameError::NoDrawOffer),
+        }
+
+        if accept {
+            self.outcome = Some(GameOutcome::draw(GameEndReason::Agreement));
+            self.update_turn(Instant::now());
+        }
+
+        Ok(self.action_info(color))
+    }
+
+    /// drop a draw offer which lapsed with a move,
+    /// the side which made it if there was one
+    pub fn expire_draw_offer(&mut self) -> Option<PlayerActionInfo> {
+        match self.draw_offer {
+            Some((color, ply)) if ply != self.chess.moves().len() => {
+                self.draw_offer = None;
+                Some(self.action_info(color))
+            }
+            _ => None,
+        }
+    }
+
+    /// the player asks to take back their last move, and the reply of
+    /// their opponent if it was played, the computer agrees right away
+    pub fn request_takeback(&mut self, session_id: SessionId) -> Result<TakebackInfo, GameError> {
+        let color = self.playing_color(session_id)?;
+
+        if self.pending_takeback().is_some() {
+            return Err(GameError::TakebackPending);
+        }
+
+        let plies = self
+            .takeback_plies(color)
+            .ok_or(GameError::NoMoveToTakeBack)?;
+
+        if self.bot.is_some() {
+            self.take_back(plies);
+            return Ok(self.takeback_info(color, plies, true));
+        }
+
+        self.takeback = Some((color, self.chess.moves().len()));
+        Ok(self.takeback_info(color, plies, false))
+    }
+
+    /// the player accepts or declines the takeback their opponent
+    /// asked for, the moves are taken back if they accept
+    pub fn answer_takeback(
+        &mut self,
+        session_id: SessionId,
+        accept: bool,
+    ) -> Result<TakebackInfo, GameError> {
+        let color = self.playing_color(session_id)?;
+
+        let plies = match self.pending_takeback() {
+            Some(asked_by) if asked_by != color => self.takeback_plies(asked_by),
+            _ => None,
+        }
+        .ok_or(GameError::NoTakebackRequest)?;
+        self.takeback = None;
+
+        if accept {
+            self.take_back(plies);
+        }
+
+        Ok(self.takeback_info(color, plies, accept))
+    }
+
+    /// drop a takeback request which lapsed with a move,
+    /// the side which asked if there was one
+    pub fn expire_takeback(&mut self) -> Option<PlayerActionInfo> {
+        match self.takeback {
+            Some((color, ply)) if ply != self.chess.moves().len() => {
+                self.takeback = None;
+                Some(self.action_info(color))
+            }
+            _ => None,
+        }
+    }
+
+    /// side which asked for a takeback no move was made since
+    pub fn pending_takeback(&self) -> Option<Color> {
+        self.takeback
+            .filter(|(_, ply)| *ply == self.chess.moves().len())
+            .map(|(color, _)| color)
+    }
+
+    /// half moves to take back for the side to be back before its last
+    /// move, `None` if it has not moved since the game started, moves the
+    /// game was seeded with stay and once both sides moved the game does
+    /// not go back to where it could be aborted
+    fn takeback_plies(&self, color: Color) -> Option<usize> {
+        let plies = match self.side_to_move() == color {
+            true => 2,
+            false => 1,
+        };
+        let first_ply = match self.abortable() {
+            true => self.start_ply,
+            false => self.start_ply + 2,
+        };
+
+        (self.chess.moves().len() >= first_ply + plies).then_some(plies)
+    }
+
+    /// take back moves, the clocks are back as they were before the
+    /// first move taken back, the clock or the deadline of the side
+    /// to move runs again and pending offers are dropped
+    fn take_back(&mut self, plies: usize) {
+        for _ in 0..plies {
+            self.chess.undo();
+        }
+        self.takeback = None;
+        self.draw_offer = None;
+
+        let side_to_move = self.side_to_move();
+        self.turn_started_at = Some(Instant::now());
+        if let Some(clock) = self.clock.as_mut() {
+            let kept = self.clock_history.len().saturating_sub(plies - 1);
+            self.clock_history.truncate(kept);
+            if let Some(before) = self.clock_history.pop() {
+                *clock = before;
+            }
+            clock.start(side_to_move, Instant::now());
+        }
+        if let Some(clock) = self.correspondence.as_mut() {
+            clock.start(side_to_move, SystemTime::now());
+        }
+    }
+
+    fn takeback_info(&self, color: Color, plies: usize, taken_back: bool) -> TakebackInfo {
+        TakebackInfo {
+            game_id: self.game_id.clone(),
+            color,
+            plies,
+            fen: taken_back.then(|| self.fen()),
+        }
+    }
+
+    /// the player offers their opponent a rematch of the finished game
+    pub fn offer_rematch(&mut self, session_id: SessionId) -> Result<Color, GameError> {
+        let color = self.rematch_color(session_id)?;
+
+        if self.rematch_offer.is_some() {
+            return Err(GameError::RematchPending);
+        }
+
+        self.rematch_offer = Some(color);
+        Ok(color)
+    }
+
+    /// the player answers the rematch their opponent offered, the color
+    /// they played, the rematch is started by the game manager
+    pub fn answer_rematch(&mut self, session_id: SessionId) -> Result<Color, GameError> {
+        let color = self.rematch_color(session_id)?;
+
+        match self.rematch_offer {
+            Some(offered_by) if offered_by != color => self.rematch_offer = None,
+            _ => return Err(GameError::NoRematchOffer),
+        }
+
+        Ok(color)
+    }
+
+    /// color of a player of the game which can be rematched, once
+    /// it is over and as long as the rematch has not started
+    fn rematch_color(&self, session_id: SessionId) -> Result<Color, GameError> {
+        if self.outcome.is_none() {
+            return Err(GameError::GameInProgress(self.game_id.clone()));
+        }
+
+        let color = self
+            .player_color(session_id)
+            .ok_or_else(|| GameError::NotAPlayer(self.game_id.clone()))?;
+
+        match &self.rematch_id {
+            Some(rematch_id) => Err(GameError::RematchStarted(rematch_id.clone())),
+            None => Ok(color),
+        }
+    }
+
+    /// the rematch of the game from the same starting position with the
+    /// same clocks, the players swap colors and it starts right away
+    pub fn rematch(&mut self, game_id: SessionGameId) -> SessionGame {
+        self.rematch_id = Some(game_id.clone());
+
+        let chess = Game::new(*self.chess.start());
+        let white = self.player_name(Color::White).unwrap_or_default();
+        let black = self.player_name(Color::Black).unwrap_or_default();
+
+        let mut rematch = match self.bot {
+            // the player is seated on the other side of the bot
+            Some(bot) => {
+                let color = bot.color.opposite();
+                let name = self.player_name(color).unwrap_or_default();
+                SessionGame::new(game_id, self.player_id(color), name, chess)
+            }
+            None => SessionGame::new(game_id, self.black_id(), black, chess),
+        };
+        rematch.pairing = self.pairing.clone();
+        rematch.round = self.round + 1;
+
+        if let Some(clock) = &self.clock {
+            rematch = rematch.with_clock(clock.control().clone());
+        }
+        if let Some(clock) = self
+            .correspondence
+            .and_then(|clock| CorrespondenceClock::new(clock.days_per_move()))
+        {
+            rematch = rematch.with_correspondence(clock);
+        }
+
+        match self.bot {
+            Some(bot) => rematch.with_bot(BotOpponent {
+                color: bot.color.opposite(),
+                ..bot
+            }),
+            None => {
+                rematch.join_game(self.white_id(), white);
+                rematch
+            }
+        }
+    }
+
+    /// the player aborts the game, which is only possible
+    /// before both sides made their first move
+    pub fn abort(&self, session_id: SessionId) -> Result<GameAbortInfo, GameError> {
+        if self.outcome.is_some() {
+            return Err(GameError::GameOver(self.game_id.clone()));
+        }
+
+        let color = self
+            .player_color(session_id)
+            .ok_or_else(|| GameError::NotAPlayer(self.game_id.clone()))?;
+
+        if !self.abortable() {
+            return Err(GameError::AbortTooLate(self.game_id.clone()));
+        }
+
+        Ok(GameAbortInfo {
+            game_id: self.game_id.clone(),
+            aborted_by: Some(color),
+        })
+    }
+
+    /// the creator of a game waiting for an opponent takes it back,
+    /// a started game is aborted or played out instead
+    pub fn delete(&self, session_id: SessionId) -> Result<(), GameError> {
+        self.player_color(session_id)
+            .ok_or_else(|| GameError::NotAPlayer(self.game_id.clone()))?;
+
+        if self.outcome.is_some() {
+            return Err(GameError::GameOver(self.game_id.clone()));
+        }
+        if self.started {
+            return Err(GameError::GameInProgress(self.game_id.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// neither side made its first move yet
+    fn abortable(&self) -> bool {
+        self.chess.moves().len() < self.start_ply + 2
+    }
+
+    /// the game started and the side to move has yet to make its
+    /// first move, correspondence games have their own deadlines
+    pub fn awaiting_first_move(&self) -> bool {
+        self.started && self.outcome.is_none() && !self.is_correspondence() && self.abortable()
+    }
+
+    /// the side to move let `timeout` pass without making its
+    /// first move at `now`, the game is to be aborted
+    pub fn first_move_missed(&self, now: Instant, timeout: Duration) -> bool {
+        self.awaiting_first_move()
+            && self
+                .turn_started_at
+                .is_some_and(|since| now.saturating_duration_since(since) >= timeout)
+    }
+
+    /// side which offered a draw no move was made since
+    pub fn pending_draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+            .filter(|(_, ply)| *ply == self.chess.moves().len())
+            .map(|(color, _)| color)
+    }
+
+    /// color of a player in the game while it is in progress, the
+    /// game ends on time if the side to move ran out of it
+    fn playing_color(&mut self, session_id: SessionId) -> Result<Color, GameError> {
+        if self.outcome.is_some() {
+            return Err(GameError::GameOver(self.game_id.clone()));
+        }
+
+        let color = self
+            .player_color(session_id)
+            .ok_or_else(|| GameError::NotAPlayer(self.game_id.clone()))?;
+
+        if !self.started {
+            return Err(GameError::GameNotStarted(self.game_id.clone()));
+        }
+
+        if self.out_of_time(Instant::now()) {
+            return Err(GameError::OutOfTime(self.side_to_move()));
+        }
+
+        Ok(color)
+    }
+
+    fn action_info(&self, color: Color) -> PlayerActionInfo {
+        PlayerActionInfo {
+            game_id: self.game_id.clone(),
+            color,
+        }
+    }
+
+    /// is the side to move in check
+    pub fn in_check(&self) -> bool {
+        let position = self.chess.position();
+        position.is_in_check(position.side_to_move())
+    }
+
+    /// legal moves in the current position, only those of the piece
+    /// on the given square if one is given, none once the game is over
+    pub fn legal_moves(&self, square: Option<Square>) -> LegalMovesInfo {
+        let position = self.chess.position();
+
+        let moves = match self.outcome {
+            Some(_) => Vec::new(),
+            None => position
+                .legal_moves()
+                .into_iter()
+                .filter(|mv| match square {
+                    Some(square) => mv.from == square && !matches!(mv.kind, MoveKind::Drop(_)),
+                    None => true,
+                })
+                .map(|mv| LegalMove {
+                    from: mv.from,
+                    to: mv.to,
+                    promotion: mv.promotion,
+                    uci: position.uci(mv),
+                    san: position.san(mv),
+                })
+                .collect(),
+        };
+
+        LegalMovesInfo {
+            game_id: self.game_id.clone(),
+            square,
+            side_to_move: position.side_to_move(),
+            moves,
+        }
+    }
+
+    pub fn is_player(&self, session_id: SessionId) -> bool {
+        self.player_color(session_id).is_some()
+    }
+
+    /// color the session is playing, `None` if the
+    /// session is not a player in this game
+    pub fn player_color(&self, session_id: SessionId) -> Option<Color> {
+        if self.white == Some(session_id) {
+            Some(Color::White)
+        } else if self.black == Some(session_id) {
+            Some(Color::Black)
+        } else {
+            None
+        }
+    }
+
+    /// username of the player of a color, `None` if nobody took the seat
+    pub fn player_name(&self, color: Color) -> Option<&str> {
+        match color {
+            Color::White => self.white_name.as_deref(),
+            Color::Black => self.black_name.as_deref(),
+        }
+    }
+
+    /// token the player of a color resumes a correspondence game with
+    pub fn resume_token(&self, color: Color) -> &str {
+        match color {
+            Color::White => &self.white_token,
+            Color::Black => &self.black_token,
+        }
+    }
+
+    /// a player of a correspondence game came back with a new session
+    /// and the resume token of their seat, they take the seat again,
+    /// the color they play
+    pub fn resume(&mut self, session_id: SessionId, token: &str) -> Result<Color, GameError> {
+        if !self.is_correspondence() {
+            return Err(GameError::NotCorrespondence(self.game_id.clone()));
+        }
+
+        let color = [Color::White, Color::Black]
+            .into_iter()
+            .find(|color| self.player_name(*color).is_some() && self.resume_token(*color) == token)
+            .ok_or_else(|| GameError::InvalidResumeToken(self.game_id.clone()))?;
+
+        match color {
+            Color::White => self.white = Some(session_id),
+            Color::Black => self.black = Some(session_id),
+        }
+
+        Ok(color)
+    }
+
+    /// session ID of the player of a color, 0 if there is none
+    pub fn player_id(&self, color: Color) -> SessionId {
+        match color {
+            Color::White => self.white_id(),
+            Color::Black => self.black_id(),
         }
     }
 
+    pub fn side_to_move(&self) -> Color {
+        self.chess.position().side_to_move()
+    }
+
+    pub fn game_over_info(&self) -> Option<GameOverInfo> {
+        self.outcome.map(|outcome| GameOverInfo {
+            game_id: self.game_id.clone(),
+            result: outcome.result,
+            reason: outcome.reason,
+        })
+    }
+
     /// main join game method
     /// if there are 2 players in the game
     /// the game is set to `started` = true
-    pub fn join_game(&mut self, black: SessionId) {
+    pub fn join_game(&mut self, black: SessionId, username: &str) {
         self.black = Some(black);
+        self.black_name = Some(username.to_string());
 
         if self.num_players() == 2 {
-            self.started = true;
+            self.start();
         }
     }
 
@@ -41,10 +1465,12 @@ impl SessionGame {
         // remove player two from game
         if self.black_id() == session_id {
             self.black = None;
+            self.black_name = None;
         }
         // remove player two from game
         if self.white_id() == session_id {
             self.white = None;
+            self.white_name = None;
         }
     }
 
@@ -105,18 +1531,199 @@ impl SessionGame {
 #[derive(Debug)]
 pub struct GameManager {
     games: HashMap<SessionGameId, SessionGame>,
+    /// games which have ended, kept for a while so their
+    /// reports, PGN and rematches are available
+    finished_games: HashMap<SessionGameId, SessionGame>,
+    /// when each finished game ended, oldest first
+    finished_order: VecDeque<(Instant, SessionGameId)>,
+}
+
+impl Default for GameManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameManager {
     pub fn new() -> Self {
         let games = HashMap::new();
+        let finished_games = HashMap::new();
+
+        Self {
+            games,
+            finished_games,
+            finished_order: VecDeque::new(),
+        }
+    }
+
+    /// create a game with the creator as its first player, its new ID
+    pub fn new_game(
+        &mut self,
+        username: &str,
+        session_id: SessionId,
+        chess: Game,
+        options: NewGameOptions,
+    ) -> SessionGameId {
+        let game_id = Uuid::new_v4().to_string();
+        let mut game = SessionGame::new(game_id.clone(), session_id, username, chess);
+        if let Some(control) = options.time_control {
+            game = game.with_clock(control);
+        }
+        if let Some(clock) = options.correspondence {
+            game = game.with_correspondence(clock);
+        }
+        if let Some(bot) = options.bot {
+            game = game.with_bot(bot);
+        }
+        self.games.insert(game_id.clone(), game);
+
+        game_id
+    }
+
+    /// IDs of the active games a session plays in
+    pub fn player_games(&self, session_id: SessionId) -> Vec<SessionGameId> {
+        self.games
+            .iter()
+            .filter(|(_, game)| game.is_player(session_id))
+            .map(|(game_id, _)| game_id.clone())
+            .collect()
+    }
+
+    /// the game and its bot when the bot is to move
+    pub fn bot_turn(&self, game_id: &str) -> Option<(Game, BotOpponent)> {
+        let game = self.games.get(game_id)?;
+        game.bot_to_move().map(|bot| (game.chess.clone(), bot))
+    }
+
+    pub fn play_bot_move(
+        &mut self,
+        game_id: &str,
+        searched: &Position,
+        bot_move: Move,
+    ) -> Result<GameMoveInfo, GameError> {
+        match self.games.get_mut(game_id) {
+            Some(game) => game.play_bot_move(searched, bot_move),
+            None => Err(GameError::GameNotFound(game_id.to_string())),
+        }
+    }
 
-        Self { games }
+    /// the chess game of a finished game to analyse, nobody gets the
+    /// engine's best move while the game is played, spectators follow
+    /// its evaluation with a delay instead
+    pub fn analysis_game(&self, game_id: &str) -> Result<Game, GameError> {
+        let game = self
+            .find_game(game_id)
+            .ok_or_else(|| GameError::GameNotFound(game_id.to_string()))?;
+
+        if game.outcome.is_none() {
+            return Err(GameError::AnalysisNotAllowed(game_id.to_string()));
+        }
+
+        Ok(game.chess.clone())
+    }
+
+    /// current position in FEN of an active or finished game
+    pub fn game_fen(&self, game_id: &str) -> Result<String, GameError> {
+        self.find_game(game_id)
+            .map(|game| game.fen())
+            .ok_or_else(|| GameError::GameNotFound(game_id.to_string()))
+    }
+
+    /// get an active game, or a finished game if
+    /// there is no active game with the ID
+    pub fn find_game(&self, game_id: &str) -> Option<&SessionGame> {
+        self.games
+            .get(game_id)
+            .or_else(|| self.finished_games.get(game_id))
+    }
+
+    /// an active game, or a finished game if there
+    /// is no active game with the ID, to modify
+    fn find_game_mut(&mut self, game_id: &str) -> Option<&mut SessionGame> {
+        match self.games.get_mut(game_id) {
+            Some(game) => Some(game),
+            None => self.finished_games.get_mut(game_id),
+        }
+    }
+
+    /// follow the engine evaluation of an active or finished game
+    pub fn follow_eval(&mut self, game_id: &str, session_id: SessionId) -> Result<(), GameError> {
+        let game = self
+            .find_game_mut(game_id)
+            .ok_or_else(|| GameError::GameNotFound(game_id.to_string()))?;
+        game.eval_followers.entry(session_id).or_insert(None);
+
+        Ok(())
+    }
+
+    pub fn unfollow_eval(&mut self, game_id: &str, session_id: SessionId) -> Result<(), GameError> {
+        let game = self
+            .find_game_mut(game_id)
+            .ok_or_else(|| GameError::GameNotFound(game_id.to_string()))?;
+        game.eval_followers.remove(&session_id);
+
+        Ok(())
+    }
+
+    /// stop a session following the evaluation of any game
+    pub fn unfollow_all_evals(&mut self, session_id: SessionId) {
+        for game in self
+            .games
+            .values_mut()
+            .chain(self.finished_games.values_mut())
+        {
+            game.eval_followers.remove(&session_id);
+        }
+    }
+
+    pub fn start_report(&mut self, game_id: &str) -> Option<Game> {
+        self.find_game_mut(game_id)?.start_report()
+    }
+
+    pub fn finish_report(&mut self, game_id: &str, report: GameReport) {
+        if let Some(game) = self.find_game_mut(game_id) {
+            game.finish_report(report);
+        }
+    }
+
+    /// analysis report of a finished game
+    pub fn report(&self, game_id: &str) -> Result<&GameReport, GameError> {
+        self.find_game(game_id)
+            .ok_or_else(|| GameError::GameNotFound(game_id.to_string()))?
+            .report()
+    }
+
+    pub fn start_eval(&mut self, game_id: &str) -> Option<Game> {
+        self.find_game_mut(game_id)?.start_eval()
+    }
+
+    pub fn next_eval(&mut self, game_id: &str, ply: usize) -> Option<Game> {
+        self.find_game_mut(game_id)?.next_eval(ply)
     }
 
-    pub fn new_game(&mut self, username: &str, session_id: SessionId) {
-        let game = SessionGame::new(username.to_string(), session_id);
-        self.games.insert(username.to_string(), game);
+    /// the evaluation of the position after `ply` half moves
+    /// and the followers to send it to with their delay
+    pub fn eval_recipients(
+        &mut self,
+        game_id: &str,
+        ply: usize,
+        info: &UciInfo,
+        done: bool,
+    ) -> Option<(GameEval, Vec<(SessionId, Duration)>)> {
+        let game = self.find_game_mut(game_id)?;
+        let recipients = game.eval_recipients(ply, done);
+        if recipients.is_empty() {
+            return None;
+        }
+
+        let eval = GameEval {
+            game_id: game_id.to_string(),
+            ply,
+            fen: game.fen(),
+            info: info.clone(),
+            done,
+        };
+        Some((eval, recipients))
     }
 
     pub fn get_game(&mut self, game_id: &str) -> Option<&mut SessionGame> {
@@ -127,13 +1734,291 @@ impl GameManager {
         &self.games
     }
 
-    pub fn join_game(&mut self, game_id: &str, session_id: SessionId) {
-        // TODO:
-        // handle case if game is already full
-        if let Some(game) = self.get_game(game_id) {
-            if game.is_joinable() {
-                game.join_game(session_id)
+    /// join a game which is waiting for an opponent,
+    /// `true` if the session joined the game
+    pub fn join_game(&mut self, game_id: &str, session_id: SessionId, username: &str) -> bool {
+        match self.get_game(game_id) {
+            Some(game) if game.is_joinable() => {
+                game.join_game(session_id, username);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// end an active game whose side to move ran out of time,
+    /// `true` while the game goes on with its clocks running
+    pub fn check_flag(&mut self, game_id: &str) -> bool {
+        match self.games.get_mut(game_id) {
+            Some(game) => {
+                game.check_flag(Instant::now());
+                game.clock_running()
+            }
+            None => false,
+        }
+    }
+
+    /// end the correspondence games whose side to move let its
+    /// deadline pass, the IDs of the games which ended
+    pub fn check_deadlines(&mut self, now: SystemTime) -> Vec<SessionGameId> {
+        self.games
+            .iter_mut()
+            .filter_map(|(game_id, game)| game.check_deadline(now).then(|| game_id.clone()))
+            .collect()
+    }
+
+    /// a player of an active correspondence game came back with
+    /// a new session and their resume token, the color they play
+    pub fn resume_game(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+        token: &str,
+    ) -> Result<Color, GameError> {
+        match self.games.get_mut(game_id) {
+            Some(game) => game.resume(session_id, token),
+            None => Err(GameError::GameNotFound(game_id.to_string())),
+        }
+    }
+
+    /// IDs of the active correspondence games a user plays in
+    pub fn correspondence_games(&self, username: &str) -> Vec<SessionGameId> {
+        self.games
+            .iter()
+            .filter(|(_, game)| game.is_correspondence())
+            .filter(|(_, game)| {
+                [Color::White, Color::Black]
+                    .into_iter()
+                    .any(|color| game.player_name(color) == Some(username))
+            })
+            .map(|(game_id, _)| game_id.clone())
+            .collect()
+    }
+
+    pub fn play_move(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+        move_str: &str,
+    ) -> Result<GameMoveInfo, GameError> {
+        match self.games.get_mut(game_id) {
+            Some(game) => game.play_move(session_id, move_str),
+            None => Err(GameError::GameNotFound(game_id.to_string())),
+        }
+    }
+
+    pub fn claim_draw(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+    ) -> Result<GameOverInfo, GameError> {
+        match self.games.get_mut(game_id) {
+            Some(game) => game.claim_draw(session_id),
+            None => Err(GameError::GameNotFound(game_id.to_string())),
+        }
+    }
+
+    pub fn resign(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+    ) -> Result<PlayerActionInfo, GameError> {
+        match self.games.get_mut(game_id) {
+            Some(game) => game.resign(session_id),
+            None => Err(GameError::GameNotFound(game_id.to_string())),
+        }
+    }
+
+    pub fn offer_draw(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+    ) -> Result<PlayerActionInfo, GameError> {
+        match self.games.get_mut(game_id) {
+            Some(game) => game.offer_draw(session_id),
+            None => Err(GameError::GameNotFound(game_id.to_string())),
+        }
+    }
+
+    pub fn answer_draw(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+        accept: bool,
+    ) -> Result<PlayerActionInfo, GameError> {
+        match self.games.get_mut(game_id) {
+            Some(game) => game.answer_draw(session_id, accept),
+            None => Err(GameError::GameNotFound(game_id.to_string())),
+        }
+    }
+
+    /// drop the draw offer of an active game which lapsed with a move
+    pub fn expire_draw_offer(&mut self, game_id: &str) -> Option<PlayerActionInfo> {
+        self.games.get_mut(game_id)?.expire_draw_offer()
+    }
+
+    pub fn request_takeback(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+    ) -> Result<TakebackInfo, GameError> {
+        match self.games.get_mut(game_id) {
+            Some(game) => game.request_takeback(session_id),
+            None => Err(GameError::GameNotFound(game_id.to_string())),
+        }
+    }
+
+    pub fn answer_takeback(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+        accept: bool,
+    ) -> Result<TakebackInfo, GameError> {
+        match self.games.get_mut(game_id) {
+            Some(game) => game.answer_takeback(session_id, accept),
+            None => Err(GameError::GameNotFound(game_id.to_string())),
+        }
+    }
+
+    /// drop the takeback request of an active game which lapsed with a move
+    pub fn expire_takeback(&mut self, game_id: &str) -> Option<PlayerActionInfo> {
+        self.games.get_mut(game_id)?.expire_takeback()
+    }
+
+    /// offer the opponent a rematch of a finished game, the
+    /// computer takes it and the rematch starts right away
+    pub fn offer_rematch(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+    ) -> Result<RematchInfo, GameError> {
+        let game = self.finished_game_mut(game_id)?;
+        let color = game.offer_rematch(session_id)?;
+
+        if game.bot().is_some() {
+            return Ok(self.start_rematch(game_id, color));
+        }
+
+        Ok(RematchInfo {
+            game_id: game_id.to_string(),
+            color,
+            rematch_id: None,
+        })
+    }
+
+    /// accept or decline the rematch the opponent offered,
+    /// the rematch starts if it is accepted
+    pub fn answer_rematch(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+        accept: bool,
+    ) -> Result<RematchInfo, GameError> {
+        let color = self
+            .finished_game_mut(game_id)?
+            .answer_rematch(session_id)?;
+
+        if accept {
+            return Ok(self.start_rematch(game_id, color));
+        }
+
+        Ok(RematchInfo {
+            game_id: game_id.to_string(),
+            color,
+            rematch_id: None,
+        })
+    }
+
+    /// start the rematch of a finished game under a new ID
+    fn start_rematch(&mut self, game_id: &str, color: Color) -> RematchInfo {
+        let rematch_id = Uuid::new_v4().to_string();
+
+        if let Some(game) = self.finished_games.get_mut(game_id) {
+            let rematch = game.rematch(rematch_id.clone());
+            self.games.insert(rematch_id.clone(), rematch);
+        }
+
+        RematchInfo {
+            game_id: game_id.to_string(),
+            color,
+            rematch_id: Some(rematch_id),
+        }
+    }
+
+    /// a finished game to modify
+    fn finished_game_mut(&mut self, game_id: &str) -> Result<&mut SessionGame, GameError> {
+        if self.games.contains_key(game_id) {
+            return Err(GameError::GameInProgress(game_id.to_string()));
+        }
+
+        self.finished_games
+            .get_mut(game_id)
+            .ok_or_else(|| GameError::GameNotFound(game_id.to_string()))
+    }
+
+    /// abort an active game on behalf of one of its players, the
+    /// game is dropped without a result
+    pub fn abort(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+    ) -> Result<GameAbortInfo, GameError> {
+        let info = match self.games.get(game_id) {
+            Some(game) => game.abort(session_id)?,
+            None => return Err(GameError::GameNotFound(game_id.to_string())),
+        };
+        self.games.remove(game_id);
+
+        Ok(info)
+    }
+
+    /// abort an active game whose side to move did not make its first
+    /// move within `timeout`, the game is dropped without a result
+    pub fn abort_if_idle(&mut self, game_id: &str, timeout: Duration) -> Option<GameAbortInfo> {
+        let game = self.games.get(game_id)?;
+        if !game.first_move_missed(Instant::now(), timeout) {
+            return None;
+        }
+        self.games.remove(game_id);
+
+        Some(GameAbortInfo {
+            game_id: game_id.to_string(),
+            aborted_by: None,
+        })
+    }
+
+    /// the game started and waits for the first move of a side
+    pub fn awaiting_first_move(&self, game_id: &str) -> bool {
+        self.games
+            .get(game_id)
+            .is_some_and(|game| game.awaiting_first_move())
+    }
+
+    /// move a game which has an outcome out of the active
+    /// game list and into the finished games
+    pub fn finish_game(&mut self, game_id: &str) -> Option<GameOverInfo> {
+        let info = self.games.get(game_id)?.game_over_info()?;
+
+        if let Some(game) = self.games.remove(game_id) {
+            self.finished_games.insert(game_id.to_string(), game);
+            self.finished_order
+                .push_back((Instant::now(), game_id.to_string()));
+        }
+        self.prune_finished_games(Instant::now());
+
+        Some(info)
+    }
+
+    /// drop the finished games which ended longer than the retention
+    /// ago, and the oldest ones while there are too many
+    pub fn prune_finished_games(&mut self, now: Instant) {
+        while let Some((ended_at, game_id)) = self.finished_order.front() {
+            let expired = now.saturating_duration_since(*ended_at) >= FINISHED_GAME_RETENTION;
+            if !expired && self.finished_order.len() <= MAX_FINISHED_GAMES {
+                break;
             }
+            self.finished_games.remove(game_id);
+            self.finished_order.pop_front();
         }
     }
 
@@ -149,6 +2034,33 @@ impl GameManager {
         }
     }
 
+    /// legal moves of an active or finished game, for the piece
+    /// on a square given in algebraic notation, eg. `e2`
+    pub fn legal_moves(
+        &self,
+        game_id: &str,
+        square: Option<&str>,
+    ) -> Result<LegalMovesInfo, GameError> {
+        let square = match square {
+            Some(square) => Some(
+                Square::parse(square)
+                    .ok_or_else(|| GameError::InvalidSquare(square.to_string()))?,
+            ),
+            None => None,
+        };
+
+        self.find_game(game_id)
+            .map(|game| game.legal_moves(square))
+            .ok_or_else(|| GameError::GameNotFound(game_id.to_string()))
+    }
+
+    pub fn in_check(&self, game_id: &str) -> bool {
+        match self.games.get(game_id) {
+            Some(game) => game.in_check(),
+            None => false,
+        }
+    }
+
     pub fn opponent_id(&self, game_id: &str, session_id: SessionId) -> SessionId {
         if let Some(game) = self.games.get(game_id) {
             game.opponent_id(session_id)
@@ -157,31 +2069,34 @@ impl GameManager {
         }
     }
 
-    pub fn delete_game(&mut self, game_id: &str) {
+    /// delete a game which is still waiting for an opponent,
+    /// only its player can
+    pub fn delete_game(&mut self, game_id: &str, session_id: SessionId) -> Result<(), GameError> {
+        match self.games.get(game_id) {
+            Some(game) => game.delete(session_id)?,
+            None => return Err(GameError::GameNotFound(game_id.to_string())),
+        }
         self.games.remove(game_id);
+
+        Ok(())
     }
 
-    pub fn available_games(&self) -> Vec<String> {
-        // build vector of strings of available games
+    pub fn available_games(&self) -> Vec<&SessionGame> {
+        // only games that are joinable
         self.games
-            .iter()
-            .map(|game| game.1)
-            .collect::<Vec<&SessionGame>>()
-            // all games collected
-            .into_iter()
+            .values()
             .filter(|game| game.is_joinable())
-            // only games that are joinable filtered
-            .into_iter()
-            .map(|game| game.game_id.clone())
-            // collected filtered games as string of game names
-            .collect::<Vec<String>>()
+            .collect()
     }
 
-    pub fn all_games(&self) -> Vec<String> {
-        // build vector of strings of available games
-        self.games
-            .iter()
-            .map(|game| game.1.game_id.clone())
-            .collect::<Vec<String>>()
+    pub fn all_games(&self) -> Vec<&SessionGame> {
+        self.games.values().collect()
     }
 }
+
+/// seconds since the Unix epoch
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default()
+}
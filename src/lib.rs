@@ -0,0 +1,19 @@
+//! Chess rules, game clocks, the UCI engine bridge and game reports,
+//! and the websocket chat and game server built on them, shared by
+//! the server binary, the perft bench and tests
+
+pub mod app;
+pub mod bot;
+pub mod chess;
+pub mod clock;
+pub mod constants;
+pub mod game;
+pub mod macros;
+pub mod message;
+pub mod report;
+pub mod routes;
+pub mod server;
+pub mod session;
+pub mod timer;
+pub mod uci;
+pub mod utils;
@@ -1,25 +1,15 @@
 #![allow(clippy::from_over_into)]
 
-use dotenv::dotenv;
-use std::sync::{atomic::AtomicUsize, Arc, Mutex};
-
+use actix::Actor;
 use actix_cors::Cors;
 use actix_files::Files;
-use actix_web::{middleware::Logger, web, App, HttpServer};
-
-mod app;
-mod constants;
-mod game;
-mod macros;
-mod message;
-mod routes;
-mod server;
-mod session;
-mod utils;
-
-use app::new_app_state;
-use routes::{register_chat_routes, register_server_routes};
-use utils::print_log_levels;
+use actix_web::{middleware::Logger, App, HttpServer};
+use dotenv::dotenv;
+
+use chess_api::app::new_app_state;
+use chess_api::routes::{register_chat_routes, register_server_routes};
+use chess_api::timer::DeadlineTimer;
+use chess_api::utils::print_log_levels;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -38,6 +28,9 @@ async fn main() -> std::io::Result<()> {
 
     let app_state = new_app_state();
 
+    // correspondence games run out of time whether anybody is connected
+    DeadlineTimer::new(app_state.chat_server.clone()).start();
+
     log::info!("starting HTTP server at http://{host}:{port}");
 
     HttpServer::new(move || {
@@ -1,9 +1,9 @@
 use actix_web::{http::header::ContentType, HttpResponse};
 
 use actix::prelude::*;
-use actix_web_actors::ws;
 use serde::Serialize;
 use std::fmt::Display;
+use std::time::Duration;
 
 use crate::session::SessionId;
 
@@ -22,12 +22,35 @@ pub enum MessageType {
     SelfInfo,
 
     // Game Messages
+    GameCreated,
     GameMove,
     AllGameList,
     AvailableGameList,
-    GameChat,
     GameJoin,
     GameLeave,
+    GameCheck,
+    GameOver,
+    GameFen,
+    GamePgn,
+    GameLegalMoves,
+    GameAnalysis,
+    GameEval,
+    GameReport,
+    GameClock,
+    GameDeadline,
+    GameResign,
+    GameDrawOffer,
+    GameDrawAccept,
+    GameDrawDecline,
+    GameDrawExpire,
+    GameTakebackRequest,
+    GameTakebackAccept,
+    GameTakebackDecline,
+    GameTakebackExpire,
+    GameRematchOffer,
+    GameRematchAccept,
+    GameRematchDecline,
+    GameAbort,
 }
 
 /// Chat server sends this messages to session
@@ -40,6 +63,14 @@ pub struct Message {
     pub content: String,
 }
 
+/// Message the session holds back for a while before sending it on
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct DelayedMessage {
+    pub message: Message,
+    pub delay: Duration,
+}
+
 impl Message {
     pub fn to_http(&self) -> HttpResponse {
         if self.msg_type == MessageType::Error {
@@ -0,0 +1,221 @@
+use derive_more::Display;
+use serde::Serialize;
+
+use crate::chess::pgn::PgnAnnotation;
+use crate::chess::{Color, Game, GameResult, Position};
+use crate::uci::{UciScore, UciSearch};
+
+/// Evaluations are capped at this many centipawns, a mate counts as it
+const MAX_CENTIPAWNS: i32 = 1000;
+
+/// Points of winning chance a move has to lose to be judged
+/// an inaccuracy, a mistake or a blunder
+const INACCURACY_WIN_LOSS: f64 = 5.0;
+const MISTAKE_WIN_LOSS: f64 = 10.0;
+const BLUNDER_WIN_LOSS: f64 = 15.0;
+
+/// How bad a move was, judged by the winning chance it lost
+#[derive(Serialize, Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveJudgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveJudgement {
+    fn from_win_loss(win_loss: f64) -> Option<MoveJudgement> {
+        if win_loss >= BLUNDER_WIN_LOSS {
+            Some(MoveJudgement::Blunder)
+        } else if win_loss >= MISTAKE_WIN_LOSS {
+            Some(MoveJudgement::Mistake)
+        } else if win_loss >= INACCURACY_WIN_LOSS {
+            Some(MoveJudgement::Inaccuracy)
+        } else {
+            None
+        }
+    }
+
+    /// numeric annotation glyph, `?!`, `?` and `??`
+    pub fn nag(self) -> u8 {
+        match self {
+            MoveJudgement::Inaccuracy => 6,
+            MoveJudgement::Mistake => 2,
+            MoveJudgement::Blunder => 4,
+        }
+    }
+}
+
+/// A move of the game as the engine saw it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MoveReport {
+    /// half moves played before the move
+    pub ply: usize,
+    pub color: Color,
+    pub san: String,
+    /// evaluation after the move in centipawns from white's point
+    /// of view, capped at a thousand which is also a mate
+    pub eval: i32,
+    pub centipawn_loss: u32,
+    /// from 0 to 100, how much of its winning chance the move kept
+    pub accuracy: f64,
+    pub judgement: Option<MoveJudgement>,
+    /// the engine's move in SAN when a judged move was not it
+    pub best_move: Option<String>,
+}
+
+/// Summary of the moves of one side
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct PlayerReport {
+    /// mean accuracy of the side's moves
+    pub accuracy: f64,
+    pub average_centipawn_loss: u32,
+    pub inaccuracies: u32,
+    pub mistakes: u32,
+    pub blunders: u32,
+}
+
+/// Analysis of a finished game, every move is compared
+/// with the engine's evaluation of the position before it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct GameReport {
+    pub white: PlayerReport,
+    pub black: PlayerReport,
+    pub moves: Vec<MoveReport>,
+}
+
+impl GameReport {
+    /// report on the game from the engine's searches of each of its
+    /// positions, `searches[i]` is the search of the position after
+    /// `i` half moves, positions which end the game need no search
+    pub fn new(game: &Game, searches: &[UciSearch]) -> GameReport {
+        let mut moves = Vec::new();
+        let mut position = *game.start();
+        let mut eval_before = white_centipawns(&position, searches.first());
+
+        for (ply, mv) in game.moves().iter().enumerate() {
+            let color = position.side_to_move();
+            let after = position.make_move(*mv);
+            let eval_after = white_centipawns(&after, searches.get(ply + 1));
+
+            // evaluations from the mover's point of view
+            let sign = match color {
+                Color::White => 1,
+                Color::Black => -1,
+            };
+            let (before, now) = (sign * eval_before, sign * eval_after);
+
+            let win_loss = (win_chance(before) - win_chance(now)).max(0.0);
+            let accuracy = 103.1668 * (-0.04354 * win_loss).exp() - 3.1669;
+            let judgement = MoveJudgement::from_win_loss(win_loss);
+
+            let engine_move = searches.get(ply).and_then(|search| search.best_move);
+            let best_move = match (judgement, engine_move) {
+                (Some(_), Some(best)) if best != *mv => Some(position.san(best)),
+                _ => None,
+            };
+
+            moves.push(MoveReport {
+                ply,
+                color,
+                san: position.san(*mv),
+                eval: eval_after,
+                centipawn_loss: (before - now).max(0) as u32,
+                accuracy: round_tenth(accuracy.clamp(0.0, 100.0)),
+                judgement,
+                best_move,
+            });
+
+            position = after;
+            eval_before = eval_after;
+        }
+
+        GameReport {
+            white: player_report(&moves, Color::White),
+            black: player_report(&moves, Color::Black),
+            moves,
+        }
+    }
+
+    /// annotations of the moves for the PGN export, the evaluation
+    /// after every move and glyphs and the better move for judged moves
+    pub fn pgn_annotations(&self) -> Vec<PgnAnnotation> {
+        self.moves
+            .iter()
+            .map(|report| {
+                let mut comment = format!("[%eval {:.2}]", report.eval as f64 / 100.0);
+                if let Some(judgement) = report.judgement {
+                    comment.push_str(&format!(" {judgement}."));
+                }
+                if let Some(best_move) = &report.best_move {
+                    comment.push_str(&format!(" {best_move} was best."));
+                }
+
+                PgnAnnotation {
+                    nags: report.judgement.iter().map(|j| j.nag()).collect(),
+                    comment: Some(comment),
+                }
+            })
+            .collect()
+    }
+}
+
+/// evaluation of a position in centipawns from white's point of view,
+/// the result for positions which end the game, the engine's score
+/// of its search otherwise
+fn white_centipawns(position: &Position, search: Option<&UciSearch>) -> i32 {
+    if let Some(outcome) = position.outcome() {
+        return match outcome.result {
+            GameResult::WhiteWins => MAX_CENTIPAWNS,
+            GameResult::BlackWins => -MAX_CENTIPAWNS,
+            GameResult::Draw => 0,
+        };
+    }
+
+    let score = search
+        .and_then(|search| search.info.as_ref())
+        .and_then(|info| info.score);
+    let centipawns = match score {
+        Some(UciScore::Cp(centipawns)) => centipawns.clamp(-MAX_CENTIPAWNS, MAX_CENTIPAWNS),
+        Some(UciScore::Mate(moves)) if moves > 0 => MAX_CENTIPAWNS,
+        Some(UciScore::Mate(_)) => -MAX_CENTIPAWNS,
+        None => 0,
+    };
+
+    match position.side_to_move() {
+        Color::White => centipawns,
+        Color::Black => -centipawns,
+    }
+}
+
+/// winning chance in percent of an evaluation in centipawns
+fn win_chance(centipawns: i32) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * centipawns as f64).exp()) - 1.0)
+}
+
+fn player_report(moves: &[MoveReport], color: Color) -> PlayerReport {
+    let moves: Vec<&MoveReport> = moves.iter().filter(|mv| mv.color == color).collect();
+    if moves.is_empty() {
+        return PlayerReport::default();
+    }
+
+    let count = |judgement| {
+        moves
+            .iter()
+            .filter(|mv| mv.judgement == Some(judgement))
+            .count() as u32
+    };
+    let total_loss: u32 = moves.iter().map(|mv| mv.centipawn_loss).sum();
+    let total_accuracy: f64 = moves.iter().map(|mv| mv.accuracy).sum();
+
+    PlayerReport {
+        accuracy: round_tenth(total_accuracy / moves.len() as f64),
+        average_centipawn_loss: (total_loss as f64 / moves.len() as f64).round() as u32,
+        inaccuracies: count(MoveJudgement::Inaccuracy),
+        mistakes: count(MoveJudgement::Mistake),
+        blunders: count(MoveJudgement::Blunder),
+    }
+}
+
+fn round_tenth(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
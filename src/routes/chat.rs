@@ -1,13 +1,15 @@
-use actix_web::{get, web, web::scope, Error, HttpRequest, Responder, Scope};
+use actix_web::{
+    get, http::header, web, web::scope, Error, HttpRequest, HttpResponse, Responder, Scope,
+};
 use actix_web_actors::ws;
 use rand::{self, Rng};
 use std::time::Instant;
 
 use crate::app::AppState;
 use crate::session::{self, SessionId};
-use crate::{server, unlock};
+use crate::unlock;
 
-use crate::message::{Message, MessageType};
+use crate::message::{Json, Message, MessageType};
 
 /// Entry point for our websocket route
 #[get("/ws/{username}")]
@@ -26,7 +28,6 @@ async fn chat_route(
             id,
             hb: Instant::now(),
             room: "main".to_owned(),
-            game: "none".to_string(),
             username: name.to_owned(),
             chat_server: srv.chat_server.clone(),
         },
@@ -38,8 +39,6 @@ async fn chat_route(
 #[get("/check-session/{session_id}")]
 async fn check_session(
     session_id: web::Path<SessionId>,
-    req: HttpRequest,
-    stream: web::Payload,
     srv: web::Data<AppState>,
 ) -> impl Responder {
     let chat_server = unlock!(srv.chat_server);
@@ -94,8 +93,118 @@ async fn check_username(name: web::Path<String>, srv: web::Data<AppState>) -> im
     }
 }
 
+/// Current position of an active or finished game in FEN, the game
+/// is given by its UUID, eg. `/game-fen/0b5a3d52-6f0e-4c1e-9a43-8f1d2c7e6b90`
+#[get("/game-fen/{game_id}")]
+async fn game_fen(game_id: web::Path<String>, srv: web::Data<AppState>) -> impl Responder {
+    let chat_server = unlock!(srv.chat_server);
+
+    let msg = match chat_server.game_fen(&game_id) {
+        Ok(fen) => Message {
+            msg_type: MessageType::GameFen,
+            from_id: 0,
+            username: "server".to_string(),
+            content: fen,
+        },
+        Err(err) => Message {
+            msg_type: MessageType::Error,
+            from_id: 0,
+            username: "server".to_string(),
+            content: err.to_string(),
+        },
+    };
+
+    msg.to_http()
+}
+
+/// All legal moves in the current position of a game,
+/// eg. `/legal-moves/0b5a3d52-6f0e-4c1e-9a43-8f1d2c7e6b90`
+#[get("/legal-moves/{game_id}")]
+async fn legal_moves(game_id: web::Path<String>, srv: web::Data<AppState>) -> impl Responder {
+    legal_moves_message(&srv, &game_id, None).to_http()
+}
+
+/// Legal moves of the piece on a square,
+/// eg. `/legal-moves/0b5a3d52-6f0e-4c1e-9a43-8f1d2c7e6b90/e2`
+#[get("/legal-moves/{game_id}/{square}")]
+async fn square_legal_moves(
+    path: web::Path<(String, String)>,
+    srv: web::Data<AppState>,
+) -> impl Responder {
+    let (game_id, square) = path.into_inner();
+    legal_moves_message(&srv, &game_id, Some(&square)).to_http()
+}
+
+fn legal_moves_message(srv: &AppState, game_id: &str, square: Option<&str>) -> Message {
+    let chat_server = unlock!(srv.chat_server);
+
+    match chat_server.legal_moves(game_id, square) {
+        Ok(info) => Message {
+            msg_type: MessageType::GameLegalMoves,
+            from_id: 0,
+            username: "server".to_string(),
+            content: info.to_json(),
+        },
+        Err(err) => Message {
+            msg_type: MessageType::Error,
+            from_id: 0,
+            username: "server".to_string(),
+            content: err.to_string(),
+        },
+    }
+}
+
+/// Download an active or finished game as a PGN file named
+/// after its UUID, eg. `/game-pgn/0b5a3d52-6f0e-4c1e-9a43-8f1d2c7e6b90`
+#[get("/game-pgn/{game_id}")]
+async fn game_pgn(game_id: web::Path<String>, srv: web::Data<AppState>) -> impl Responder {
+    let chat_server = unlock!(srv.chat_server);
+
+    match chat_server.game_pgn(&game_id) {
+        Ok(pgn) => HttpResponse::Ok()
+            .content_type("application/x-chess-pgn")
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{game_id}.pgn\""),
+            ))
+            .body(pgn),
+        Err(err) => Message {
+            msg_type: MessageType::Error,
+            from_id: 0,
+            username: "server".to_string(),
+            content: err.to_string(),
+        }
+        .to_http(),
+    }
+}
+
+/// Analysis report of a finished game, with the accuracy of both
+/// players and the inaccuracies, mistakes and blunders of each move,
+/// eg. `/game-report/0b5a3d52-6f0e-4c1e-9a43-8f1d2c7e6b90`
+#[get("/game-report/{game_id}")]
+async fn game_report(game_id: web::Path<String>, srv: web::Data<AppState>) -> impl Responder {
+    let chat_server = unlock!(srv.chat_server);
+
+    let msg = match chat_server.game_report(&game_id) {
+        Ok(report) => Message {
+            msg_type: MessageType::GameReport,
+            from_id: 0,
+            username: "server".to_string(),
+            content: report,
+        },
+        Err(err) => Message {
+            msg_type: MessageType::Error,
+            from_id: 0,
+            username: "server".to_string(),
+            content: err.to_string(),
+        },
+    };
+
+    msg.to_http()
+}
+
 #[get("/sessions")]
-async fn sessions(req: HttpRequest, srv: web::Data<AppState>) -> impl Responder {
+async fn sessions(srv: web::Data<AppState>) -> impl Responder {
     // check server for names
     let chat_server = srv.chat_server.lock().unwrap();
 
@@ -121,5 +230,10 @@ pub fn register_chat_routes() -> Scope {
         .service(check_session)
         .service(sessions)
         .service(check_username)
+        .service(game_fen)
+        .service(game_pgn)
+        .service(game_report)
+        .service(legal_moves)
+        .service(square_legal_moves)
         .service(chat_route)
 }
@@ -5,14 +5,19 @@ use std::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
+    time::{Duration, Instant, SystemTime},
 };
 
-use crate::game::{GameManager, SessionGame};
-use crate::session::{SessionId, WsSession};
-use crate::{
-    message::{Message, MessageType},
-    session,
+use crate::bot::BotOpponent;
+use crate::chess::{Color, Game, Move, Position};
+use crate::game::{
+    GameAbortInfo, GameAnalysis, GameError, GameManager, GameMoveInfo, GameSummary, LegalMovesInfo,
+    NewGameOptions, RematchInfo, SessionGame,
 };
+use crate::message::{DelayedMessage, Json, Message, MessageType};
+use crate::report::GameReport;
+use crate::session::{SessionId, WsSession};
+use crate::uci::UciInfo;
 
 #[derive(Debug)]
 pub struct ChatServer {
@@ -47,6 +52,29 @@ impl ChatServer {
             .insert(session_id, (username.to_string(), addr));
 
         self.join_room("main", session_id, username);
+
+        // let a returning player know about the
+        // correspondence games waiting for them
+        let games: Vec<String> = self
+            .game_manager
+            .correspondence_games(username)
+            .iter()
+            .filter_map(|game_id| {
+                self.game_manager
+                    .find_game(game_id)
+                    .map(|game| format!("{} ({})", game.name(), game_id))
+            })
+            .collect();
+        if !games.is_empty() {
+            let msg = self.new_server_msg(
+                MessageType::Status,
+                &format!(
+                    "Your correspondence games {} go on, resume one with /resume-game <game id> <resume token>",
+                    games.join(", ")
+                ),
+            );
+            self.send_client_msg(session_id, msg);
+        }
     }
 
     pub fn disconnect(&mut self, id: SessionId) {
@@ -60,6 +88,19 @@ impl ChatServer {
 
             self.leave_all_games(id);
 
+            // correspondence games go on without the player
+            for game in self.game_manager.get_games().values() {
+                if game.is_correspondence() && game.is_player(id) {
+                    let msg = self.new_server_msg(
+                        MessageType::Status,
+                        &format!("{username} disconnected, the correspondence game goes on"),
+                    );
+                    self.send_client_msg(game.opponent_id(id), msg);
+                }
+            }
+
+            self.game_manager.unfollow_all_evals(id);
+
             // decrement visitor count
             self.visitor_count.fetch_sub(1, Ordering::SeqCst);
         }
@@ -97,12 +138,10 @@ impl ChatServer {
     pub fn join_room(&mut self, room_name: &str, session_id: SessionId, username: &str) {
         self.leave_all_rooms(session_id, username);
 
-        self.leave_all_games(session_id);
-
         // join room which already exists or create new one
         self.rooms
             .entry(room_name.to_string())
-            .or_insert_with(HashSet::new)
+            .or_default()
             .insert(session_id);
 
         // send message to client that joined room,
@@ -208,14 +247,36 @@ impl ChatServer {
     // Game methods
     // ---
 
-    pub fn new_game(&mut self, session_id: SessionId, username: &str) {
+    pub fn new_game(
+        &mut self,
+        session_id: SessionId,
+        username: &str,
+        chess: Game,
+        options: NewGameOptions,
+    ) -> Option<GameSummary> {
         self.leave_all_rooms(session_id, username);
 
         self.join_room("in_game", session_id, username);
 
-        self.game_manager.new_game(username, session_id);
+        let game_id = self
+            .game_manager
+            .new_game(username, session_id, chess, options);
+
+        // the clocks of a game against the computer start right away
+        self.send_clock(&game_id);
+        self.send_deadline(&game_id);
+        self.send_resume_tokens(&game_id);
 
         self.broadcast_games();
+
+        self.game_manager
+            .find_game(&game_id)
+            .map(|game| self.summary(game))
+    }
+
+    /// IDs of the active games the session plays in
+    pub fn player_games(&self, session_id: SessionId) -> Vec<String> {
+        self.game_manager.player_games(session_id)
     }
 
     pub fn leave_game(&mut self, game_id: &str, session_id: SessionId) {
@@ -232,32 +293,487 @@ impl ChatServer {
         self.broadcast_games();
     }
 
-    pub fn join_game(&mut self, session_id: SessionId, game_id: &str, username: &str) {
+    /// Join a game waiting for an opponent, `true` if the session
+    /// joined it, the game and its clocks start
+    pub fn join_game(&mut self, session_id: SessionId, game_id: &str, username: &str) -> bool {
+        // a game which can not be joined leaves the session where it was
+        if !self.game_manager.join_game(game_id, session_id, username) {
+            return false;
+        }
+
         // set active room on server as `lobby`
         self.join_room("lobby", session_id, username);
-        self.game_manager.join_game(game_id, session_id);
 
         // notify opponent of joining game
         let opponent_id = self.game_manager.opponent_id(game_id, session_id);
         let msg = self.new_server_msg(MessageType::GameJoin, "Opponent joined the game");
         self.send_client_msg(opponent_id, msg);
 
+        self.send_clock(game_id);
+        self.send_deadline(game_id);
+        self.send_resume_tokens(game_id);
+
         self.broadcast_games();
+
+        true
     }
 
-    pub fn send_game_move(&mut self, game_id: &str, move_str: &str, session_id: SessionId) {
-        // notify opponent of joining game
+    /// Validate the move against the game's board before forwarding
+    /// it to the players, illegal moves never reach the opponent
+    pub fn send_game_move(
+        &mut self,
+        game_id: &str,
+        move_str: &str,
+        session_id: SessionId,
+    ) -> Result<(), GameError> {
+        let result = self.game_manager.play_move(game_id, session_id, move_str);
+
+        // a player whose clock ran out lost the game instead of moving
+        if let Err(GameError::OutOfTime(_)) = result {
+            self.end_game_if_over(game_id, session_id);
+        }
+
+        let game_move = result?;
+        self.notify_move(game_id, &game_move, session_id);
+
+        Ok(())
+    }
+
+    /// The game and its bot if the computer is to move
+    pub fn bot_turn(&self, game_id: &str) -> Option<(Game, BotOpponent)> {
+        self.game_manager.bot_turn(game_id)
+    }
+
+    /// Play the move the bot found from the `searched` position
+    /// and send it to the player like a move of an opponent
+    pub fn send_bot_move(
+        &mut self,
+        game_id: &str,
+        searched: &Position,
+        bot_move: Move,
+    ) -> Result<(), GameError> {
+        let result = self.game_manager.play_bot_move(game_id, searched, bot_move);
+
+        // the player of the game is on the other side of the bot
+        let player_id = match self.game_manager.find_game(game_id) {
+            Some(game) => game.player_id(game.side_to_move()),
+            None => 0,
+        };
+
+        // the computer may run out of time too
+        if let Err(GameError::OutOfTime(_)) = result {
+            self.end_game_if_over(game_id, player_id);
+        }
+
+        let game_move = result?;
+        self.notify_move(game_id, &game_move, player_id);
+
+        Ok(())
+    }
+
+    /// The chess game of a finished game to analyse
+    pub fn analysis_game(&self, game_id: &str) -> Result<Game, GameError> {
+        self.game_manager.analysis_game(game_id)
+    }
+
+    /// Send the engine's analysis to the session which asked for it
+    pub fn send_analysis(&self, session_id: SessionId, analysis: &GameAnalysis) {
+        let msg = self.new_server_msg(MessageType::GameAnalysis, &analysis.to_json());
+        self.send_client_msg(session_id, msg);
+    }
+
+    pub fn follow_eval(&mut self, game_id: &str, session_id: SessionId) -> Result<(), GameError> {
+        self.game_manager.follow_eval(game_id, session_id)
+    }
+
+    pub fn unfollow_eval(&mut self, game_id: &str, session_id: SessionId) -> Result<(), GameError> {
+        self.game_manager.unfollow_eval(game_id, session_id)
+    }
+
+    /// The game for the engine to evaluate if it has followers
+    /// and no engine is evaluating it yet
+    pub fn start_eval(&mut self, game_id: &str) -> Option<Game> {
+        self.game_manager.start_eval(game_id)
+    }
+
+    /// The game for the engine to evaluate next if it moved on
+    /// while the position after `ply` half moves was evaluated
+    pub fn next_eval(&mut self, game_id: &str, ply: usize) -> Option<Game> {
+        self.game_manager.next_eval(game_id, ply)
+    }
+
+    /// Send the engine's progress on the position after `ply` half
+    /// moves to the followers of the game's evaluation, spectators
+    /// of a game in progress get it with a delay
+    pub fn send_eval(&mut self, game_id: &str, ply: usize, info: &UciInfo, done: bool) {
+        let (eval, recipients) = match self.game_manager.eval_recipients(game_id, ply, info, done) {
+            Some(eval) => eval,
+            None => return,
+        };
+
+        let msg = self.new_server_msg(MessageType::GameEval, &eval.to_json());
+        for (session_id, delay) in recipients {
+            if let Some((_username, addr)) = self.sessions.get(&session_id) {
+                addr.do_send(DelayedMessage {
+                    message: msg.clone(),
+                    delay,
+                });
+            }
+        }
+    }
+
+    /// The finished game to analyse for its report
+    pub fn start_report(&mut self, game_id: &str) -> Option<Game> {
+        self.game_manager.start_report(game_id)
+    }
+
+    /// Keep the analysis report with the game and send it to its players
+    pub fn finish_report(&mut self, game_id: &str, report: GameReport) {
+        let msg = self.new_server_msg(MessageType::GameReport, &report.to_json());
+        self.game_manager.finish_report(game_id, report);
+
+        if let Some(game) = self.game_manager.find_game(game_id) {
+            for session_id in [game.white_id(), game.black_id()] {
+                self.send_client_msg(session_id, msg.clone());
+            }
+        }
+    }
+
+    /// Analysis report of a finished game in JSON
+    pub fn game_report(&self, game_id: &str) -> Result<String, GameError> {
+        self.game_manager
+            .report(game_id)
+            .map(|report| report.to_json())
+    }
+
+    /// Claim a draw by threefold repetition or the fifty-move rule,
+    /// the game ends immediately if the claim is valid
+    pub fn claim_draw(&mut self, game_id: &str, session_id: SessionId) -> Result<(), GameError> {
+        let result = self.game_manager.claim_draw(game_id, session_id);
+
+        // a claim made once the clock ran out loses the game on time
+        self.end_game_if_over(game_id, session_id);
+
+        result.map(|_| ())
+    }
+
+    /// The player resigns, both players learn who resigned and the result
+    pub fn resign(&mut self, game_id: &str, session_id: SessionId) -> Result<(), GameError> {
+        let result = self.game_manager.resign(game_id, session_id);
+
+        if let Ok(info) = &result {
+            let msg = self.new_server_msg(MessageType::GameResign, &info.to_json());
+            self.send_players(game_id, msg);
+        }
+
+        // a resignation once the clock ran out loses the game on time
+        self.end_game_if_over(game_id, session_id);
+
+        result.map(|_| ())
+    }
+
+    /// Offer the opponent a draw, both players get the offer
+    pub fn offer_draw(&mut self, game_id: &str, session_id: SessionId) -> Result<(), GameError> {
+        let result = self.game_manager.offer_draw(game_id, session_id);
+
+        if let Ok(info) = &result {
+            let msg = self.new_server_msg(MessageType::GameDrawOffer, &info.to_json());
+            self.send_players(game_id, msg);
+        }
+
+        self.end_game_if_over(game_id, session_id);
+
+        result.map(|_| ())
+    }
+
+    /// Accept or decline the opponent's draw offer, the game
+    /// is over once both players agreed to a draw
+    pub fn answer_draw(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+        accept: bool,
+    ) -> Result<(), GameError> {
+        let result = self.game_manager.answer_draw(game_id, session_id, accept);
+
+        if let Ok(info) = &result {
+            let msg_type = match accept {
+                true => MessageType::GameDrawAccept,
+                false => MessageType::GameDrawDecline,
+            };
+            let msg = self.new_server_msg(msg_type, &info.to_json());
+            self.send_players(game_id, msg);
+        }
+
+        self.end_game_if_over(game_id, session_id);
+
+        result.map(|_| ())
+    }
+
+    /// Ask the opponent to take back the last move, against the computer
+    /// the moves are taken back right away, `true` if they were
+    pub fn request_takeback(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+    ) -> Result<bool, GameError> {
+        let result = self.game_manager.request_takeback(game_id, session_id);
+
+        let taken_back = match &result {
+            Ok(info) if info.fen.is_some() => {
+                self.send_takeback(game_id, MessageType::GameTakebackAccept, &info.to_json());
+                true
+            }
+            Ok(info) => {
+                let msg = self.new_server_msg(MessageType::GameTakebackRequest, &info.to_json());
+                self.send_players(game_id, msg);
+                false
+            }
+            Err(_) => false,
+        };
+
+        self.end_game_if_over(game_id, session_id);
+
+        result.map(|_| taken_back)
+    }
+
+    /// Accept or decline the opponent's takeback request, both players
+    /// get the position the game went back to if it was accepted
+    pub fn answer_takeback(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+        accept: bool,
+    ) -> Result<(), GameError> {
+        let result = self
+            .game_manager
+            .answer_takeback(game_id, session_id, accept);
+
+        if let Ok(info) = &result {
+            match accept {
+                true => {
+                    self.send_takeback(game_id, MessageType::GameTakebackAccept, &info.to_json())
+                }
+                false => {
+                    let msg =
+                        self.new_server_msg(MessageType::GameTakebackDecline, &info.to_json());
+                    self.send_players(game_id, msg);
+                }
+            }
+        }
+
+        self.end_game_if_over(game_id, session_id);
+
+        result.map(|_| ())
+    }
+
+    /// Offer the opponent a rematch of a finished game, against the
+    /// computer the rematch starts right away, the ID of the rematch
+    /// if it started
+    pub fn offer_rematch(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+    ) -> Result<Option<String>, GameError> {
+        // the opponent has to be around to take the offer
+        if let Some(game) = self.game_manager.find_game(game_id) {
+            let opponent_id = game.opponent_id(session_id);
+            let connected = self.sessions.contains_key(&opponent_id);
+            if game.is_player(session_id) && game.bot().is_none() && !connected {
+                return Err(GameError::OpponentLeft);
+            }
+        }
+
+        let info = self.game_manager.offer_rematch(game_id, session_id)?;
+        match &info.rematch_id {
+            Some(rematch_id) => self.start_rematch(rematch_id, &info),
+            None => {
+                let msg = self.new_server_msg(MessageType::GameRematchOffer, &info.to_json());
+                self.send_players(game_id, msg);
+            }
+        }
+
+        Ok(info.rematch_id)
+    }
+
+    /// Accept or decline the opponent's rematch offer, the
+    /// ID of the rematch if it was accepted
+    pub fn answer_rematch(
+        &mut self,
+        game_id: &str,
+        session_id: SessionId,
+        accept: bool,
+    ) -> Result<Option<String>, GameError> {
+        // the rematch is not started without the player who offered it
+        if let Some(game) = self.game_manager.find_game(game_id) {
+            let opponent_id = game.opponent_id(session_id);
+            let connected = self.sessions.contains_key(&opponent_id);
+            if accept && game.is_player(session_id) && game.bot().is_none() && !connected {
+                return Err(GameError::OpponentLeft);
+            }
+        }
+
+        let info = self
+            .game_manager
+            .answer_rematch(game_id, session_id, accept)?;
+
+        match &info.rematch_id {
+            Some(rematch_id) => self.start_rematch(rematch_id, &info),
+            None => {
+                let msg = self.new_server_msg(MessageType::GameRematchDecline, &info.to_json());
+                self.send_players(game_id, msg);
+            }
+        }
+
+        Ok(info.rematch_id)
+    }
+
+    /// Abort a game before both sides moved, the players are
+    /// told and the game is dropped without a result
+    pub fn abort_game(&mut self, game_id: &str, session_id: SessionId) -> Result<(), GameError> {
+        let players = self.player_ids(game_id);
+        let info = self.game_manager.abort(game_id, session_id)?;
+        self.send_abort(players, &info);
+
+        Ok(())
+    }
+
+    /// The game started and waits for the first move of a side
+    pub fn awaiting_first_move(&self, game_id: &str) -> bool {
+        self.game_manager.awaiting_first_move(game_id)
+    }
+
+    /// Abort the game if the side to move did not make its first
+    /// move within `timeout`, `true` while the game still waits
+    /// for the first move of a side
+    pub fn check_first_move(&mut self, game_id: &str, timeout: Duration) -> bool {
+        let players = self.player_ids(game_id);
+        if let Some(info) = self.game_manager.abort_if_idle(game_id, timeout) {
+            self.send_abort(players, &info);
+            return false;
+        }
+
+        self.game_manager.awaiting_first_move(game_id)
+    }
+
+    /// The clocks of the game are running
+    pub fn clock_running(&self, game_id: &str) -> bool {
+        self.game_manager
+            .find_game(game_id)
+            .is_some_and(|game| game.clock_running())
+    }
+
+    /// End the game if the side to move ran out of time, `true`
+    /// while the game goes on with its clocks running
+    pub fn check_flag(&mut self, game_id: &str) -> bool {
+        if self.game_manager.check_flag(game_id) {
+            return true;
+        }
+
+        // the final time left, then the result if the game was lost on time
+        self.send_clock(game_id);
+        let white_id = match self.game_manager.find_game(game_id) {
+            Some(game) => game.white_id(),
+            None => 0,
+        };
+        self.end_game_if_over(game_id, white_id);
+
+        false
+    }
+
+    /// Send the time left on the clocks of a timed game to its players
+    pub fn send_clock(&self, game_id: &str) {
+        let game = match self.game_manager.find_game(game_id) {
+            Some(game) => game,
+            None => return,
+        };
+
+        if let Some(info) = game.clock_info() {
+            let msg = self.new_server_msg(MessageType::GameClock, &info.to_json());
+            for session_id in [game.white_id(), game.black_id()] {
+                self.send_client_msg(session_id, msg.clone());
+            }
+        }
+    }
+
+    /// Send the deadline of the side to move in a
+    /// correspondence game to its players
+    pub fn send_deadline(&self, game_id: &str) {
+        let game = match self.game_manager.find_game(game_id) {
+            Some(game) => game,
+            None => return,
+        };
+
+        if let Some(info) = game.deadline_info() {
+            let msg = self.new_server_msg(MessageType::GameDeadline, &info.to_json());
+            for session_id in [game.white_id(), game.black_id()] {
+                self.send_client_msg(session_id, msg.clone());
+            }
+        }
+    }
+
+    /// End the correspondence games whose side to move let its deadline
+    /// pass and notify their players, the IDs of the games which ended
+    pub fn check_deadlines(&mut self) -> Vec<String> {
+        let ended = self.game_manager.check_deadlines(SystemTime::now());
+
+        for game_id in &ended {
+            self.send_deadline(game_id);
+            let white_id = match self.game_manager.find_game(game_id) {
+                Some(game) => game.white_id(),
+                None => 0,
+            };
+            self.end_game_if_over(game_id, white_id);
+        }
+
+        ended
+    }
+
+    /// Drop finished games past their retention
+    pub fn prune_finished_games(&mut self) {
+        self.game_manager.prune_finished_games(Instant::now());
+    }
+
+    /// A player of a correspondence game came back with a new session
+    /// and the resume token of their seat, they take the seat again and
+    /// the game goes on
+    pub fn resume_game(
+        &mut self,
+        session_id: SessionId,
+        game_id: &str,
+        token: &str,
+        username: &str,
+    ) -> Result<(), GameError> {
+        let color = self.game_manager.resume_game(game_id, session_id, token)?;
+
+        self.leave_all_rooms(session_id, username);
+        self.join_room("in_game", session_id, username);
+
         let opponent_id = self.game_manager.opponent_id(game_id, session_id);
-        let msg = self.new_server_msg(MessageType::GameMove, move_str);
+        let msg = self.new_server_msg(
+            MessageType::Status,
+            &format!("{username} is back in the correspondence game"),
+        );
         self.send_client_msg(opponent_id, msg);
+
+        let msg = self.new_server_msg(
+            MessageType::Status,
+            &format!("You resumed {game_id} chess game playing {color:?}"),
+        );
+        self.send_client_msg(session_id, msg);
+        self.send_deadline(game_id);
+
+        Ok(())
     }
 
-    pub fn delete_game(&mut self, game_id: &str) {
-        self.game_manager.delete_game(game_id);
+    pub fn delete_game(&mut self, game_id: &str, session_id: SessionId) -> Result<(), GameError> {
+        self.game_manager.delete_game(game_id, session_id)?;
 
         // update all clients `lobby` of new game list
         // for available game and all games
         self.broadcast_games();
+
+        Ok(())
     }
 
     pub fn leave_all_games(&mut self, session_id: SessionId) {
@@ -266,10 +782,12 @@ impl ChatServer {
         // Must get opponent ID before leaving game
         // otherwise cannot find game that the user is leaving
         // from and the cannot find opponent id within that game
+        // correspondence games survive their players leaving
         let rooms: Vec<String> = self
             .game_manager
             .get_games()
             .iter()
+            .filter(|(_, game)| !game.is_correspondence())
             .map(|game| game.0.to_string())
             .collect();
 
@@ -283,21 +801,191 @@ impl ChatServer {
         self.broadcast_games();
     }
 
-    pub fn list_games(&self) -> HashMap<String, SessionGame> {
-        self.game_manager.get_games().clone()
+    pub fn game_fen(&self, game_id: &str) -> Result<String, GameError> {
+        self.game_manager.game_fen(game_id)
+    }
+
+    /// Legal moves in the current position of a game, only
+    /// those of the piece on `square` if one is given
+    pub fn legal_moves(
+        &self,
+        game_id: &str,
+        square: Option<&str>,
+    ) -> Result<LegalMovesInfo, GameError> {
+        self.game_manager.legal_moves(game_id, square)
+    }
+
+    /// Export an active or finished game as PGN, player names
+    /// are the usernames of the connected sessions
+    pub fn game_pgn(&self, game_id: &str) -> Result<String, GameError> {
+        let game = self
+            .game_manager
+            .find_game(game_id)
+            .ok_or_else(|| GameError::GameNotFound(game_id.to_string()))?;
+
+        let player = |color| {
+            self.player_name(game, color)
+                .unwrap_or_else(|| "?".to_string())
+        };
+
+        Ok(game.pgn(&player(Color::White), &player(Color::Black)))
     }
 
-    pub fn available_games(&self) -> Vec<String> {
-        self.game_manager.available_games()
+    /// joinable games with their players and opening
+    pub fn available_games(&self) -> Vec<GameSummary> {
+        let games = self.game_manager.available_games();
+        games.into_iter().map(|game| self.summary(game)).collect()
     }
 
-    pub fn all_games(&self) -> Vec<String> {
-        self.game_manager.all_games()
+    pub fn all_games(&self) -> Vec<GameSummary> {
+        let games = self.game_manager.all_games();
+        games.into_iter().map(|game| self.summary(game)).collect()
     }
 
     // ---
     // Private methods
     // ---
+
+    /// Send the canonical move to both players so they render the
+    /// same move whatever notation was used, followed by check and
+    /// game over notifications
+    fn notify_move(&mut self, game_id: &str, game_move: &GameMoveInfo, session_id: SessionId) {
+        let opponent_id = self.game_manager.opponent_id(game_id, session_id);
+        let msg = self.new_server_msg(MessageType::GameMove, &game_move.to_json());
+        self.send_client_msg(session_id, msg.clone());
+        self.send_client_msg(opponent_id, msg);
+
+        // the mover's clock stopped and the opponent's started
+        self.send_clock(game_id);
+        self.send_deadline(game_id);
+
+        // neither a draw offer nor a takeback request outlasts a move
+        if let Some(info) = self.game_manager.expire_draw_offer(game_id) {
+            let msg = self.new_server_msg(MessageType::GameDrawExpire, &info.to_json());
+            self.send_players(game_id, msg);
+        }
+        if let Some(info) = self.game_manager.expire_takeback(game_id) {
+            let msg = self.new_server_msg(MessageType::GameTakebackExpire, &info.to_json());
+            self.send_players(game_id, msg);
+        }
+
+        // let both players know the side to move is in check
+        if self.game_manager.in_check(game_id) {
+            let msg = self.new_server_msg(MessageType::GameCheck, &game_move.san);
+            self.send_client_msg(session_id, msg.clone());
+            self.send_client_msg(opponent_id, msg);
+        }
+
+        // game ended with this move, notify both players
+        // and remove the game from the active game list
+        self.end_game_if_over(game_id, session_id);
+    }
+
+    /// If the game has an outcome notify both players with
+    /// `MessageType::GameOver` and move it out of the active games
+    fn end_game_if_over(&mut self, game_id: &str, session_id: SessionId) {
+        let opponent_id = self.game_manager.opponent_id(game_id, session_id);
+
+        if let Some(info) = self.game_manager.finish_game(game_id) {
+            let msg = self.new_server_msg(MessageType::GameOver, &info.to_json());
+            self.send_client_msg(session_id, msg.clone());
+            self.send_client_msg(opponent_id, msg);
+
+            self.broadcast_games();
+        }
+    }
+
+    /// Tell the players the rematch started and send them
+    /// its clocks, the lobby gets the new game
+    fn start_rematch(&self, rematch_id: &str, info: &RematchInfo) {
+        let msg = self.new_server_msg(MessageType::GameRematchAccept, &info.to_json());
+        self.send_players(&info.game_id, msg);
+
+        self.send_clock(rematch_id);
+        self.send_deadline(rematch_id);
+        self.send_resume_tokens(rematch_id);
+        self.broadcast_games();
+    }
+
+    /// Send each player of a correspondence game the token
+    /// they take their seat back with after disconnecting
+    fn send_resume_tokens(&self, game_id: &str) {
+        let game = match self.game_manager.find_game(game_id) {
+            Some(game) if game.is_correspondence() => game,
+            _ => return,
+        };
+
+        for color in [Color::White, Color::Black] {
+            let session_id = game.player_id(color);
+            if session_id == 0 {
+                continue;
+            }
+            let msg = self.new_server_msg(
+                MessageType::Status,
+                &format!(
+                    "Resume this correspondence game with /resume-game {game_id} {}",
+                    game.resume_token(color)
+                ),
+            );
+            self.send_client_msg(session_id, msg);
+        }
+    }
+
+    /// Send the players of a game the position it went back to, then
+    /// the clocks and the lobby the game with fewer moves
+    fn send_takeback(&self, game_id: &str, msg_type: MessageType, content: &str) {
+        let msg = self.new_server_msg(msg_type, content);
+        self.send_players(game_id, msg);
+
+        self.send_clock(game_id);
+        self.send_deadline(game_id);
+        self.broadcast_games();
+    }
+
+    /// Tell the players of an aborted game, which the lobby no longer lists
+    fn send_abort(&self, players: [SessionId; 2], info: &GameAbortInfo) {
+        let msg = self.new_server_msg(MessageType::GameAbort, &info.to_json());
+        for session_id in players {
+            self.send_client_msg(session_id, msg.clone());
+        }
+
+        self.broadcast_games();
+    }
+
+    /// session IDs of the white and black players of a game
+    fn player_ids(&self, game_id: &str) -> [SessionId; 2] {
+        match self.game_manager.find_game(game_id) {
+            Some(game) => [game.white_id(), game.black_id()],
+            None => [0, 0],
+        }
+    }
+
+    /// Send a message to both players of an active or finished game
+    fn send_players(&self, game_id: &str, msg: Message) {
+        if let Some(game) = self.game_manager.find_game(game_id) {
+            for session_id in [game.white_id(), game.black_id()] {
+                self.send_client_msg(session_id, msg.clone());
+            }
+        }
+    }
+
+    fn summary(&self, game: &SessionGame) -> GameSummary {
+        game.summary(
+            self.player_name(game, Color::White),
+            self.player_name(game, Color::Black),
+        )
+    }
+
+    /// name of the player of a side, the computer is named after
+    /// its level, `None` if nobody plays the side
+    fn player_name(&self, game: &SessionGame, color: Color) -> Option<String> {
+        if let Some(bot) = game.bot().filter(|bot| bot.color == color) {
+            return Some(bot.to_string());
+        }
+
+        game.player_name(color).map(str::to_string)
+    }
+
     fn new_server_msg(&self, msg_type: MessageType, content: &str) -> Message {
         Message {
             msg_type,
@@ -314,11 +1002,11 @@ impl ChatServer {
         // broadcast new available game list
         let available_game_msg = self.new_server_msg(
             MessageType::AvailableGameList,
-            &self.available_games().join(","),
+            &self.available_games().to_json(),
         );
 
         let all_game_msg =
-            self.new_server_msg(MessageType::AllGameList, &self.all_games().join(","));
+            self.new_server_msg(MessageType::AllGameList, &self.all_games().to_json());
 
         for room in ["lobby", "in_game"] {
             self.broadcast(room, available_game_msg.clone(), 0);
@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
@@ -6,9 +5,13 @@ use actix::prelude::*;
 use actix_web_actors::ws::{self, CloseCode, CloseReason};
 use serde::Serialize;
 
+use crate::bot;
+use crate::chess::{Game, Variant};
 use crate::constants::{CLIENT_TIMEOUT, HEARTBEAT_INTERVAL};
-use crate::message::{Json, Message, MessageType};
+use crate::game::NewGameOptions;
+use crate::message::{DelayedMessage, Json, Message, MessageType};
 use crate::server::ChatServer;
+use crate::timer::{AbortTimer, ClockTimer};
 use crate::unlock;
 
 pub type SessionId = usize;
@@ -17,7 +20,7 @@ pub type SessionId = usize;
 pub struct SessionProfile {
     username: String,
     room: String,
-    game: String,
+    games: Vec<String>,
     id: SessionId,
 }
 
@@ -31,7 +34,6 @@ pub struct WsSession {
 
     /// joined room
     pub room: String,
-    pub game: String,
 
     /// peer name
     pub username: String,
@@ -81,7 +83,6 @@ impl WsSession {
                     // the current room of the session is updated here
                     // ONLY
                     self.room = new_room.clone();
-                    self.game = "none".to_string();
 
                     let mut chat_server = unlock!(self.chat_server);
 
@@ -102,35 +103,73 @@ impl WsSession {
             // Game Commands
             // ---
             "/new-game" => {
+                // optional variant and starting position, eg.
+                // `/new-game chess960 518`, `/new-game crazyhouse`
+                // or `/new-game <fen>`,
+                // standard starting position if not given
+                let args = if v.len() == 2 { v[1] } else { "" };
+                let game = NewGameOptions::parse(args)
+                    .and_then(|options| Ok((options.to_game()?, options)));
+                match game {
+                    Ok((chess, options)) => self.create_game(chess, options, ctx),
+                    Err(err) => {
+                        let msg = self.new_message(MessageType::Error, &err.to_string(), true);
+
+                        ctx.text(msg.to_string());
+                    }
+                }
+            }
+
+            "/new-game-pgn" => {
+                // start point and game options followed by the PGN text,
+                // eg. `/new-game-pgn 23... 5+3 [Event "Club game"] 1. e4 e5`
+                let args = if v.len() == 2 { v[1] } else { "" };
+                match NewGameOptions::parse_pgn(args) {
+                    Ok((chess, options)) => self.create_game(chess, options, ctx),
+                    Err(err) => {
+                        let msg = self.new_message(MessageType::Error, &err.to_string(), true);
+
+                        ctx.text(msg.to_string());
+                    }
+                }
+            }
+
+            "/join-game" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
                 let mut server = unlock!(self.chat_server);
 
-                // ensure game with that name does not already exist
-                // return early if already exists
-                let games = server.list_games();
-                if games.contains_key(&self.username) {
+                let joined = server.join_game(self.id, game_id, &self.username);
+                drop(server);
+
+                // the room is left as it was when the game is
+                // missing, already started or taken by another player
+                if !joined {
                     let msg = self.new_message(
                         MessageType::Error,
-                        &format!("Game with the name {} already exists", self.username),
+                        &format!("Could not join game {game_id}"),
                         true,
                     );
 
+                    // send message back to client session
                     ctx.text(msg.to_string());
                     return;
                 }
 
-                // set room to `none`
+                // the game starts, its clocks with it, and
+                // it is aborted if nobody makes a first move
+                ClockTimer::start_for(self.chat_server.clone(), game_id.to_string());
+                AbortTimer::start_for(self.chat_server.clone(), game_id.to_string());
+
+                // set room to `in_game`
                 self.room = "in_game".to_string();
-                // set game name
-                self.game = self.username.clone();
 
-                // create new game if no error above
-                server.new_game(self.id, &self.username);
                 let msg = self.new_message(
                     MessageType::Status,
-                    &format!(
-                        "New game with the name {} created and joined",
-                        self.username
-                    ),
+                    &format!("New joined {game_id} chess game"),
                     true,
                 );
 
@@ -138,35 +177,30 @@ impl WsSession {
                 ctx.text(msg.to_string());
             }
 
-            "/join-game" => {
-                if v.len() == 2 {
-                    // TODO:
-                    // Very naive logic for joining room
-                    // currently allows any room name to be joined
-                    let game_name = v[1].to_owned();
-
-                    let mut server = unlock!(self.chat_server);
-
-                    server.join_game(self.id, &game_name, &self.username);
+            "/resume-game" => {
+                // a player of a correspondence game who disconnected
+                // takes their seat again with its resume token
+                let (game_id, token) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
 
-                    // set room to `in_game`
-                    self.room = "is_game".to_string();
-                    // set game name
-                    self.game = game_name.clone();
+                let result =
+                    unlock!(self.chat_server).resume_game(self.id, game_id, token, &self.username);
 
-                    let msg = self.new_message(
-                        MessageType::Status,
-                        &format!("New joined {game_name} chess game"),
-                        true,
-                    );
+                match result {
+                    Ok(()) => {
+                        self.room = "in_game".to_string();
 
-                    // send message back to client session
-                    ctx.text(msg.to_string());
-                } else {
-                    let msg = self.new_message(MessageType::Error, "Game name is required", true);
+                        // the computer answers if it was its turn
+                        bot::play_bot_move(self.chat_server.clone(), game_id.to_string());
+                    }
+                    Err(err) => {
+                        let msg = self.new_message(MessageType::Error, &err.to_string(), true);
 
-                    // send message back to client session
-                    ctx.text(msg.to_string());
+                        // send message back to client session
+                        ctx.text(msg.to_string());
+                    }
                 }
             }
 
@@ -175,21 +209,23 @@ impl WsSession {
                 // check if currently in game
                 // if not return error message
                 // saying `You are currently not in a game`
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
 
                 let mut server = unlock!(self.chat_server);
 
-                server.leave_game(&self.game, self.id);
+                server.leave_game(game_id, self.id);
 
                 server.join_room("main", self.id, &self.username);
 
                 // set room to `main`
                 self.room = "main".to_string();
-                // set game name
-                self.game = "none".to_string();
 
                 let msg = self.new_message(
                     MessageType::Status,
-                    &format!("You left {} chess game and joined the main room", self.game),
+                    &format!("You left {game_id} chess game and joined the main room"),
                     true,
                 );
 
@@ -198,37 +234,388 @@ impl WsSession {
             }
 
             "/game-move" => {
-                if v.len() == 2 {
-                    // TODO:
-                    // ensure user is in a game
-                    let move_str = v[1].to_owned();
+                // the server checks the session is a player
+                // in the game and that it is their turn
+                let (game_id, move_str) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
 
-                    let mut server = unlock!(self.chat_server);
+                if !move_str.is_empty() {
+                    let result =
+                        unlock!(self.chat_server).send_game_move(game_id, move_str, self.id);
 
-                    server.send_game_move(&self.game, &move_str, self.id);
+                    let msg = match &result {
+                        Ok(()) => self.new_message(
+                            MessageType::Status,
+                            &format!("Game move sent {}", move_str),
+                            true,
+                        ),
+                        Err(err) => self.new_message(MessageType::Error, &err.to_string(), true),
+                    };
 
-                    let msg = self.new_message(
+                    // send message back to client session
+                    ctx.text(msg.to_string());
+
+                    // the computer answers in games against the bot,
+                    // the engine evaluates the new position or the
+                    // finished game for its report
+                    if result.is_ok() {
+                        bot::after_move(self.chat_server.clone(), game_id.to_string());
+                    }
+                } else {
+                    let msg = self.new_message(MessageType::Error, "Move string is required", true);
+
+                    // send message back to client session
+                    ctx.text(msg.to_string());
+                }
+            }
+
+            "/game-fen" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let server = unlock!(self.chat_server);
+
+                let msg = match server.game_fen(game_id) {
+                    Ok(fen) => self.new_message(MessageType::GameFen, &fen, true),
+                    Err(err) => self.new_message(MessageType::Error, &err.to_string(), true),
+                };
+
+                // send message back to client session
+                ctx.text(msg.to_string());
+            }
+
+            "/legal-moves" => {
+                // legal moves of the piece on the given square,
+                // or all legal moves in the game
+                let (game_id, square) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+                let square = if square.is_empty() {
+                    None
+                } else {
+                    Some(square)
+                };
+
+                let server = unlock!(self.chat_server);
+
+                let msg = match server.legal_moves(game_id, square) {
+                    Ok(info) => {
+                        self.new_message(MessageType::GameLegalMoves, &info.to_json(), true)
+                    }
+                    Err(err) => self.new_message(MessageType::Error, &err.to_string(), true),
+                };
+
+                // send message back to client session
+                ctx.text(msg.to_string());
+            }
+
+            "/analyse" => {
+                // finished games only, the analysis is sent
+                // once the engine has finished
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let game = unlock!(self.chat_server).analysis_game(game_id);
+
+                let msg = match game {
+                    Ok(chess) => {
+                        bot::send_analysis(
+                            self.chat_server.clone(),
+                            game_id.to_string(),
+                            self.id,
+                            chess,
+                        );
+                        self.new_message(
+                            MessageType::Status,
+                            &format!("Analysing game {game_id}"),
+                            true,
+                        )
+                    }
+                    Err(err) => self.new_message(MessageType::Error, &err.to_string(), true),
+                };
+
+                // send message back to client session
+                ctx.text(msg.to_string());
+            }
+
+            "/follow-eval" => {
+                // players only get the evaluation once the game is over
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let result = unlock!(self.chat_server).follow_eval(game_id, self.id);
+
+                let msg = match result {
+                    Ok(()) => {
+                        bot::stream_eval(self.chat_server.clone(), game_id.to_string());
+                        self.new_message(
+                            MessageType::Status,
+                            &format!("Following the engine evaluation of game {game_id}"),
+                            true,
+                        )
+                    }
+                    Err(err) => self.new_message(MessageType::Error, &err.to_string(), true),
+                };
+
+                // send message back to client session
+                ctx.text(msg.to_string());
+            }
+
+            "/unfollow-eval" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let result = unlock!(self.chat_server).unfollow_eval(game_id, self.id);
+
+                let msg = match result {
+                    Ok(()) => self.new_message(
                         MessageType::Status,
-                        &format!("Game move sent {}", move_str),
+                        &format!("Stopped following the engine evaluation of game {game_id}"),
                         true,
-                    );
+                    ),
+                    Err(err) => self.new_message(MessageType::Error, &err.to_string(), true),
+                };
+
+                // send message back to client session
+                ctx.text(msg.to_string());
+            }
+
+            "/game-pgn" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let server = unlock!(self.chat_server);
+
+                let msg = match server.game_pgn(game_id) {
+                    Ok(pgn) => self.new_message(MessageType::GamePgn, &pgn, true),
+                    Err(err) => self.new_message(MessageType::Error, &err.to_string(), true),
+                };
+
+                // send message back to client session
+                ctx.text(msg.to_string());
+            }
+
+            "/game-report" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let server = unlock!(self.chat_server);
+
+                let msg = match server.game_report(game_id) {
+                    Ok(report) => self.new_message(MessageType::GameReport, &report, true),
+                    Err(err) => self.new_message(MessageType::Error, &err.to_string(), true),
+                };
+
+                // send message back to client session
+                ctx.text(msg.to_string());
+            }
+
+            "/claim-draw" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let result = unlock!(self.chat_server).claim_draw(game_id, self.id);
+
+                // the game over message is sent to both players
+                // by the server if the claim is valid
+                match result {
+                    // the drawn game is analysed for its report and players
+                    // following the evaluation get it now the game is over
+                    Ok(()) => bot::after_move(self.chat_server.clone(), game_id.to_string()),
+                    Err(err) => {
+                        let msg = self.new_message(MessageType::Error, &err.to_string(), true);
+
+                        // send message back to client session
+                        ctx.text(msg.to_string());
+                    }
+                }
+            }
+
+            "/resign" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let result = unlock!(self.chat_server).resign(game_id, self.id);
+
+                // both players get the resignation and the game over
+                // message from the server, the game is analysed
+                match result {
+                    Ok(()) => bot::after_move(self.chat_server.clone(), game_id.to_string()),
+                    Err(err) => {
+                        let msg = self.new_message(MessageType::Error, &err.to_string(), true);
+
+                        // send message back to client session
+                        ctx.text(msg.to_string());
+                    }
+                }
+            }
+
+            "/offer-draw" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let result = unlock!(self.chat_server).offer_draw(game_id, self.id);
+
+                if let Err(err) = result {
+                    let msg = self.new_message(MessageType::Error, &err.to_string(), true);
 
                     // send message back to client session
                     ctx.text(msg.to_string());
-                } else {
-                    let msg = self.new_message(MessageType::Error, "Move string is required", true);
+                }
+            }
+
+            "/accept-draw" | "/decline-draw" => {
+                let accept = v[0] == "/accept-draw";
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+                let result = unlock!(self.chat_server).answer_draw(game_id, self.id, accept);
+
+                // a game drawn by agreement is analysed for its report
+                match result {
+                    Ok(()) if accept => {
+                        bot::after_move(self.chat_server.clone(), game_id.to_string())
+                    }
+                    Ok(()) => (),
+                    Err(err) => {
+                        let msg = self.new_message(MessageType::Error, &err.to_string(), true);
+
+                        // send message back to client session
+                        ctx.text(msg.to_string());
+                    }
+                }
+            }
+
+            "/takeback" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let result = unlock!(self.chat_server).request_takeback(game_id, self.id);
+
+                // the computer agrees to takebacks, it may be its turn again
+                match result {
+                    Ok(true) => bot::after_move(self.chat_server.clone(), game_id.to_string()),
+                    Ok(false) => (),
+                    Err(err) => {
+                        let msg = self.new_message(MessageType::Error, &err.to_string(), true);
+
+                        // send message back to client session
+                        ctx.text(msg.to_string());
+                    }
+                }
+            }
+
+            "/accept-takeback" | "/decline-takeback" => {
+                let accept = v[0] == "/accept-takeback";
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+                let result = unlock!(self.chat_server).answer_takeback(game_id, self.id, accept);
+
+                // followers get the evaluation of the position the game went back to
+                match result {
+                    Ok(()) if accept => {
+                        bot::after_move(self.chat_server.clone(), game_id.to_string())
+                    }
+                    Ok(()) => (),
+                    Err(err) => {
+                        let msg = self.new_message(MessageType::Error, &err.to_string(), true);
+
+                        // send message back to client session
+                        ctx.text(msg.to_string());
+                    }
+                }
+            }
+
+            "/abort" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let result = unlock!(self.chat_server).abort_game(game_id, self.id);
+
+                // both players get the abort message from the server
+                if let Err(err) = result {
+                    let msg = self.new_message(MessageType::Error, &err.to_string(), true);
 
                     // send message back to client session
                     ctx.text(msg.to_string());
                 }
             }
 
+            "/rematch" => {
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+
+                let result = unlock!(self.chat_server).offer_rematch(game_id, self.id);
+
+                // the computer takes the rematch right away
+                match result {
+                    Ok(Some(rematch_id)) => self.start_rematch(rematch_id),
+                    Ok(None) => (),
+                    Err(err) => {
+                        let msg = self.new_message(MessageType::Error, &err.to_string(), true);
+
+                        // send message back to client session
+                        ctx.text(msg.to_string());
+                    }
+                }
+            }
+
+            "/accept-rematch" | "/decline-rematch" => {
+                let accept = v[0] == "/accept-rematch";
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+                let result = unlock!(self.chat_server).answer_rematch(game_id, self.id, accept);
+
+                match result {
+                    Ok(Some(rematch_id)) => self.start_rematch(rematch_id),
+                    Ok(None) => (),
+                    Err(err) => {
+                        let msg = self.new_message(MessageType::Error, &err.to_string(), true);
+
+                        // send message back to client session
+                        ctx.text(msg.to_string());
+                    }
+                }
+            }
+
             "/list-available-games" => {
                 let server = unlock!(self.chat_server);
 
                 let msg = self.new_message(
                     MessageType::AvailableGameList,
-                    &server.available_games().join(","),
+                    &server.available_games().to_json(),
                     true,
                 );
 
@@ -241,7 +628,7 @@ impl WsSession {
 
                 let msg = self.new_message(
                     MessageType::AllGameList,
-                    &server.all_games().join(","),
+                    &server.all_games().to_json(),
                     true,
                 );
 
@@ -250,26 +637,23 @@ impl WsSession {
             }
 
             "/delete-game" => {
-                if v.len() == 2 {
-                    let game_name = v[1].to_owned();
-                    let mut server = unlock!(self.chat_server);
-
-                    server.delete_game(&game_name);
+                let (game_id, _) = match self.game_args(&v, ctx) {
+                    Some(args) => args,
+                    None => return,
+                };
+                let result = unlock!(self.chat_server).delete_game(game_id, self.id);
 
-                    let msg = self.new_message(
+                let msg = match result {
+                    Ok(()) => self.new_message(
                         MessageType::Status,
-                        &format!("{} chess game deleted", self.game),
+                        &format!("{game_id} chess game deleted"),
                         true,
-                    );
-
-                    // send message back to client session
-                    ctx.text(msg.to_string());
-                } else {
-                    let msg = self.new_message(MessageType::Error, "Game name is required", true);
+                    ),
+                    Err(err) => self.new_message(MessageType::Error, &err.to_string(), true),
+                };
 
-                    // send message back to client session
-                    ctx.text(msg.to_string());
-                }
+                // send message back to client session
+                ctx.text(msg.to_string());
             }
 
             // ---
@@ -279,7 +663,7 @@ impl WsSession {
                 let profile = SessionProfile {
                     username: self.username.clone(),
                     room: self.room.clone(),
-                    game: self.game.clone(),
+                    games: unlock!(self.chat_server).player_games(self.id),
                     id: self.id,
                 };
 
@@ -303,6 +687,86 @@ impl WsSession {
         }
     }
 
+    /// create a new game and join it as the white player,
+    /// the session gets the id and name of the game
+    fn create_game(
+        &mut self,
+        chess: Game,
+        options: NewGameOptions,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        // set room to `none`
+        self.room = "in_game".to_string();
+
+        // report the starting position of variants
+        // which do not start from the standard position
+        let variant = chess.variant();
+        let start = chess.start().to_fen();
+
+        let summary = unlock!(self.chat_server).new_game(self.id, &self.username, chess, options);
+        let summary = match summary {
+            Some(summary) => summary,
+            None => return,
+        };
+
+        let content = match variant {
+            Variant::Standard => format!(
+                "New game {} with the id {} created and joined",
+                summary.name, summary.game_id
+            ),
+            _ => format!(
+                "New {variant} game {} with the id {} created and joined, starting position {start}",
+                summary.name, summary.game_id
+            ),
+        };
+        let msg = self.new_message(MessageType::Status, &content, true);
+
+        // send message back to client session
+        ctx.text(msg.to_string());
+
+        let msg = self.new_message(MessageType::GameCreated, &summary.to_json(), true);
+        ctx.text(msg.to_string());
+
+        // a game against the computer starts with its clocks
+        ClockTimer::start_for(self.chat_server.clone(), summary.game_id.clone());
+        AbortTimer::start_for(self.chat_server.clone(), summary.game_id.clone());
+
+        // the computer opens the game when it plays white
+        bot::play_bot_move(self.chat_server.clone(), summary.game_id);
+    }
+
+    /// the session plays on in the rematch, its clocks start
+    /// and the computer opens the game when it plays white
+    fn start_rematch(&mut self, rematch_id: String) {
+        self.room = "in_game".to_string();
+
+        ClockTimer::start_for(self.chat_server.clone(), rematch_id.clone());
+        AbortTimer::start_for(self.chat_server.clone(), rematch_id.clone());
+        bot::play_bot_move(self.chat_server.clone(), rematch_id);
+    }
+
+    /// split the arguments of a game command into the game id
+    /// and the rest, the client is told if the game id is missing
+    fn game_args<'a>(
+        &self,
+        v: &[&'a str],
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) -> Option<(&'a str, &'a str)> {
+        let args = if v.len() == 2 { v[1].trim() } else { "" };
+        if args.is_empty() {
+            let msg = self.new_message(MessageType::Error, "Game ID is required", true);
+
+            // send message back to client session
+            ctx.text(msg.to_string());
+            return None;
+        }
+
+        match args.split_once(char::is_whitespace) {
+            Some((game_id, rest)) => Some((game_id, rest.trim())),
+            None => Some((args, "")),
+        }
+    }
+
     fn handle_message(&mut self, msg: &str) {
         let chat_server = unlock!(self.chat_server);
 
@@ -345,6 +809,17 @@ impl Handler<Message> for WsSession {
     }
 }
 
+impl Handler<DelayedMessage> for WsSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: DelayedMessage, ctx: &mut Self::Context) {
+        let message = msg.message;
+        ctx.run_later(msg.delay, move |_act, ctx| {
+            ctx.text(message.to_json());
+        });
+    }
+}
+
 /// WebSocket message handler
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
@@ -425,7 +900,7 @@ impl Actor for WsSession {
         ctx.text(session_id_msg.to_json())
     }
 
-    fn stopping(&mut self, ctx: &mut Self::Context) -> Running {
+    fn stopping(&mut self, _ctx: &mut Self::Context) -> Running {
         let mut chat_server = unlock!(self.chat_server);
 
         log::info!("CLIENT DISCONNECTED");
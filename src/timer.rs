@@ -0,0 +1,131 @@
+use actix::prelude::*;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::bot;
+use crate::constants::{
+    ABORT_CHECK_INTERVAL, CLOCK_SYNC_INTERVAL, CLOCK_TICK, DEADLINE_CHECK_INTERVAL,
+    FIRST_MOVE_TIMEOUT,
+};
+use crate::server::ChatServer;
+use crate::unlock;
+
+/// Drives the clocks of a timed game on the actor runtime, the game
+/// is lost by the side to move once its clock runs out and the players
+/// are kept in sync with the time left, stops once the game is over
+pub struct ClockTimer {
+    chat_server: Arc<Mutex<ChatServer>>,
+    game_id: String,
+}
+
+impl ClockTimer {
+    /// start driving the clocks of the game if they are running
+    pub fn start_for(chat_server: Arc<Mutex<ChatServer>>, game_id: String) {
+        if unlock!(chat_server).clock_running(&game_id) {
+            ClockTimer {
+                chat_server,
+                game_id,
+            }
+            .start();
+        }
+    }
+}
+
+impl Actor for ClockTimer {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(CLOCK_TICK, |act, ctx| {
+            let running = unlock!(act.chat_server).check_flag(&act.game_id);
+            if !running {
+                // a game lost on time is analysed like any finished game
+                bot::after_move(act.chat_server.clone(), act.game_id.clone());
+                ctx.stop();
+            }
+        });
+
+        ctx.run_interval(CLOCK_SYNC_INTERVAL, |act, _ctx| {
+            unlock!(act.chat_server).send_clock(&act.game_id);
+        });
+    }
+}
+
+/// Checks the deadlines of all correspondence games for the lifetime
+/// of the server, a game whose side to move let its days for the move
+/// pass is lost on time whether its players are connected or not,
+/// finished games past their retention are dropped on the same tick
+pub struct DeadlineTimer {
+    chat_server: Arc<Mutex<ChatServer>>,
+}
+
+impl DeadlineTimer {
+    pub fn new(chat_server: Arc<Mutex<ChatServer>>) -> Self {
+        Self { chat_server }
+    }
+}
+
+impl Actor for DeadlineTimer {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(DEADLINE_CHECK_INTERVAL, |act, _ctx| {
+            let mut server = unlock!(act.chat_server);
+            server.prune_finished_games();
+            let ended = server.check_deadlines();
+            drop(server);
+
+            for game_id in ended {
+                bot::after_move(act.chat_server.clone(), game_id);
+            }
+        });
+    }
+}
+
+static FIRST_MOVE_TIMEOUT_CONFIG: OnceLock<Duration> = OnceLock::new();
+
+/// time each side has for its first move, `FIRST_MOVE_TIMEOUT`
+/// in seconds, the default when it is not set
+fn first_move_timeout() -> Duration {
+    *FIRST_MOVE_TIMEOUT_CONFIG.get_or_init(|| {
+        std::env::var("FIRST_MOVE_TIMEOUT")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(FIRST_MOVE_TIMEOUT)
+    })
+}
+
+/// Aborts a started game whose side to move does not make its first
+/// move in time, so games the joiner walked away from do not linger,
+/// stops once both sides moved or the game is gone
+pub struct AbortTimer {
+    chat_server: Arc<Mutex<ChatServer>>,
+    game_id: String,
+}
+
+impl AbortTimer {
+    /// start watching the game if it waits for a first move
+    pub fn start_for(chat_server: Arc<Mutex<ChatServer>>, game_id: String) {
+        if unlock!(chat_server).awaiting_first_move(&game_id) {
+            AbortTimer {
+                chat_server,
+                game_id,
+            }
+            .start();
+        }
+    }
+}
+
+impl Actor for AbortTimer {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(ABORT_CHECK_INTERVAL, |act, ctx| {
+            let waiting =
+                unlock!(act.chat_server).check_first_move(&act.game_id, first_move_timeout());
+            if !waiting {
+                ctx.stop();
+            }
+        });
+    }
+}
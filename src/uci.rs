@@ -0,0 +1,367 @@
+use derive_more::Display;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::chess::search::{SearchResult, MATE_SCORE, MATE_THRESHOLD};
+use crate::chess::{Game, Move, Position, Variant};
+
+/// How long an engine may take to answer `uci` and `isready`
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time past the search time after which the engine is told to stop
+const SEARCH_GRACE: Duration = Duration::from_secs(1);
+
+/// How long a stopped engine may take to send its best move
+/// before it is considered hung and killed
+const STOP_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum UciError {
+    #[display(fmt = "Could not start engine {_0}: {_1}")]
+    Spawn(String, String),
+    #[display(fmt = "Engine exited unexpectedly, {_0}")]
+    Crashed(String),
+    #[display(fmt = "Engine did not answer `{_0}` in time")]
+    Timeout(String),
+    #[display(fmt = "Engine does not support the {_0} variant")]
+    UnsupportedVariant(Variant),
+    #[display(fmt = "Engine played an illegal move {_0}")]
+    IllegalMove(String),
+}
+
+/// Score reported by an engine from the side to move's point of view
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UciScore {
+    /// centipawns
+    Cp(i32),
+    /// moves to mate, negative when the side to move gets mated
+    Mate(i32),
+}
+
+impl UciScore {
+    /// score of the built-in search, which scores mates
+    /// as `MATE_SCORE` less the plies to the mate
+    pub fn from_search(score: i32) -> UciScore {
+        if score.abs() < MATE_THRESHOLD {
+            return UciScore::Cp(score);
+        }
+
+        let moves = (MATE_SCORE - score.abs() + 1) / 2;
+        UciScore::Mate(moves * score.signum())
+    }
+}
+
+/// Search progress sent by the engine in an `info` line,
+/// fields the engine left out are `None`
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct UciInfo {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub multipv: Option<u32>,
+    pub score: Option<UciScore>,
+    pub nodes: Option<u64>,
+    /// milliseconds searched
+    pub time: Option<u64>,
+    /// principal variation in UCI notation
+    pub pv: Vec<String>,
+}
+
+impl UciInfo {
+    /// parse an `info` line, `None` for other lines
+    pub fn parse(line: &str) -> Option<UciInfo> {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some("info") {
+            return None;
+        }
+
+        let mut info = UciInfo::default();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => info.depth = tokens.next().and_then(|t| t.parse().ok()),
+                "seldepth" => info.seldepth = tokens.next().and_then(|t| t.parse().ok()),
+                "multipv" => info.multipv = tokens.next().and_then(|t| t.parse().ok()),
+                "nodes" => info.nodes = tokens.next().and_then(|t| t.parse().ok()),
+                "time" => info.time = tokens.next().and_then(|t| t.parse().ok()),
+                "score" => {
+                    let kind = tokens.next();
+                    let value = tokens.next().and_then(|t| t.parse().ok());
+                    info.score = match (kind, value) {
+                        (Some("cp"), Some(value)) => Some(UciScore::Cp(value)),
+                        (Some("mate"), Some(value)) => Some(UciScore::Mate(value)),
+                        _ => None,
+                    };
+                }
+                // the principal variation runs to the end of the line
+                "pv" => info.pv = tokens.by_ref().map(str::to_string).collect(),
+                // free text to the end of the line
+                "string" => break,
+                _ => {}
+            }
+        }
+
+        Some(info)
+    }
+
+    /// progress of an iteration of the built-in search of `position`
+    pub fn from_search(position: &Position, result: &SearchResult) -> UciInfo {
+        UciInfo {
+            depth: Some(result.depth),
+            score: Some(UciScore::from_search(result.score)),
+            nodes: Some(result.nodes),
+            pv: result
+                .best_move
+                .iter()
+                .map(|mv| position.uci(*mv))
+                .collect(),
+            ..UciInfo::default()
+        }
+    }
+}
+
+/// How long an engine searches, `go movetime` with an optional `depth`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UciLimits {
+    pub movetime: Duration,
+    pub depth: Option<u32>,
+}
+
+/// Outcome of a `go` command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UciSearch {
+    /// `None` when the engine has no move, the game is over
+    pub best_move: Option<Move>,
+    /// last `info` of the principal variation
+    pub info: Option<UciInfo>,
+}
+
+/// An external engine running as a child process, spoken to over
+/// its stdin and stdout, the process is killed when this is dropped
+pub struct UciEngine {
+    name: String,
+    /// names of the options the engine declared
+    options: Vec<String>,
+    child: Child,
+    stdin: ChildStdin,
+    /// lines of the engine's output, read on a separate thread so
+    /// reads can time out, disconnected once the engine exits
+    lines: Receiver<String>,
+}
+
+impl UciEngine {
+    /// start the engine and complete the `uci` and `isready` handshake
+    pub fn start<S: AsRef<OsStr>>(program: &str, args: &[S]) -> Result<UciEngine, UciError> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| UciError::Spawn(program.to_string(), err.to_string()))?;
+
+        let stdin = child.stdin.take().expect("engine stdin is piped");
+        let stdout = child.stdout.take().expect("engine stdout is piped");
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = UciEngine {
+            name: program.to_string(),
+            options: Vec::new(),
+            child,
+            stdin,
+            lines,
+        };
+
+        engine.send("uci")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            let line = engine.recv("uci", deadline)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            } else if let Some(option) = line.strip_prefix("option name ") {
+                let name = option.split(" type ").next().unwrap_or(option);
+                engine.options.push(name.trim().to_string());
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        engine.is_ready()?;
+
+        Ok(engine)
+    }
+
+    /// name the engine gave in `id name`, the program if it gave none
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn has_option(&self, name: &str) -> bool {
+        self.options
+            .iter()
+            .any(|option| option.eq_ignore_ascii_case(name))
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), UciError> {
+        self.send(&format!("setoption name {name} value {value}"))
+    }
+
+    /// wait until the engine processed all commands sent so far
+    pub fn is_ready(&mut self) -> Result<(), UciError> {
+        self.send("isready")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        while self.recv("isready", deadline)?.trim() != "readyok" {}
+
+        Ok(())
+    }
+
+    /// search the current position of the game, `on_info` is called
+    /// with every `info` line of the principal variation with a score,
+    /// an engine which does not stop in time is killed
+    pub fn go(
+        &mut self,
+        game: &Game,
+        limits: UciLimits,
+        mut on_info: impl FnMut(&UciInfo),
+    ) -> Result<UciSearch, UciError> {
+        self.set_position(game)?;
+
+        let mut command = format!("go movetime {}", limits.movetime.as_millis());
+        if let Some(depth) = limits.depth {
+            command.push_str(&format!(" depth {depth}"));
+        }
+        self.send(&command)?;
+
+        let mut info = None;
+        let mut stopped = false;
+        let mut deadline = Instant::now() + limits.movetime + SEARCH_GRACE;
+        loop {
+            let line = match self.recv("go", deadline) {
+                Ok(line) => line,
+                // ask a slow engine for its move once before giving up on it
+                Err(UciError::Timeout(_)) if !stopped => {
+                    self.send("stop")?;
+                    stopped = true;
+                    deadline = Instant::now() + STOP_TIMEOUT;
+                    continue;
+                }
+                Err(err) => {
+                    let _ = self.child.kill();
+                    return Err(err);
+                }
+            };
+
+            if let Some(line_info) = UciInfo::parse(&line) {
+                if line_info.score.is_some() && line_info.multipv.unwrap_or(1) == 1 {
+                    on_info(&line_info);
+                    info = Some(line_info);
+                }
+            } else if let Some(best) = line.strip_prefix("bestmove") {
+                let best_move = match best.split_whitespace().next() {
+                    None | Some("(none)") | Some("0000") => None,
+                    Some(notation) => Some(
+                        game.position()
+                            .parse_move(notation)
+                            .map_err(|_| UciError::IllegalMove(notation.to_string()))?,
+                    ),
+                };
+
+                return Ok(UciSearch { best_move, info });
+            }
+        }
+    }
+
+    /// send the game as its starting position and the moves played,
+    /// so the engine can detect repetitions
+    fn set_position(&mut self, game: &Game) -> Result<(), UciError> {
+        let variant = game.variant();
+        if self.has_option("UCI_Chess960") {
+            let chess960 = variant == Variant::Chess960;
+            self.set_option("UCI_Chess960", &chess960.to_string())?;
+        }
+
+        let supported = match variant {
+            Variant::Standard => true,
+            Variant::Chess960 => self.has_option("UCI_Chess960"),
+            _ => self.has_option("UCI_Variant"),
+        };
+        if !supported {
+            return Err(UciError::UnsupportedVariant(variant));
+        }
+        if self.has_option("UCI_Variant") {
+            self.set_option("UCI_Variant", uci_variant(variant))?;
+        }
+
+        let mut command = format!("position fen {}", game.start().to_fen());
+        if !game.moves().is_empty() {
+            command.push_str(" moves");
+            let mut position = *game.start();
+            for mv in game.moves() {
+                command.push(' ');
+                command.push_str(&position.uci(*mv));
+                position = position.make_move(*mv);
+            }
+        }
+
+        self.send(&command)
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), UciError> {
+        writeln!(self.stdin, "{command}")
+            .and_then(|_| self.stdin.flush())
+            .map_err(|_| self.crashed())
+    }
+
+    /// next line of output, an error if the engine exited
+    /// or sent nothing before the deadline
+    fn recv(&mut self, command: &str, deadline: Instant) -> Result<String, UciError> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err(UciError::Timeout(command.to_string())),
+            Err(RecvTimeoutError::Disconnected) => Err(self.crashed()),
+        }
+    }
+
+    /// the engine closed its pipes, with its exit status if it exited,
+    /// it is not waited for in case it is still running
+    fn crashed(&mut self) -> UciError {
+        let status = match self.child.try_wait() {
+            Ok(Some(status)) => status.to_string(),
+            Ok(None) => "it closed its pipes".to_string(),
+            Err(err) => err.to_string(),
+        };
+
+        UciError::Crashed(status)
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// `UCI_Variant` name of a variant, as used by Fairy-Stockfish
+fn uci_variant(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Standard | Variant::Chess960 => "chess",
+        Variant::KingOfTheHill => "kingofthehill",
+        Variant::ThreeCheck => "3check",
+        Variant::Atomic => "atomic",
+        Variant::Crazyhouse => "crazyhouse",
+    }
+}
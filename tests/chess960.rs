@@ -0,0 +1,89 @@
+//! Chess960 starting positions and castling with the king and
+//! rooks away from their standard squares
+
+use std::collections::HashSet;
+
+use chess_api::chess::{Game, Position, Variant};
+use chess_api::game::{GameError, NewGameOptions};
+
+/// white's back rank in a FEN, eg. `RNBQKBNR`
+fn back_rank(position: &Position) -> String {
+    let fen = position.to_fen();
+    let board = fen.split(' ').next().unwrap();
+    board.rsplit('/').next().unwrap().to_string()
+}
+
+#[test]
+fn starting_positions() {
+    let mut back_ranks = HashSet::new();
+    for index in 0..960 {
+        let position = Position::chess960(index).unwrap();
+        let rank = back_rank(&position);
+        let files = |piece: char| -> Vec<usize> {
+            rank.char_indices()
+                .filter(|(_, c)| *c == piece)
+                .map(|(file, _)| file)
+                .collect()
+        };
+
+        // bishops on opposite colors, the king between the rooks
+        let bishops = files('B');
+        assert_eq!(bishops[0] % 2 + bishops[1] % 2, 1, "{index} {rank}");
+        let (rooks, king) = (files('R'), files('K'));
+        assert!(rooks[0] < king[0] && king[0] < rooks[1], "{index} {rank}");
+        assert_eq!(files('N').len(), 2);
+        assert_eq!(files('Q').len(), 1);
+
+        // black mirrors white
+        let fen = position.to_fen();
+        assert!(fen.starts_with(&rank.to_lowercase()), "{fen}");
+        back_ranks.insert(rank);
+    }
+    assert_eq!(back_ranks.len(), 960);
+
+    assert_eq!(back_rank(&Position::chess960(0).unwrap()), "BBQNNRKR");
+    assert_eq!(back_rank(&Position::chess960(959).unwrap()), "RKRNNQBB");
+}
+
+#[test]
+fn new_game_options() {
+    let options = NewGameOptions::parse("chess960 518").unwrap();
+    let game = options.to_game().unwrap();
+    assert_eq!(game.variant(), Variant::Chess960);
+    assert_eq!(back_rank(game.position()), "RNBQKBNR");
+
+    // without an index the starting position is random
+    let game = NewGameOptions::parse("chess960")
+        .unwrap()
+        .to_game()
+        .unwrap();
+    assert_eq!(game.variant(), Variant::Chess960);
+
+    // an index is only taken for Chess960 and in its range
+    for args in ["chess960 1000", "standard 518"] {
+        assert_eq!(
+            NewGameOptions::parse(args).unwrap_err(),
+            GameError::InvalidOption(args.rsplit(' ').next().unwrap().to_string())
+        );
+    }
+}
+
+#[test]
+fn castling() {
+    let fen = "r1k1r2q/p1ppp1pp/8/8/8/8/P1PPP1PP/R1K1R2Q w KQkq - 0 1";
+
+    // the king goes to g1 and the rook to f1 on the king side
+    let mut game = Game::new(Position::from_fen(fen).unwrap());
+    game.play("O-O").unwrap();
+    assert_eq!(back_rank(game.position()), "R4RKQ");
+
+    // on the queen side the king stays on c1, only the rook moves
+    let mut game = Game::new(Position::from_fen(fen).unwrap());
+    game.play("O-O-O").unwrap();
+    assert_eq!(back_rank(game.position()), "2KRR2Q");
+    game.play("O-O").unwrap();
+    assert!(game.position().to_fen().starts_with("r4rkq/"));
+
+    // castling rights are gone once a side castled
+    assert!(game.play("O-O").is_err());
+}
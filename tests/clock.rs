@@ -0,0 +1,294 @@
+//! Game clocks driven by hand with instants in the future
+
+use std::time::{Duration, Instant, SystemTime};
+
+use chess_api::chess::{Color, GameEndReason, GameOutcome, GameResult, Position};
+use chess_api::clock::{CorrespondenceClock, GameClock, IncrementMode, TimeControl, TimeStage};
+use chess_api::game::{GameError, NewGameOptions};
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+#[test]
+fn parse_time_control() {
+    let blitz = TimeControl::parse("3+2").unwrap();
+    assert_eq!(
+        blitz.stages,
+        vec![TimeStage {
+            moves: None,
+            time: secs(180)
+        }]
+    );
+    assert_eq!(blitz.increment, secs(2));
+    assert_eq!(blitz.to_string(), "3+2");
+    assert_eq!(blitz.pgn(), "180+2");
+
+    assert_eq!(TimeControl::parse("10+0").unwrap().pgn(), "600+0");
+    assert_eq!(TimeControl::parse("0+5"), None);
+    assert_eq!(TimeControl::parse("10"), None);
+    assert_eq!(TimeControl::parse("a+b"), None);
+    assert_eq!(TimeControl::parse("5+3x"), None);
+    assert_eq!(TimeControl::parse("0/90:30+30"), None);
+    assert_eq!(TimeControl::parse("40/90:+30"), None);
+}
+
+#[test]
+fn parse_delays_and_stages() {
+    let delay = TimeControl::parse("5+3d").unwrap();
+    assert_eq!(delay.mode, IncrementMode::Delay);
+    assert_eq!(delay.increment, secs(3));
+    assert_eq!(delay.to_string(), "5+3d");
+    assert_eq!(delay.pgn(), "300+3d");
+
+    let bronstein = TimeControl::parse("5+3b").unwrap();
+    assert_eq!(bronstein.mode, IncrementMode::Bronstein);
+    assert_eq!(bronstein.to_string(), "5+3b");
+
+    let classical = TimeControl::parse("40/90:30+30").unwrap();
+    assert_eq!(classical.mode, IncrementMode::Fischer);
+    assert_eq!(
+        classical.stages,
+        vec![
+            TimeStage {
+                moves: Some(40),
+                time: secs(5400)
+            },
+            TimeStage {
+                moves: None,
+                time: secs(1800)
+            },
+        ]
+    );
+    assert_eq!(classical.to_string(), "40/90:30+30");
+    assert_eq!(classical.pgn(), "40/5400+30:1800+30");
+}
+
+#[test]
+fn serialize_time_control() {
+    let control = TimeControl::parse("40/90:30+30").unwrap();
+    assert_eq!(
+        serde_json::to_string(&control).unwrap(),
+        r#"{"stages":[{"moves":40,"seconds":5400},{"moves":null,"seconds":1800}],"increment":30,"mode":"fischer"}"#
+    );
+}
+
+#[test]
+fn increment_and_switch() {
+    let mut clock = GameClock::new(TimeControl::parse("3+2").unwrap());
+    let start = Instant::now();
+    assert_eq!(clock.running(), None);
+
+    clock.start(Color::White, start);
+    assert_eq!(clock.remaining(Color::White, start + secs(10)), secs(170));
+    assert_eq!(clock.remaining(Color::Black, start + secs(10)), secs(180));
+
+    // white moves after 10 seconds, black's clock starts
+    clock.press(start + secs(10));
+    assert_eq!(clock.running(), Some(Color::Black));
+    assert_eq!(clock.remaining(Color::White, start + secs(20)), secs(172));
+    assert_eq!(clock.remaining(Color::Black, start + secs(20)), secs(170));
+
+    clock.stop(start + secs(30));
+    assert_eq!(clock.running(), None);
+    assert_eq!(clock.remaining(Color::Black, start + secs(60)), secs(160));
+    assert_eq!(clock.flagged(start + secs(600)), None);
+}
+
+#[test]
+fn flag() {
+    let mut clock = GameClock::new(TimeControl::parse("1+0").unwrap());
+    let start = Instant::now();
+    clock.start(Color::White, start);
+
+    assert_eq!(clock.flagged(start + secs(59)), None);
+    assert_eq!(clock.flagged(start + secs(60)), Some(Color::White));
+    assert_eq!(
+        clock.remaining(Color::White, start + secs(90)),
+        Duration::ZERO
+    );
+}
+
+#[test]
+fn timeout_outcome() {
+    let outcome = GameOutcome::timeout(&Position::starting(), Color::White);
+    assert_eq!(outcome.result, GameResult::BlackWins);
+    assert_eq!(outcome.reason, GameEndReason::Timeout);
+
+    // black has only a knight left, it cannot mate
+    let position = Position::from_fen("4k3/8/8/8/8/8/4n3/R3K3 w - - 0 1").unwrap();
+    let outcome = GameOutcome::timeout(&position, Color::White);
+    assert_eq!(outcome.result, GameResult::Draw);
+    assert_eq!(outcome.reason, GameEndReason::TimeoutVsInsufficientMaterial);
+
+    // white still has a rook when black runs out of time
+    let outcome = GameOutcome::timeout(&position, Color::Black);
+    assert_eq!(outcome.result, GameResult::WhiteWins);
+}
+
+#[test]
+fn simple_delay() {
+    let mut clock = GameClock::new(TimeControl::parse("1+5d").unwrap());
+    let start = Instant::now();
+    clock.start(Color::White, start);
+
+    // the clock does not run during the delay
+    assert_eq!(clock.remaining(Color::White, start + secs(4)), secs(60));
+    clock.press(start + secs(4));
+    assert_eq!(clock.remaining(Color::White, start + secs(4)), secs(60));
+
+    // only the time after the delay is taken off, nothing is added
+    assert_eq!(clock.remaining(Color::Black, start + secs(14)), secs(55));
+    clock.press(start + secs(14));
+    assert_eq!(clock.remaining(Color::Black, start + secs(14)), secs(55));
+    assert_eq!(clock.flagged(start + secs(78)), None);
+    assert_eq!(clock.flagged(start + secs(79)), Some(Color::White));
+}
+
+#[test]
+fn bronstein_delay() {
+    let mut clock = GameClock::new(TimeControl::parse("1+5b").unwrap());
+    let start = Instant::now();
+    clock.start(Color::White, start);
+
+    // the time used is given back, up to the delay
+    assert_eq!(clock.remaining(Color::White, start + secs(4)), secs(56));
+    clock.press(start + secs(4));
+    assert_eq!(clock.remaining(Color::White, start + secs(4)), secs(60));
+
+    clock.press(start + secs(14));
+    assert_eq!(clock.remaining(Color::Black, start + secs(14)), secs(55));
+}
+
+#[test]
+fn stages() {
+    let mut clock = GameClock::new(TimeControl::parse("2/1:1+0").unwrap());
+    let start = Instant::now();
+    clock.start(Color::White, start);
+
+    // ten seconds a move, white's second move ends its first stage
+    let mut now = start;
+    for _ in 0..3 {
+        now += secs(10);
+        clock.press(now);
+    }
+    assert_eq!(clock.remaining(Color::White, now), secs(100));
+    assert_eq!(clock.remaining(Color::Black, now), secs(50));
+
+    // black's second move ends its first stage as well
+    now += secs(10);
+    clock.press(now);
+    assert_eq!(clock.remaining(Color::Black, now), secs(100));
+
+    // the last stage has no moves, no more time is added
+    for _ in 0..4 {
+        now += secs(10);
+        clock.press(now);
+    }
+    assert_eq!(clock.remaining(Color::White, now), secs(80));
+    assert_eq!(clock.remaining(Color::Black, now), secs(80));
+}
+
+#[test]
+fn repeating_stage() {
+    let mut clock = GameClock::new(TimeControl::parse("1/1+0").unwrap());
+    let start = Instant::now();
+    clock.start(Color::White, start);
+
+    // every move adds another minute
+    clock.press(start + secs(10));
+    assert_eq!(clock.remaining(Color::White, start + secs(10)), secs(110));
+    clock.press(start + secs(20));
+    clock.press(start + secs(30));
+    assert_eq!(clock.remaining(Color::White, start + secs(30)), secs(160));
+}
+
+#[test]
+fn parse_rejects_huge_time_controls() {
+    assert_eq!(TimeControl::parse("1+18446744073709551615"), None);
+    assert_eq!(TimeControl::parse("1/1:307445734561825860+0"), None);
+    assert_eq!(TimeControl::parse("10081+0"), None);
+    assert_eq!(TimeControl::parse("1+3601"), None);
+
+    let longest = TimeControl::parse("40/10080:10080+3600d").unwrap();
+    assert_eq!(longest.stages[0].time, secs(7 * 24 * 60 * 60));
+    assert_eq!(longest.increment, secs(60 * 60));
+}
+
+#[test]
+fn press_saturates() {
+    // time controls built by hand are not bounded like parsed ones
+    let control = TimeControl {
+        stages: vec![
+            TimeStage {
+                moves: Some(1),
+                time: Duration::MAX,
+            },
+            TimeStage {
+                moves: None,
+                time: Duration::MAX,
+            },
+        ],
+        increment: Duration::MAX,
+        mode: IncrementMode::Fischer,
+    };
+    let mut clock = GameClock::new(control);
+    let start = Instant::now();
+    clock.start(Color::White, start);
+
+    clock.press(start + secs(10));
+    assert_eq!(
+        clock.remaining(Color::White, start + secs(10)),
+        Duration::MAX
+    );
+    clock.press(start + secs(20));
+    assert_eq!(
+        clock.remaining(Color::Black, start + secs(20)),
+        Duration::MAX
+    );
+}
+
+#[test]
+fn correspondence_deadlines() {
+    const DAY: u64 = 24 * 60 * 60;
+
+    assert_eq!(CorrespondenceClock::new(0), None);
+    assert_eq!(CorrespondenceClock::new(31), None);
+    assert_eq!(CorrespondenceClock::new(30).unwrap().days_per_move(), 30);
+
+    let mut clock = CorrespondenceClock::new(3).unwrap();
+    let start = SystemTime::now();
+    assert_eq!(clock.deadline(), None);
+    assert_eq!(clock.expired(start + secs(30 * DAY)), None);
+
+    clock.start(Color::White, start);
+    assert_eq!(
+        clock.deadline(),
+        Some((Color::White, start + secs(3 * DAY)))
+    );
+
+    // white moves on the second day, black gets three days from then
+    clock.press(start + secs(2 * DAY));
+    assert_eq!(
+        clock.deadline(),
+        Some((Color::Black, start + secs(5 * DAY)))
+    );
+    assert_eq!(clock.expired(start + secs(5 * DAY - 1)), None);
+    assert_eq!(clock.expired(start + secs(5 * DAY)), Some(Color::Black));
+
+    clock.stop();
+    assert_eq!(clock.expired(start + secs(30 * DAY)), None);
+}
+
+#[test]
+fn correspondence_days_option() {
+    let options = NewGameOptions::parse("days=30").unwrap();
+    assert_eq!(options.correspondence.unwrap().days_per_move(), 30);
+
+    for days in ["days=0", "days=31", "days=4294967295", "days=-1"] {
+        assert_eq!(
+            NewGameOptions::parse(days).unwrap_err(),
+            GameError::InvalidOption(days.to_string())
+        );
+    }
+}
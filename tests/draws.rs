@@ -0,0 +1,145 @@
+//! Draws by repetition, the move rules and insufficient material,
+//! the ones a player claims and the ones which end the game
+
+use chess_api::chess::types::{Color, Piece, PieceKind, Square};
+use chess_api::chess::{Game, GameEndReason, GameResult, Position};
+
+/// a game from a board with only the given pieces, white to move
+fn game_with(pieces: &[(&str, Color, PieceKind)]) -> Game {
+    let mut position = Position::empty();
+    for (square, color, kind) in pieces {
+        let square = Square::parse(square).unwrap();
+        position.set_piece(square, Some(Piece::new(*color, *kind)));
+    }
+    Game::new(position)
+}
+
+fn play(game: &mut Game, moves: &[&str]) {
+    for mv in moves {
+        game.play(mv).unwrap();
+    }
+}
+
+#[test]
+fn threefold_repetition_is_claimed() {
+    let mut game = Game::default();
+    play(
+        &mut game,
+        &["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"],
+    );
+    assert_eq!(game.claimable_draw(), None);
+
+    // the starting position occurs a third time
+    play(&mut game, &["f6g8"]);
+    assert_eq!(game.repetitions(), 3);
+    assert_eq!(
+        game.claimable_draw(),
+        Some(GameEndReason::ThreefoldRepetition)
+    );
+    assert_eq!(game.outcome(), None);
+}
+
+#[test]
+fn fivefold_repetition_ends_the_game() {
+    let mut game = Game::default();
+    for _ in 0..4 {
+        play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+    }
+
+    let outcome = game.outcome().unwrap();
+    assert_eq!(outcome.result, GameResult::Draw);
+    assert_eq!(outcome.reason, GameEndReason::FivefoldRepetition);
+}
+
+#[test]
+fn fifty_move_rule_is_claimed() {
+    let mut game = Game::new(Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 98 80").unwrap());
+    play(&mut game, &["a1a2"]);
+    assert_eq!(game.claimable_draw(), None);
+    play(&mut game, &["e8d7"]);
+    assert_eq!(game.claimable_draw(), Some(GameEndReason::FiftyMoveRule));
+
+    // a capture or pawn move starts the count again
+    let mut game = Game::new(Position::from_fen("4k3/8/8/8/8/8/p7/R3K3 w - - 99 80").unwrap());
+    play(&mut game, &["a1a2"]);
+    assert_eq!(game.claimable_draw(), None);
+}
+
+#[test]
+fn seventy_five_move_rule_ends_the_game() {
+    let mut game = Game::new(Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 120").unwrap());
+    assert_eq!(game.outcome(), None);
+    play(&mut game, &["a1a2"]);
+    assert_eq!(
+        game.outcome().unwrap().reason,
+        GameEndReason::SeventyFiveMoveRule
+    );
+}
+
+#[test]
+fn insufficient_material_ends_the_game() {
+    use Color::*;
+    use PieceKind::*;
+
+    let drawn = [
+        game_with(&[("e1", White, King), ("e8", Black, King)]),
+        game_with(&[
+            ("e1", White, King),
+            ("d1", White, Bishop),
+            ("e8", Black, King),
+        ]),
+        game_with(&[
+            ("e1", White, King),
+            ("e8", Black, King),
+            ("b8", Black, Knight),
+        ]),
+        // bishops on squares of the same color
+        game_with(&[
+            ("e1", White, King),
+            ("c1", White, Bishop),
+            ("e8", Black, King),
+            ("f8", Black, Bishop),
+        ]),
+    ];
+    for game in drawn {
+        let outcome = game.outcome().unwrap();
+        assert_eq!(outcome.reason, GameEndReason::InsufficientMaterial);
+    }
+
+    let playable = [
+        game_with(&[
+            ("e1", White, King),
+            ("a2", White, Pawn),
+            ("e8", Black, King),
+        ]),
+        game_with(&[
+            ("e1", White, King),
+            ("b1", White, Knight),
+            ("g1", White, Knight),
+            ("e8", Black, King),
+        ]),
+        // bishops on squares of both colors
+        game_with(&[
+            ("e1", White, King),
+            ("c1", White, Bishop),
+            ("e8", Black, King),
+            ("c8", Black, Bishop),
+        ]),
+    ];
+    for game in playable {
+        assert_eq!(game.outcome(), None);
+    }
+
+    // the capture of the last piece which could mate
+    let mut game = game_with(&[
+        ("e1", White, King),
+        ("d1", White, Bishop),
+        ("e2", Black, Rook),
+        ("e8", Black, King),
+    ]);
+    play(&mut game, &["e1e2"]);
+    assert_eq!(
+        game.outcome().unwrap().reason,
+        GameEndReason::InsufficientMaterial
+    );
+}
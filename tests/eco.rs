@@ -0,0 +1,96 @@
+//! Opening classification against the bundled ECO table
+
+use chess_api::chess::eco::ECO_TABLE;
+use chess_api::chess::pgn::PgnHeaders;
+use chess_api::chess::{Game, Position, Variant};
+
+fn game(moves: &[&str]) -> Game {
+    let mut game = Game::default();
+    for mv in moves {
+        game.play(mv).unwrap();
+    }
+    game
+}
+
+/// ECO code and name of the opening the game reached
+fn opening(game: &Game) -> Option<(&str, &str)> {
+    game.opening()
+        .map(|opening| (opening.eco.as_str(), opening.name.as_str()))
+}
+
+#[test]
+fn table_moves_are_legal() {
+    for line in ECO_TABLE.lines().skip(1) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        assert_eq!(fields.len(), 3, "{line}");
+
+        let mut game = Game::default();
+        for san in fields[2].split_whitespace().filter(|t| !t.ends_with('.')) {
+            game.play(san).unwrap_or_else(|err| panic!("{line}: {err}"));
+        }
+        assert!(game.opening().is_some(), "{line}");
+    }
+}
+
+#[test]
+fn deepest_opening() {
+    let mut game = game(&["e4", "e5", "Nf3", "Nc6"]);
+    assert_eq!(
+        opening(&game),
+        Some(("C44", "King's Knight Opening: Normal Variation"))
+    );
+
+    game.play("Bc4").unwrap();
+    game.play("Bc5").unwrap();
+    assert_eq!(opening(&game), Some(("C50", "Italian Game: Giuoco Piano")));
+
+    // the opening is kept once the game leaves the table
+    game.play("h3").unwrap();
+    assert_eq!(opening(&game), Some(("C50", "Italian Game: Giuoco Piano")));
+}
+
+#[test]
+fn transposition() {
+    // the Catalan Open Defense reached by another move order
+    let game = game(&["d4", "Nf6", "c4", "e6", "g3", "d5", "Bg2", "dxc4", "Nf3"]);
+    assert_eq!(
+        opening(&game),
+        Some(("E04", "Catalan Opening: Open Defense"))
+    );
+}
+
+#[test]
+fn no_opening() {
+    assert_eq!(opening(&Game::default()), None);
+
+    // variants are not classified
+    let mut atomic = Game::new(Position::starting().with_variant(Variant::Atomic));
+    atomic.play("e4").unwrap();
+    assert_eq!(opening(&atomic), None);
+}
+
+#[test]
+fn pgn_tags() {
+    let game = game(&[
+        "e4", "c5", "Nf3", "d6", "d4", "cxd4", "Nxd4", "Nf6", "Nc3", "a6",
+    ]);
+    let headers = PgnHeaders {
+        event: "Club game".to_string(),
+        site: "Chess API".to_string(),
+        date: "2024.01.01".to_string(),
+        white: "alice".to_string(),
+        black: "bob".to_string(),
+        result: "*".to_string(),
+        time_control: "-".to_string(),
+    };
+
+    let pgn = game.to_pgn(&headers);
+    assert!(pgn.contains("[ECO \"B90\"]\n"), "{pgn}");
+    assert!(
+        pgn.contains("[Opening \"Sicilian Defense: Najdorf Variation\"]\n"),
+        "{pgn}"
+    );
+
+    let pgn = Game::default().to_pgn(&headers);
+    assert!(!pgn.contains("[ECO"), "{pgn}");
+}
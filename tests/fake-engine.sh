@@ -0,0 +1,45 @@
+#!/bin/sh
+# Stand-in for a UCI engine in the tests, the first argument picks
+# how it behaves when asked to search:
+#   play    answer `go` with the move given as second argument
+#   slow    only answer `go` once told to `stop`
+#   hang    ignore `go` and `stop`
+#   crash   exit on `go`
+#   silent  never finish the `uci` handshake
+mode=${1:-play}
+move=${2:-e2e4}
+
+while read -r line; do
+    case "$line" in
+        uci)
+            [ "$mode" = silent ] && continue
+            echo "id name Fake Engine"
+            echo "id author Chess API"
+            echo "option name Hash type spin default 16 min 1 max 1024"
+            echo "option name UCI_Chess960 type check default false"
+            echo "uciok"
+            ;;
+        isready)
+            echo "readyok"
+            ;;
+        go*)
+            case "$mode" in
+                crash)
+                    exit 3
+                    ;;
+                play)
+                    echo "info depth 1 seldepth 1 score cp 20 nodes 20 time 1 pv $move"
+                    echo "info string searching"
+                    echo "info depth 2 seldepth 3 multipv 1 score cp 35 nodes 400 time 2 pv $move e7e5"
+                    echo "bestmove $move"
+                    ;;
+            esac
+            ;;
+        stop)
+            [ "$mode" = slow ] && echo "bestmove $move"
+            ;;
+        quit)
+            exit 0
+            ;;
+    esac
+done
@@ -0,0 +1,236 @@
+//! FEN, X-FEN and Shredder-FEN parsing, writing and the checks
+//! a position has to pass to be accepted
+
+use chess_api::chess::fen::FenError;
+use chess_api::chess::position::CastleSide;
+use chess_api::chess::{Color, PieceKind, Position, Square, Variant};
+
+fn assert_round_trip(fen: &str) {
+    let position = Position::from_fen(fen).expect("valid FEN");
+    assert_eq!(position.to_fen(), fen);
+}
+
+fn assert_illegal(fen: &str) {
+    match Position::from_fen(fen) {
+        Err(FenError::IllegalPosition(_)) => (),
+        other => panic!("{fen} should be an illegal position, got {other:?}"),
+    }
+}
+
+#[test]
+fn round_trip() {
+    assert_round_trip("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_round_trip("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    assert_round_trip("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3");
+    assert_round_trip("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40");
+
+    assert_eq!(
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap(),
+        Position::starting()
+    );
+}
+
+#[test]
+fn four_field_fen() {
+    let position =
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3").unwrap();
+    assert_eq!(position.halfmove_clock(), 0);
+    assert_eq!(position.fullmove_number(), 1);
+    assert_eq!(position.en_passant(), Square::parse("e3"));
+    assert_eq!(
+        position.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+}
+
+#[test]
+fn field_count() {
+    assert_eq!(
+        Position::from_fen("8/8/8/8/8/8/8/8 w -"),
+        Err(FenError::FieldCount(3))
+    );
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0"),
+        Err(FenError::FieldCount(5))
+    );
+}
+
+#[test]
+fn invalid_fields() {
+    assert!(matches!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K4 w - - 0 1"),
+        Err(FenError::Board(_))
+    ));
+    assert!(matches!(
+        Position::from_fen("4k3/8/8/8/8/8/4K3 w - - 0 1"),
+        Err(FenError::Board(_))
+    ));
+    assert!(matches!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4X3 w - - 0 1"),
+        Err(FenError::Board(_))
+    ));
+    assert!(matches!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3 x - - 0 1"),
+        Err(FenError::SideToMove(_))
+    ));
+    assert!(matches!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3 w Z - 0 1"),
+        Err(FenError::Castling(_))
+    ));
+    assert!(matches!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - e4 0 1"),
+        Err(FenError::EnPassant(_))
+    ));
+    assert!(matches!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - x 1"),
+        Err(FenError::MoveCounter(_))
+    ));
+}
+
+#[test]
+fn overlong_ranks_and_counters() {
+    // empty squares adding up past the rank, however many digits
+    let fen = format!("{}/8/8/8/8/8/8/4K2k w - - 0 1", "9".repeat(30));
+    assert!(matches!(Position::from_fen(&fen), Err(FenError::Board(_))));
+    let fen = format!("{}8/8/8/8/8/8/8/4K2k w - - 0 1", "9".repeat(29) + "3");
+    assert!(matches!(Position::from_fen(&fen), Err(FenError::Board(_))));
+
+    // a promoted marker past the last file
+    for board in [
+        "K8~/8/8/8/8/8/8/k7",
+        "K7/8/8/8/8/8/8/k8~",
+        "~K7/8/8/8/8/8/8/k7",
+    ] {
+        assert!(
+            matches!(
+                Position::from_fen(&format!("{board} w - - 0 1")),
+                Err(FenError::Board(_))
+            ),
+            "{board}"
+        );
+    }
+
+    for counters in ["0 4294967295", "10001 1", "0 10001", "0 99999999999"] {
+        assert!(matches!(
+            Position::from_fen(&format!("4k3/8/8/8/8/8/8/4K3 b - - {counters}")),
+            Err(FenError::MoveCounter(_))
+        ));
+    }
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 10000 10000").unwrap();
+    let next = position.make_move(position.legal_moves()[0]);
+    assert_eq!(next.fullmove_number(), 10_001);
+}
+
+#[test]
+fn missing_or_extra_kings() {
+    assert_illegal("8/8/8/8/8/8/8/4K3 w - - 0 1");
+    assert_illegal("4k3/8/8/8/8/8/8/8 w - - 0 1");
+    assert_illegal("4k3/8/8/8/8/8/8/3KK3 w - - 0 1");
+}
+
+#[test]
+fn pawns_on_back_ranks() {
+    assert_illegal("P3k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    assert_illegal("4k3/8/8/8/8/8/8/p3K3 b - - 0 1");
+}
+
+#[test]
+fn side_not_to_move_in_check() {
+    // the black king is attacked with white to move
+    assert_illegal("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1");
+    // the side to move may be in check
+    assert!(Position::from_fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").is_ok());
+}
+
+#[test]
+fn castling_rights_without_rook_are_dropped() {
+    let position = Position::from_fen("r3k3/8/8/8/8/8/8/4K2R w KQkq - 0 1").unwrap();
+    assert!(position.castling().has(Color::White, CastleSide::King));
+    assert!(!position.castling().has(Color::White, CastleSide::Queen));
+    assert!(!position.castling().has(Color::Black, CastleSide::King));
+    assert!(position.castling().has(Color::Black, CastleSide::Queen));
+    assert_eq!(position.to_fen(), "r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1");
+}
+
+#[test]
+fn chess960_castling_letters() {
+    // Shredder-FEN names the rook files, written back as `KQkq`
+    // since they are the outermost rooks
+    let position =
+        Position::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9")
+            .unwrap();
+    assert_eq!(position.variant(), Variant::Chess960);
+    assert_eq!(
+        position
+            .castling()
+            .rook_file(Color::White, CastleSide::King),
+        Some(7)
+    );
+    assert_eq!(
+        position
+            .castling()
+            .rook_file(Color::White, CastleSide::Queen),
+        Some(5)
+    );
+    assert_eq!(
+        position.to_fen(),
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"
+    );
+    assert_round_trip("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+
+    // X-FEN names an inner rook by its file
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/R2K1R1R w F - 0 1").unwrap();
+    assert_eq!(position.variant(), Variant::Chess960);
+    assert_eq!(
+        position
+            .castling()
+            .rook_file(Color::White, CastleSide::King),
+        Some(5)
+    );
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/R2K1R1R w F - 0 1");
+
+    // the standard squares given as files stay a Chess960 position
+    let position =
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1").unwrap();
+    assert_eq!(position.variant(), Variant::Chess960);
+    assert_eq!(
+        position.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    );
+}
+
+#[test]
+fn crazyhouse_pockets() {
+    let fen = "rnbqkb1r/ppp1pppp/5n2/8/8/8/PPPP1PPP/RNBQKBNR[Pp] w KQkq - 0 3";
+    let position = Position::from_fen(fen)
+        .unwrap()
+        .with_variant(Variant::Crazyhouse);
+    assert_eq!(position.pockets().count(Color::White, PieceKind::Pawn), 1);
+    assert_eq!(position.pockets().count(Color::Black, PieceKind::Pawn), 1);
+    assert_eq!(position.to_fen(), fen);
+
+    assert!(matches!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3[PK] w - - 0 1"),
+        Err(FenError::Pocket(_))
+    ));
+    assert!(matches!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3[P w - - 0 1"),
+        Err(FenError::Pocket(_))
+    ));
+
+    // more pieces than a game starts with, however they are counted
+    let pocket = "Q".repeat(300);
+    assert!(matches!(
+        Position::from_fen(&format!("4k3/8/8/8/8/8/8/4K3[{pocket}] w - - 0 1")),
+        Err(FenError::Pocket(_))
+    ));
+    assert!(matches!(
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[P] w KQkq - 0 1"),
+        Err(FenError::Pocket(_))
+    ));
+    assert!(Position::from_fen(&format!(
+        "4k3/8/8/8/8/8/8/4K3[{}] w - - 0 1",
+        "Q".repeat(30)
+    ))
+    .is_ok());
+}
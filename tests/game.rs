@@ -0,0 +1,49 @@
+//! Taking back moves of a game
+
+use chess_api::chess::{Game, Position};
+
+#[test]
+fn undo_restores_position() {
+    let mut game = Game::default();
+    assert_eq!(game.undo(), None);
+
+    game.play("e4").unwrap();
+    let after_e4 = *game.position();
+    let e5 = game.play("e5").unwrap();
+    game.play("Nf3").unwrap();
+
+    game.undo().unwrap();
+    assert_eq!(game.undo(), Some(e5));
+    assert_eq!(game.position(), &after_e4);
+    assert_eq!(game.moves().len(), 1);
+    assert_eq!(game.hashes().len(), 2);
+
+    game.undo().unwrap();
+    assert_eq!(game.position(), &Position::starting());
+    assert_eq!(game.undo(), None);
+}
+
+#[test]
+fn undo_opening_and_repetitions() {
+    let mut game = Game::default();
+    for mv in ["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5"] {
+        game.play(mv).unwrap();
+    }
+    assert_eq!(game.opening().unwrap().eco, "C50");
+
+    // back to the King's Knight Opening
+    game.undo().unwrap();
+    game.undo().unwrap();
+    assert_eq!(game.opening().unwrap().eco, "C44");
+
+    // knights back and out again, the position occurs twice until
+    // the knight moves are taken back
+    for mv in ["Ng1", "Nb8", "Nf3", "Nc6"] {
+        game.play(mv).unwrap();
+    }
+    assert_eq!(game.repetitions(), 2);
+    for _ in 0..4 {
+        game.undo().unwrap();
+    }
+    assert_eq!(game.repetitions(), 1);
+}
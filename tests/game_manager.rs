@@ -0,0 +1,346 @@
+//! Games kept by the game manager, from their creation until they
+//! finish or are aborted, and the rematches played after them
+
+use std::time::Duration;
+
+use chess_api::chess::pgn::game_from_pgn;
+use chess_api::chess::{Color, Game, Position};
+use chess_api::clock::{CorrespondenceClock, TimeControl};
+use chess_api::game::{GameError, GameManager, NewGameOptions};
+
+const ALICE: usize = 1;
+const BOB: usize = 2;
+const CAROL: usize = 3;
+
+/// a game alice created with white and bob joined with black, its ID
+fn started_game(manager: &mut GameManager, options: NewGameOptions) -> String {
+    let game_id = manager.new_game("alice", ALICE, Game::default(), options);
+    assert!(manager.join_game(&game_id, BOB, "bob"));
+    game_id
+}
+
+/// play moves alternating between alice and bob, white first
+fn play(manager: &mut GameManager, game_id: &str, moves: &[&str]) {
+    for mv in moves {
+        let game = manager.find_game(game_id).unwrap();
+        let session_id = game.player_id(game.side_to_move());
+        manager.play_move(game_id, session_id, mv).unwrap();
+    }
+}
+
+/// a started game alice resigned, moved to the finished games
+fn finished_game(manager: &mut GameManager, options: NewGameOptions) -> String {
+    let game_id = started_game(manager, options);
+    manager.resign(&game_id, ALICE).unwrap();
+    manager.finish_game(&game_id).unwrap();
+    game_id
+}
+
+#[test]
+fn rematch_swaps_colors() {
+    let mut manager = GameManager::new();
+    let game_id = finished_game(&mut manager, NewGameOptions::default());
+
+    let info = manager.offer_rematch(&game_id, ALICE).unwrap();
+    assert_eq!(info.color, Color::White);
+    assert_eq!(info.rematch_id, None);
+
+    let info = manager.answer_rematch(&game_id, BOB, true).unwrap();
+    assert_eq!(info.color, Color::Black);
+    let rematch_id = info.rematch_id.unwrap();
+
+    let rematch = manager.find_game(&rematch_id).unwrap();
+    assert_eq!(rematch.player_id(Color::White), BOB);
+    assert_eq!(rematch.player_id(Color::Black), ALICE);
+    assert_eq!(rematch.player_name(Color::White), Some("bob"));
+    assert_eq!(rematch.player_name(Color::Black), Some("alice"));
+    assert_eq!(rematch.name(), "alice #2");
+
+    // the rematch starts right away, bob opens it
+    manager.play_move(&rematch_id, BOB, "e4").unwrap();
+}
+
+#[test]
+fn rematch_keeps_the_time_control() {
+    let mut manager = GameManager::new();
+    let control = TimeControl::parse("40/90:30+30").unwrap();
+    let options = NewGameOptions {
+        time_control: Some(control.clone()),
+        ..NewGameOptions::default()
+    };
+    let game_id = finished_game(&mut manager, options);
+
+    manager.offer_rematch(&game_id, BOB).unwrap();
+    let rematch_id = manager
+        .answer_rematch(&game_id, ALICE, true)
+        .unwrap()
+        .rematch_id
+        .unwrap();
+
+    let rematch = manager.find_game(&rematch_id).unwrap();
+    assert_eq!(rematch.summary(None, None).time_control, Some(control));
+    assert_eq!(rematch.clock_info().unwrap().running, Some(Color::White));
+}
+
+#[test]
+fn rematch_offered_twice() {
+    let mut manager = GameManager::new();
+    let game_id = finished_game(&mut manager, NewGameOptions::default());
+
+    manager.offer_rematch(&game_id, ALICE).unwrap();
+    for session_id in [ALICE, BOB] {
+        assert_eq!(
+            manager.offer_rematch(&game_id, session_id).unwrap_err(),
+            GameError::RematchPending
+        );
+    }
+    // a player does not answer their own offer
+    assert_eq!(
+        manager.answer_rematch(&game_id, ALICE, true).unwrap_err(),
+        GameError::NoRematchOffer
+    );
+
+    // a declined offer can be made again
+    manager.answer_rematch(&game_id, BOB, false).unwrap();
+    manager.offer_rematch(&game_id, BOB).unwrap();
+    let rematch_id = manager
+        .answer_rematch(&game_id, ALICE, true)
+        .unwrap()
+        .rematch_id
+        .unwrap();
+
+    // one rematch per game
+    for session_id in [ALICE, BOB] {
+        assert_eq!(
+            manager.offer_rematch(&game_id, session_id).unwrap_err(),
+            GameError::RematchStarted(rematch_id.clone())
+        );
+    }
+    assert_eq!(
+        manager.offer_rematch(&game_id, CAROL).unwrap_err(),
+        GameError::NotAPlayer(game_id.clone())
+    );
+}
+
+#[test]
+fn no_rematch_while_in_progress() {
+    let mut manager = GameManager::new();
+    let game_id = started_game(&mut manager, NewGameOptions::default());
+
+    assert_eq!(
+        manager.offer_rematch(&game_id, ALICE).unwrap_err(),
+        GameError::GameInProgress(game_id.clone())
+    );
+    assert_eq!(
+        manager.offer_rematch("no-such-game", ALICE).unwrap_err(),
+        GameError::GameNotFound("no-such-game".to_string())
+    );
+}
+
+#[test]
+fn abort_before_both_sides_move() {
+    let mut manager = GameManager::new();
+    let game_id = started_game(&mut manager, NewGameOptions::default());
+
+    let info = manager.abort(&game_id, ALICE).unwrap();
+    assert_eq!(info.aborted_by, Some(Color::White));
+    assert!(manager.find_game(&game_id).is_none());
+
+    // either side may abort until black made its first move
+    let game_id = started_game(&mut manager, NewGameOptions::default());
+    play(&mut manager, &game_id, &["e4"]);
+    assert_eq!(
+        manager.abort(&game_id, CAROL).unwrap_err(),
+        GameError::NotAPlayer(game_id.clone())
+    );
+    let info = manager.abort(&game_id, BOB).unwrap();
+    assert_eq!(info.aborted_by, Some(Color::Black));
+    assert_eq!(
+        manager.abort(&game_id, BOB).unwrap_err(),
+        GameError::GameNotFound(game_id.clone())
+    );
+}
+
+#[test]
+fn no_abort_after_both_sides_move() {
+    let mut manager = GameManager::new();
+    let game_id = started_game(&mut manager, NewGameOptions::default());
+    play(&mut manager, &game_id, &["e4", "e5"]);
+
+    for session_id in [ALICE, BOB] {
+        assert_eq!(
+            manager.abort(&game_id, session_id).unwrap_err(),
+            GameError::AbortTooLate(game_id.clone())
+        );
+    }
+    assert!(manager.find_game(&game_id).is_some());
+
+    // a finished game is no longer active
+    manager.resign(&game_id, BOB).unwrap();
+    assert_eq!(
+        manager.abort(&game_id, ALICE).unwrap_err(),
+        GameError::GameOver(game_id.clone())
+    );
+}
+
+#[test]
+fn abort_games_seeded_from_pgn() {
+    let mut manager = GameManager::new();
+    let chess = game_from_pgn("end", "1. e4 e5 2. Nf3 *").unwrap();
+    let game_id = manager.new_game("alice", ALICE, chess, NewGameOptions::default());
+    manager.join_game(&game_id, BOB, "bob");
+
+    // black is to move, each side moves once after the seeded moves
+    play(&mut manager, &game_id, &["Nc6"]);
+    assert!(manager.awaiting_first_move(&game_id));
+    play(&mut manager, &game_id, &["Bb5"]);
+    assert!(!manager.awaiting_first_move(&game_id));
+    assert_eq!(
+        manager.abort(&game_id, ALICE).unwrap_err(),
+        GameError::AbortTooLate(game_id.clone())
+    );
+
+    let chess = game_from_pgn("end", "1. e4 e5 2. Nf3 *").unwrap();
+    let game_id = manager.new_game("alice", ALICE, chess, NewGameOptions::default());
+    manager.join_game(&game_id, BOB, "bob");
+    play(&mut manager, &game_id, &["Nc6"]);
+    let info = manager.abort(&game_id, BOB).unwrap();
+    assert_eq!(info.aborted_by, Some(Color::Black));
+}
+
+#[test]
+fn abort_idle_games() {
+    let mut manager = GameManager::new();
+
+    // nobody waits for a first move before the game starts
+    let waiting = manager.new_game("alice", ALICE, Game::default(), NewGameOptions::default());
+    assert!(!manager.awaiting_first_move(&waiting));
+    assert!(manager.abort_if_idle(&waiting, Duration::ZERO).is_none());
+
+    let game_id = started_game(&mut manager, NewGameOptions::default());
+    assert!(manager.awaiting_first_move(&game_id));
+    assert!(manager
+        .abort_if_idle(&game_id, Duration::from_secs(60))
+        .is_none());
+
+    let info = manager.abort_if_idle(&game_id, Duration::ZERO).unwrap();
+    assert_eq!(info.aborted_by, None);
+    assert!(manager.find_game(&game_id).is_none());
+
+    // once both sides moved the game is never idle
+    let game_id = started_game(&mut manager, NewGameOptions::default());
+    play(&mut manager, &game_id, &["e4", "e5"]);
+    assert!(manager.abort_if_idle(&game_id, Duration::ZERO).is_none());
+
+    // correspondence games have their own deadlines
+    let options = NewGameOptions {
+        correspondence: CorrespondenceClock::new(3),
+        ..NewGameOptions::default()
+    };
+    let game_id = started_game(&mut manager, options);
+    assert!(manager.abort_if_idle(&game_id, Duration::ZERO).is_none());
+    assert!(manager.find_game(&game_id).is_some());
+}
+
+#[test]
+fn analysis_only_once_the_game_is_over() {
+    let mut manager = GameManager::new();
+    let game_id = started_game(&mut manager, NewGameOptions::default());
+    play(&mut manager, &game_id, &["e4", "e5"]);
+
+    // neither the players nor anyone else get the engine's help
+    assert_eq!(
+        manager.analysis_game(&game_id).unwrap_err(),
+        GameError::AnalysisNotAllowed(game_id.clone())
+    );
+
+    manager.resign(&game_id, BOB).unwrap();
+    manager.finish_game(&game_id).unwrap();
+    assert_eq!(manager.analysis_game(&game_id).unwrap().moves().len(), 2);
+}
+
+#[test]
+fn delete_only_own_waiting_games() {
+    let mut manager = GameManager::new();
+    let waiting = manager.new_game("alice", ALICE, Game::default(), NewGameOptions::default());
+    assert_eq!(
+        manager.delete_game(&waiting, BOB).unwrap_err(),
+        GameError::NotAPlayer(waiting.clone())
+    );
+    manager.delete_game(&waiting, ALICE).unwrap();
+    assert!(manager.find_game(&waiting).is_none());
+    assert_eq!(
+        manager.delete_game(&waiting, ALICE).unwrap_err(),
+        GameError::GameNotFound(waiting.clone())
+    );
+
+    // a started game is aborted or played out instead
+    let game_id = started_game(&mut manager, NewGameOptions::default());
+    for session_id in [ALICE, BOB] {
+        assert_eq!(
+            manager.delete_game(&game_id, session_id).unwrap_err(),
+            GameError::GameInProgress(game_id.clone())
+        );
+    }
+    manager.resign(&game_id, ALICE).unwrap();
+    assert_eq!(
+        manager.delete_game(&game_id, BOB).unwrap_err(),
+        GameError::GameOver(game_id.clone())
+    );
+    assert!(manager.find_game(&game_id).is_some());
+}
+
+#[test]
+fn legal_moves_by_square() {
+    let mut manager = GameManager::new();
+    let game_id = started_game(&mut manager, NewGameOptions::default());
+
+    let info = manager.legal_moves(&game_id, None).unwrap();
+    assert_eq!(info.side_to_move, Color::White);
+    assert_eq!(info.moves.len(), 20);
+
+    let info = manager.legal_moves(&game_id, Some("g1")).unwrap();
+    let moves: Vec<_> = info.moves.iter().map(|mv| (&*mv.uci, &*mv.san)).collect();
+    assert_eq!(moves.len(), 2);
+    assert!(moves.contains(&("g1f3", "Nf3")) && moves.contains(&("g1h3", "Nh3")));
+
+    // pieces of the side not to move and empty squares have none
+    for square in ["e7", "e4"] {
+        assert!(manager
+            .legal_moves(&game_id, Some(square))
+            .unwrap()
+            .moves
+            .is_empty());
+    }
+    assert_eq!(
+        manager.legal_moves(&game_id, Some("z9")).unwrap_err(),
+        GameError::InvalidSquare("z9".to_string())
+    );
+    assert_eq!(
+        manager.legal_moves("no-such-game", None).unwrap_err(),
+        GameError::GameNotFound("no-such-game".to_string())
+    );
+
+    // a finished game has no moves left
+    manager.resign(&game_id, ALICE).unwrap();
+    assert!(manager
+        .legal_moves(&game_id, None)
+        .unwrap()
+        .moves
+        .is_empty());
+}
+
+#[test]
+fn legal_promotions() {
+    let mut manager = GameManager::new();
+    let chess = Game::new(Position::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap());
+    let game_id = manager.new_game("alice", ALICE, chess, NewGameOptions::default());
+
+    let info = manager.legal_moves(&game_id, Some("a7")).unwrap();
+    let sans: Vec<_> = info.moves.iter().map(|mv| mv.san.as_str()).collect();
+    assert_eq!(sans.len(), 4);
+    for san in ["a8=Q", "a8=R", "a8=B", "a8=N"] {
+        assert!(sans.iter().any(|s| s.starts_with(san)), "{san}");
+    }
+    assert!(info.moves.iter().all(|mv| mv.promotion.is_some()));
+}
@@ -0,0 +1,169 @@
+//! Moves written in SAN, long algebraic and UCI notation, resolved
+//! against the legal moves and written back the same way whatever
+//! notation they came in
+
+use chess_api::chess::moves::MoveError;
+use chess_api::chess::{Move, MoveKind, PieceKind, Position, Square};
+
+fn from_fen(fen: &str) -> Position {
+    Position::from_fen(fen).expect("valid FEN")
+}
+
+fn square(name: &str) -> Square {
+    Square::parse(name).expect("valid square")
+}
+
+/// the move every notation resolves to, with its SAN and UCI
+fn assert_same_move(position: &Position, notations: &[&str], san: &str, uci: &str) {
+    let expected = position.parse_move(notations[0]).unwrap();
+    for notation in notations {
+        assert_eq!(
+            position.parse_move(notation),
+            Ok(expected),
+            "{notation} in {}",
+            position.to_fen()
+        );
+    }
+    assert_eq!(position.san(expected), san);
+    assert_eq!(position.uci(expected), uci);
+}
+
+#[test]
+fn notations_resolve_to_the_same_move() {
+    let start = Position::starting();
+    assert_same_move(&start, &["e4", "e2e4", "e2-e4", "Pe2-e4"], "e4", "e2e4");
+    assert_same_move(&start, &["Nf3", "g1f3", "Ng1-f3", "Ng1f3"], "Nf3", "g1f3");
+
+    // check suffixes and annotations are ignored
+    let position = from_fen("rnbqkbnr/ppppp2p/5p2/6p1/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3");
+    assert_same_move(
+        &position,
+        &["Qh5#", "Qh5", "Qh5+", "Qh5!", "d1h5", "Qd1-h5#"],
+        "Qh5#",
+        "d1h5",
+    );
+}
+
+#[test]
+fn captures() {
+    let position = from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2");
+    assert_same_move(&position, &["exd5", "ed5", "e4xd5", "e4d5"], "exd5", "e4d5");
+
+    // en passant
+    let position = from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3");
+    assert_same_move(&position, &["exf6", "e5f6", "e5xf6"], "exf6", "e5f6");
+    assert_eq!(
+        position.parse_move("exf6").unwrap().kind,
+        MoveKind::EnPassant
+    );
+}
+
+#[test]
+fn disambiguation() {
+    // knights on b1 and f3 can both go to d2
+    let position = from_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1");
+    assert_eq!(
+        position.parse_move("Nd2"),
+        Err(MoveError::AmbiguousMove("Nd2".to_string()))
+    );
+    assert_same_move(&position, &["Nbd2", "b1d2", "Nb1d2"], "Nbd2", "b1d2");
+    assert_same_move(&position, &["Nfd2", "f3d2"], "Nfd2", "f3d2");
+
+    // rooks on a1 and a5 share the file
+    let position = from_fen("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1");
+    assert_same_move(&position, &["R1a3", "a1a3"], "R1a3", "a1a3");
+    assert_same_move(&position, &["R5a3", "a5a3"], "R5a3", "a5a3");
+
+    // queens on a1, a3 and c1 need file and rank for b2
+    let position = from_fen("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1");
+    assert_same_move(&position, &["Qa1b2", "a1b2"], "Qa1b2", "a1b2");
+    assert_eq!(
+        position.parse_move("Qab2"),
+        Err(MoveError::AmbiguousMove("Qab2".to_string()))
+    );
+}
+
+#[test]
+fn promotions() {
+    let position = from_fen("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1");
+    assert_same_move(
+        &position,
+        &["e8=Q+", "e8Q", "e7e8q", "e7-e8=Q"],
+        "e8=Q+",
+        "e7e8q",
+    );
+    assert_same_move(&position, &["e8=N", "e8N", "e7e8n"], "e8=N", "e7e8n");
+    assert_same_move(
+        &position,
+        &["exd8=R+", "exd8R", "e7d8r", "e7xd8=R+"],
+        "exd8=R+",
+        "e7d8r",
+    );
+
+    assert_eq!(
+        position.parse_move("e8=Q").unwrap(),
+        Move::promotion(square("e7"), square("e8"), PieceKind::Queen)
+    );
+
+    // the piece is required and has to be a legal one
+    assert!(matches!(
+        position.parse_move("e8"),
+        Err(MoveError::IllegalMove(_))
+    ));
+    assert!(matches!(
+        position.parse_move("e7e8k"),
+        Err(MoveError::InvalidNotation(_))
+    ));
+}
+
+#[test]
+fn castling() {
+    let position = from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert_same_move(&position, &["O-O", "0-0", "e1g1", "Ke1-g1"], "O-O", "e1g1");
+    assert_same_move(&position, &["O-O-O", "0-0-0", "e1c1"], "O-O-O", "e1c1");
+    assert_eq!(position.parse_move("O-O").unwrap().kind, MoveKind::Castle);
+
+    // the king may take its own rook
+    assert_same_move(&position, &["O-O", "e1h1"], "O-O", "e1g1");
+    assert_same_move(&position, &["O-O-O", "e1a1"], "O-O-O", "e1c1");
+
+    // Chess960 castling is written as the king taking its rook
+    let position = from_fen("4k3/8/8/8/8/8/8/1R2K1R1 w GB - 0 1");
+    assert_same_move(&position, &["O-O", "0-0", "e1g1"], "O-O", "e1g1");
+    assert_same_move(&position, &["O-O-O", "e1b1"], "O-O-O", "e1b1");
+
+    // no castling through check
+    let position = from_fen("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    assert!(matches!(
+        position.parse_move("O-O"),
+        Err(MoveError::IllegalMove(_))
+    ));
+    assert!(position.parse_move("O-O-O").is_ok());
+}
+
+#[test]
+fn invalid_and_illegal() {
+    let start = Position::starting();
+    for notation in ["", "e9", "Zf3", "e2e", "xyz", "Nf3é"] {
+        assert!(
+            matches!(
+                start.parse_move(notation),
+                Err(MoveError::InvalidNotation(_))
+            ),
+            "{notation:?}"
+        );
+    }
+    for notation in ["e5", "e2e5", "Nf4", "Ke2", "O-O", "g1e2"] {
+        assert!(
+            matches!(start.parse_move(notation), Err(MoveError::IllegalMove(_))),
+            "{notation:?}"
+        );
+    }
+
+    // a pinned piece can not move
+    let position = from_fen("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1");
+    assert!(matches!(
+        position.parse_move("Nf4"),
+        Err(MoveError::IllegalMove(_))
+    ));
+}
@@ -0,0 +1,128 @@
+//! Move generation checked against published perft counts,
+//! positions from https://www.chessprogramming.org/Perft_Results
+
+use chess_api::chess::{Position, Variant};
+
+fn assert_perft(position: Position, counts: &[u64]) {
+    for (depth, expected) in counts.iter().enumerate() {
+        let depth = depth as u32 + 1;
+        assert_eq!(
+            position.perft(depth),
+            *expected,
+            "perft({depth}) of {}",
+            position.to_fen()
+        );
+    }
+}
+
+fn assert_fen_perft(fen: &str, counts: &[u64]) {
+    let position = Position::from_fen(fen).expect("valid FEN");
+    assert_perft(position, counts);
+}
+
+#[test]
+fn initial_position() {
+    assert_perft(Position::starting(), &[20, 400, 8902, 197281]);
+}
+
+#[test]
+fn kiwipete() {
+    assert_fen_perft(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2039, 97862],
+    );
+}
+
+#[test]
+fn en_passant_and_pins() {
+    assert_fen_perft(
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        &[14, 191, 2812, 43238],
+    );
+}
+
+#[test]
+fn castling_and_promotions() {
+    assert_fen_perft(
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[6, 264, 9467],
+    );
+    // the same position with the colors reversed
+    assert_fen_perft(
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        &[6, 264, 9467],
+    );
+}
+
+#[test]
+fn promotion_with_discovered_check() {
+    assert_fen_perft(
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[44, 1486, 62379],
+    );
+}
+
+#[test]
+fn middlegame() {
+    assert_fen_perft(
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[46, 2079, 89890],
+    );
+}
+
+#[test]
+fn underpromotions() {
+    assert_fen_perft(
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        &[24, 496, 9483, 182838],
+    );
+}
+
+#[test]
+fn chess960_positions() {
+    assert_fen_perft(
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        &[21, 528, 12189],
+    );
+    assert_fen_perft(
+        "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        &[21, 807, 18002],
+    );
+    assert_fen_perft(
+        "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+        &[20, 479, 10471],
+    );
+    // king and rook on their destination squares
+    assert_fen_perft(
+        "r1k1r2q/p1ppp1pp/8/8/8/8/P1PPP1PP/R1K1R2Q w KQkq - 0 1",
+        &[23, 522, 12333],
+    );
+    assert_fen_perft(
+        "r1k2r1q/p1ppp1pp/8/8/8/8/P1PPP1PP/R1K2R1Q w KQkq - 0 1",
+        &[28, 738, 20218],
+    );
+}
+
+#[test]
+fn chess960_standard_index() {
+    let position = Position::chess960(518).expect("valid index");
+    assert_eq!(
+        position.to_fen(),
+        Position::starting().to_fen(),
+        "index 518 is the standard starting position"
+    );
+    assert!(Position::chess960(960).is_none());
+}
+
+#[test]
+fn variants() {
+    let starting = Position::starting();
+    assert_perft(
+        starting.with_variant(Variant::Atomic),
+        &[20, 400, 8902, 197326],
+    );
+    assert_perft(
+        starting.with_variant(Variant::Crazyhouse),
+        &[20, 400, 8902, 197281],
+    );
+}
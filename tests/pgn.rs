@@ -0,0 +1,403 @@
+//! Importing games from PGN, the movetext syntax the parser accepts,
+//! variations and the start points play continues from, and the
+//! games exported back to PGN
+
+use chess_api::chess::pgn::{game_from_pgn, parse_pgn, PgnError, PgnHeaders, PgnMove};
+use chess_api::chess::{Color, Game, Position};
+use chess_api::game::{GameError, NewGameOptions};
+
+const TWO_GAMES: &str = r#"[Event "First"]
+[White "alice"]
+[Black "bob"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 1-0
+
+[Event "Second"]
+[Result "0-1"]
+
+1. d4 d5 2. c4 0-1
+"#;
+
+fn sans(line: &[PgnMove]) -> Vec<&str> {
+    line.iter().map(|pgn_move| pgn_move.san.as_str()).collect()
+}
+
+fn headers(result: &str) -> PgnHeaders {
+    PgnHeaders {
+        event: "Casual \"blitz\"".to_string(),
+        site: "Chess API".to_string(),
+        date: "2024.01.02".to_string(),
+        white: "alice".to_string(),
+        black: "bob".to_string(),
+        result: result.to_string(),
+        time_control: "180+2".to_string(),
+    }
+}
+
+#[test]
+fn tags_and_result() {
+    let games = parse_pgn(TWO_GAMES).unwrap();
+    assert_eq!(games.len(), 2);
+
+    assert_eq!(games[0].tags[0], ("Event".to_string(), "First".to_string()));
+    assert_eq!(games[0].tags.len(), 4);
+    assert_eq!(
+        sans(&games[0].moves),
+        ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]
+    );
+    assert_eq!(games[0].result, "1-0");
+
+    assert_eq!(
+        games[1].tags[0],
+        ("Event".to_string(), "Second".to_string())
+    );
+    assert_eq!(sans(&games[1].moves), ["d4", "d5", "c4"]);
+    assert_eq!(games[1].result, "0-1");
+}
+
+#[test]
+fn comments() {
+    let pgn = "{before the game} 1. e4 {best by test} e5 ; the usual reply\n2. Nf3 {one} {two} *";
+    let games = parse_pgn(pgn).unwrap();
+    let moves = &games[0].moves;
+
+    assert_eq!(sans(moves), ["e4", "e5", "Nf3"]);
+    assert_eq!(moves[0].comment.as_deref(), Some("best by test"));
+    assert_eq!(moves[1].comment.as_deref(), Some("the usual reply"));
+    assert_eq!(moves[2].comment.as_deref(), Some("one two"));
+    assert_eq!(games[0].result, "*");
+
+    assert_eq!(
+        parse_pgn("1. e4 {never closed"),
+        Err(PgnError::UnterminatedComment)
+    );
+}
+
+#[test]
+fn escaped_lines() {
+    let pgn = "% exported by some program\n1. e4 e5\n%2. d4\n2. Nf3 *";
+    let games = parse_pgn(pgn).unwrap();
+    assert_eq!(sans(&games[0].moves), ["e4", "e5", "Nf3"]);
+
+    // only a `%` at the start of a line escapes it
+    assert!(parse_pgn("1. e4 % e5").is_err());
+}
+
+#[test]
+fn nags_and_suffixes() {
+    let pgn = "1. e4! e5? 2. Nf3!! Nc6?? 3. Bb5!? a6?! 4. Ba4 $10 $14 *";
+    let games = parse_pgn(pgn).unwrap();
+    let nags: Vec<Vec<u8>> = games[0]
+        .moves
+        .iter()
+        .map(|pgn_move| pgn_move.nags.clone())
+        .collect();
+
+    assert_eq!(
+        nags,
+        [
+            vec![1],
+            vec![2],
+            vec![3],
+            vec![4],
+            vec![5],
+            vec![6],
+            vec![10, 14],
+        ]
+    );
+    assert_eq!(sans(&games[0].moves)[4], "Bb5");
+
+    // move numbers may be glued to the move
+    let games = parse_pgn("1.e4 e5 2.Nf3!? *").unwrap();
+    assert_eq!(sans(&games[0].moves), ["e4", "e5", "Nf3"]);
+    assert_eq!(games[0].moves[2].nags, [5]);
+}
+
+#[test]
+fn nested_variations() {
+    let pgn = "1. e4 e5 (1... c5 2. Nf3 (2. Nc3 Nc6) 2... d6) (1... e6) 2. Nf3 *";
+    let games = parse_pgn(pgn).unwrap();
+    let moves = &games[0].moves;
+
+    assert_eq!(sans(moves), ["e4", "e5", "Nf3"]);
+    assert_eq!(moves[1].variations.len(), 2);
+
+    let sicilian = &moves[1].variations[0];
+    assert_eq!(sans(sicilian), ["c5", "Nf3", "d6"]);
+    assert_eq!(sans(&sicilian[1].variations[0]), ["Nc3", "Nc6"]);
+    assert_eq!(sans(&moves[1].variations[1]), ["e6"]);
+
+    // moves of a variation are checked in their own position
+    assert!(matches!(
+        parse_pgn("1. e4 e5 (1... Nf3) *"),
+        Err(PgnError::InvalidMove(_))
+    ));
+}
+
+#[test]
+fn unbalanced_variations() {
+    assert_eq!(
+        parse_pgn("1. e4 (1. d4 *"),
+        Err(PgnError::UnbalancedVariation)
+    );
+    assert_eq!(parse_pgn("1. e4 ) *"), Err(PgnError::UnbalancedVariation));
+    assert_eq!(
+        parse_pgn("(1. d4) 1. e4 *"),
+        Err(PgnError::UnbalancedVariation)
+    );
+}
+
+#[test]
+fn deep_variations_are_rejected() {
+    // an opening bracket without a move to branch from fails before
+    // nesting deeper, however many of them follow
+    let pgn = format!("1. e4 {}", "(".repeat(60_000));
+    assert_eq!(parse_pgn(&pgn), Err(PgnError::UnbalancedVariation));
+
+    // variations alternating between two first moves
+    let pgn = format!("1. e4 {}{} *", "(1. d4 ".repeat(60_000), ")".repeat(60_000));
+    assert!(matches!(
+        parse_pgn(&pgn),
+        Err(PgnError::VariationTooDeep(_))
+    ));
+
+    let pgn = format!("1. e4 {}{} *", "(1. d4 ".repeat(8), ")".repeat(8));
+    assert!(parse_pgn(&pgn).is_ok());
+}
+
+#[test]
+fn setup_position() {
+    let pgn = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 40"]
+
+40. e4 Kd7 41. e5 *"#;
+    let games = parse_pgn(pgn).unwrap();
+    assert_eq!(
+        games[0].start,
+        Position::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 40").unwrap()
+    );
+    assert_eq!(games[0].ply_at(41, Color::White), Some(2));
+    assert_eq!(games[0].ply_at(39, Color::White), None);
+}
+
+#[test]
+fn start_points() {
+    let pgn = "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 *";
+
+    // white to play move 2
+    let game = game_from_pgn("2", pgn).unwrap();
+    assert_eq!(game.moves().len(), 2);
+    assert_eq!(game.position().side_to_move(), Color::White);
+
+    // black to play move 2
+    let game = game_from_pgn("2...", pgn).unwrap();
+    assert_eq!(game.moves().len(), 3);
+    assert_eq!(game.position().side_to_move(), Color::Black);
+
+    let game = game_from_pgn("end", pgn).unwrap();
+    assert_eq!(game.moves().len(), 6);
+
+    let game = game_from_pgn("1", pgn).unwrap();
+    assert_eq!(game.position(), &Position::starting());
+
+    // the main line does not reach move 23
+    assert!(matches!(
+        game_from_pgn("23", pgn),
+        Err(PgnError::InvalidStartPoint(_))
+    ));
+    assert!(matches!(
+        game_from_pgn("23...", pgn),
+        Err(PgnError::InvalidStartPoint(_))
+    ));
+}
+
+#[test]
+fn start_point_deep_in_game() {
+    // pawns, then pieces, a square forward each until move 24
+    let moves = "a3 a6 b3 b6 c3 c6 d3 d6 e3 e6 f3 f6 g3 g6 h3 h6 a4 a5 h4 h5 \
+        Nd2 Nd7 Ne2 Ne7 Nc4 Nc5 Nf4 Nf5 Bb2 Bb7 Bh3 Bh6 Qc2 Qc7 Kf2 Kf7 \
+        Rae1 Rae8 Rhg1 Rhg8 Qd1 Qd8 Re2 Re7 Rg2 Rg7 Nd2 Nd7";
+    let moves: Vec<&str> = moves.split_whitespace().collect();
+    let pgn: String = moves
+        .chunks(2)
+        .enumerate()
+        .map(|(index, pair)| format!("{}. {} {} ", index + 1, pair[0], pair[1]))
+        .collect();
+
+    let game = game_from_pgn("23", &pgn).unwrap();
+    assert_eq!(game.moves().len(), 44);
+    assert_eq!(game.position().fullmove_number(), 23);
+    assert_eq!(game.position().side_to_move(), Color::White);
+
+    let game = game_from_pgn("23...", &pgn).unwrap();
+    assert_eq!(game.moves().len(), 45);
+    assert_eq!(game.position().fullmove_number(), 23);
+    assert_eq!(game.position().side_to_move(), Color::Black);
+}
+
+#[test]
+fn start_point_in_later_game() {
+    let game = game_from_pgn("2:2", TWO_GAMES).unwrap();
+    assert_eq!(game.moves().len(), 2);
+    assert_eq!(
+        game.position().to_fen(),
+        "rnbqkbnr/ppp1pppp/8/3p4/3P4/8/PPP1PPPP/RNBQKBNR w KQkq d6 0 2"
+    );
+
+    let game = game_from_pgn("1:3", TWO_GAMES).unwrap();
+    assert_eq!(game.moves().len(), 4);
+
+    assert!(matches!(
+        game_from_pgn("3:end", TWO_GAMES),
+        Err(PgnError::InvalidStartPoint(_))
+    ));
+    assert!(matches!(
+        game_from_pgn("x:end", TWO_GAMES),
+        Err(PgnError::InvalidStartPoint(_))
+    ));
+}
+
+#[test]
+fn decided_start_points() {
+    // checkmate on the board
+    let pgn = "1. f3 e5 2. g4 Qh4# *";
+    assert_eq!(
+        game_from_pgn("end", pgn).unwrap_err(),
+        PgnError::DecidedStartPoint("end".to_string())
+    );
+    assert!(game_from_pgn("2...", pgn).is_ok());
+
+    // a result after the last move, resigned or agreed
+    for result in ["1-0", "0-1", "1/2-1/2"] {
+        let pgn = format!("1. e4 e5 2. Nf3 {result}");
+        assert_eq!(
+            game_from_pgn("end", &pgn).unwrap_err(),
+            PgnError::DecidedStartPoint("end".to_string())
+        );
+        assert_eq!(
+            game_from_pgn("2...", &pgn).unwrap_err(),
+            PgnError::DecidedStartPoint("2...".to_string())
+        );
+        assert!(game_from_pgn("2", &pgn).is_ok());
+    }
+}
+
+#[test]
+fn huge_start_points() {
+    let pgn = "1. e4 e5 *";
+    for start_point in ["3000000000", "4294967295...", "99999999999"] {
+        assert!(matches!(
+            game_from_pgn(start_point, pgn),
+            Err(PgnError::InvalidStartPoint(_))
+        ));
+    }
+
+    let pgn = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 10000"]
+
+10000. e4 *"#;
+    let games = parse_pgn(pgn).unwrap();
+    assert_eq!(games[0].ply_at(u32::MAX, Color::Black), None);
+    assert_eq!(games[0].ply_at(10_000, Color::Black), Some(1));
+}
+
+#[test]
+fn seeding_options() {
+    let (game, options) =
+        NewGameOptions::parse_pgn("2 5+3 [Event \"Club game\"] 1. e4 e5 2. Nf3 *").unwrap();
+    assert_eq!(game.moves().len(), 2);
+    assert_eq!(options.time_control.unwrap().to_string(), "5+3");
+
+    let (game, options) = NewGameOptions::parse_pgn("end bot color=black 1. e4 *").unwrap();
+    assert_eq!(game.moves().len(), 1);
+    assert_eq!(options.bot.unwrap().color, Color::White);
+
+    let (_, options) = NewGameOptions::parse_pgn("1 days=3 1. e4 *").unwrap();
+    assert_eq!(options.correspondence.unwrap().days_per_move(), 3);
+
+    // the PGN sets the variant and the position
+    assert!(matches!(
+        NewGameOptions::parse_pgn("1 chess960 1. e4 *"),
+        Err(GameError::InvalidOption(_))
+    ));
+    assert_eq!(
+        NewGameOptions::parse_pgn("1 5+3").unwrap_err(),
+        GameError::InvalidPgn(PgnError::NoGames)
+    );
+    assert_eq!(
+        NewGameOptions::parse_pgn("end 1. f3 e5 2. g4 Qh4# 0-1").unwrap_err(),
+        GameError::InvalidPgn(PgnError::DecidedStartPoint("end".to_string()))
+    );
+}
+
+#[test]
+fn no_games() {
+    assert_eq!(parse_pgn(""), Err(PgnError::NoGames));
+    assert_eq!(parse_pgn("  % nothing here\n"), Err(PgnError::NoGames));
+}
+
+#[test]
+fn export_tags_and_movetext() {
+    let mut game = Game::default();
+    for san in ["e4", "e5", "Nf3", "Nc6", "Bb5"] {
+        game.play(san).unwrap();
+    }
+
+    let pgn = game.to_pgn(&headers("*"));
+    let (tags, movetext) = pgn.split_once("\n\n").unwrap();
+    assert_eq!(
+        tags.lines().collect::<Vec<_>>(),
+        [
+            r#"[Event "Casual \"blitz\""]"#,
+            r#"[Site "Chess API"]"#,
+            r#"[Date "2024.01.02"]"#,
+            r#"[Round "-"]"#,
+            r#"[White "alice"]"#,
+            r#"[Black "bob"]"#,
+            r#"[Result "*"]"#,
+            r#"[TimeControl "180+2"]"#,
+            r#"[ECO "C60"]"#,
+            r#"[Opening "Ruy Lopez"]"#,
+        ]
+    );
+    assert_eq!(movetext, "1. e4 e5 2. Nf3 Nc6 3. Bb5 *\n");
+
+    // the export reads back as the same game
+    let games = parse_pgn(&pgn).unwrap();
+    assert_eq!(sans(&games[0].moves), ["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+    assert_eq!(games[0].tags[0].1, "Casual \"blitz\"");
+}
+
+#[test]
+fn export_from_setup_position() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30";
+    let mut game = Game::new(Position::from_fen(fen).unwrap());
+    for san in ["Kd7", "e4", "Ke6"] {
+        game.play(san).unwrap();
+    }
+
+    let pgn = game.to_pgn(&headers("*"));
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n"));
+    assert!(pgn.ends_with("\n\n30... Kd7 31. e4 Ke6 *\n"));
+
+    let imported = game_from_pgn("end", &pgn).unwrap();
+    assert_eq!(imported.position(), game.position());
+}
+
+#[test]
+fn export_wraps_long_movetext() {
+    let mut game = Game::default();
+    for _ in 0..10 {
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+            game.play(san).unwrap();
+        }
+    }
+
+    let pgn = game.to_pgn(&headers("1/2-1/2"));
+    let movetext = pgn.split_once("\n\n").unwrap().1;
+    assert!(movetext.lines().count() > 1);
+    assert!(movetext.lines().all(|line| line.len() <= 80));
+    assert!(movetext.starts_with("1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3"));
+    assert!(movetext.ends_with(" 20. Ng1 Ng8 1/2-1/2\n"));
+}
@@ -0,0 +1,148 @@
+//! Game reports built from engine searches given by hand
+
+use chess_api::chess::pgn::{parse_pgn, PgnHeaders};
+use chess_api::chess::{Color, Game};
+use chess_api::report::{GameReport, MoveJudgement};
+use chess_api::uci::{UciInfo, UciScore, UciSearch};
+
+/// the game played from the standard starting position
+fn game(moves: &[&str]) -> Game {
+    let mut game = Game::default();
+    for mv in moves {
+        game.play(mv).unwrap();
+    }
+    game
+}
+
+/// search of the position after `ply` half moves of the game,
+/// scored from the side to move's point of view
+fn search(game: &Game, ply: usize, best_move: &str, score: UciScore) -> UciSearch {
+    let mut position = *game.start();
+    for mv in &game.moves()[..ply] {
+        position = position.make_move(*mv);
+    }
+
+    UciSearch {
+        best_move: Some(position.parse_move(best_move).unwrap()),
+        info: Some(UciInfo {
+            score: Some(score),
+            ..UciInfo::default()
+        }),
+    }
+}
+
+fn no_search() -> UciSearch {
+    UciSearch {
+        best_move: None,
+        info: None,
+    }
+}
+
+fn fools_mate() -> (Game, Vec<UciSearch>) {
+    let game = game(&["f3", "e5", "g4", "Qh4#"]);
+    let searches = vec![
+        search(&game, 0, "e2e4", UciScore::Cp(20)),
+        search(&game, 1, "e7e5", UciScore::Cp(0)),
+        search(&game, 2, "b1c3", UciScore::Cp(-10)),
+        search(&game, 3, "d8h4", UciScore::Mate(1)),
+        no_search(),
+    ];
+    (game, searches)
+}
+
+#[test]
+fn blunder() {
+    let (game, searches) = fools_mate();
+    let report = GameReport::new(&game, &searches);
+
+    let g4 = &report.moves[2];
+    assert_eq!(g4.san, "g4");
+    assert_eq!(g4.color, Color::White);
+    assert_eq!(g4.eval, -1000);
+    assert_eq!(g4.centipawn_loss, 990);
+    assert_eq!(g4.judgement, Some(MoveJudgement::Blunder));
+    assert_eq!(g4.best_move.as_deref(), Some("Nc3"));
+
+    // a small loss is not judged
+    let f3 = &report.moves[0];
+    assert_eq!(f3.centipawn_loss, 20);
+    assert_eq!(f3.judgement, None);
+    assert_eq!(f3.best_move, None);
+
+    assert_eq!(report.white.blunders, 1);
+    assert_eq!(report.white.mistakes, 0);
+    assert!(report.white.accuracy < 60.0);
+}
+
+#[test]
+fn best_moves_are_accurate() {
+    let (game, searches) = fools_mate();
+    let report = GameReport::new(&game, &searches);
+
+    // black played the engine's moves, the mate keeps the winning evaluation
+    let mate = &report.moves[3];
+    assert_eq!(mate.eval, -1000);
+    assert_eq!(mate.centipawn_loss, 0);
+    assert_eq!(mate.accuracy, 100.0);
+
+    assert_eq!(report.black.accuracy, 100.0);
+    assert_eq!(report.black.blunders, 0);
+}
+
+#[test]
+fn judgements() {
+    let game = game(&["e4", "e5", "Nf3", "Nc6"]);
+    let searches = vec![
+        search(&game, 0, "e2e4", UciScore::Cp(20)),
+        search(&game, 1, "e7e5", UciScore::Cp(60)),
+        search(&game, 2, "g1f3", UciScore::Cp(80)),
+        search(&game, 3, "b8c6", UciScore::Cp(150)),
+        search(&game, 4, "f1b5", UciScore::Cp(-140)),
+    ];
+    let report = GameReport::new(&game, &searches);
+
+    let judgements: Vec<_> = report.moves.iter().map(|mv| mv.judgement).collect();
+    assert_eq!(
+        judgements,
+        [
+            Some(MoveJudgement::Inaccuracy),
+            Some(MoveJudgement::Mistake),
+            Some(MoveJudgement::Blunder),
+            None
+        ]
+    );
+    // judged moves which were the engine's move have no better move
+    assert!(report.moves.iter().all(|mv| mv.best_move.is_none()));
+}
+
+#[test]
+fn annotated_pgn() {
+    let (game, searches) = fools_mate();
+    let report = GameReport::new(&game, &searches);
+
+    let headers = PgnHeaders {
+        event: "Club game".to_string(),
+        site: "Chess API".to_string(),
+        date: "2024.01.01".to_string(),
+        white: "alice".to_string(),
+        black: "bob".to_string(),
+        result: "0-1".to_string(),
+        time_control: "-".to_string(),
+    };
+    let pgn = game.to_annotated_pgn(&headers, &report.pgn_annotations());
+    // long comments wrap over lines
+    let movetext = pgn.split_whitespace().collect::<Vec<_>>().join(" ");
+    assert!(
+        movetext.contains("2. g4 $4 { [%eval -10.00] Blunder. Nc3 was best. } 2... Qh4#"),
+        "{pgn}"
+    );
+
+    let parsed = parse_pgn(&pgn).unwrap();
+    let g4 = &parsed[0].moves[2];
+    assert_eq!(g4.nags, [4]);
+    let comment = g4.comment.as_deref().unwrap_or_default();
+    assert_eq!(
+        comment.split_whitespace().collect::<Vec<_>>().join(" "),
+        "[%eval -10.00] Blunder. Nc3 was best."
+    );
+}
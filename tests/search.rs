@@ -0,0 +1,152 @@
+//! The built-in search, finding mates and keeping to the depth
+//! and time limits of the computer's levels
+
+use std::time::{Duration, Instant};
+
+use chess_api::bot::BotLevel;
+use chess_api::chess::search::{Search, SearchLimits, SearchResult, MATE_SCORE};
+use chess_api::chess::{Game, Position};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn from_fen(fen: &str) -> Game {
+    Game::new(Position::from_fen(fen).expect("valid FEN"))
+}
+
+fn search(game: &Game, depth: u32, time: Duration) -> SearchResult {
+    let limits = SearchLimits {
+        depth,
+        time,
+        randomness: 0,
+    };
+    Search::new(limits).run(game, |_| {})
+}
+
+/// score of the side to move mating in `moves` of its own moves
+fn mate_in(moves: i32) -> i32 {
+    MATE_SCORE - (2 * moves - 1)
+}
+
+#[test]
+fn mate_in_one() {
+    let game = from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    let result = search(&game, 8, Duration::from_secs(10));
+
+    assert_eq!(game.position().san(result.best_move.unwrap()), "Ra8#");
+    assert_eq!(result.score, mate_in(1));
+    // the search stops at the first mate found
+    assert_eq!(result.depth, 1);
+
+    let game = from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4");
+    let result = search(&game, 8, Duration::from_secs(10));
+    assert_eq!(game.position().san(result.best_move.unwrap()), "Qxf7#");
+}
+
+#[test]
+fn mate_in_two() {
+    let mut game = from_fen("7k/8/5K2/8/8/8/8/R7 w - - 0 1");
+    let result = search(&game, 8, Duration::from_secs(10));
+    assert_eq!(result.score, mate_in(2));
+
+    // every reply to the first move runs into a mate in one
+    game.push(result.best_move.unwrap());
+    for reply in game.position().legal_moves() {
+        let mut line = game.clone();
+        line.push(reply);
+        let result = search(&line, 8, Duration::from_secs(10));
+        assert_eq!(result.score, mate_in(1), "after {reply}");
+
+        line.push(result.best_move.unwrap());
+        assert!(line.outcome().is_some(), "after {reply}");
+    }
+}
+
+#[test]
+fn getting_mated() {
+    // the king has one move, then the rook mates
+    let game = from_fen("7k/8/6K1/8/8/8/8/R7 b - - 0 1");
+    let result = search(&game, 8, Duration::from_secs(10));
+
+    assert_eq!(game.position().san(result.best_move.unwrap()), "Kg8");
+    assert_eq!(result.score, -(MATE_SCORE - 2));
+}
+
+#[test]
+fn depth_limit() {
+    let game = from_fen(KIWIPETE);
+    let mut depths = Vec::new();
+    let limits = SearchLimits {
+        depth: 3,
+        time: Duration::from_secs(60),
+        randomness: 0,
+    };
+    let result = Search::new(limits).run(&game, |iteration| depths.push(iteration.depth));
+
+    assert_eq!(depths, [1, 2, 3]);
+    assert_eq!(result.depth, 3);
+    assert!(game
+        .position()
+        .legal_moves()
+        .contains(&result.best_move.unwrap()));
+}
+
+#[test]
+fn time_limit() {
+    let game = from_fen(KIWIPETE);
+    let limits = SearchLimits {
+        depth: 64,
+        time: Duration::from_millis(200),
+        randomness: 0,
+    };
+
+    // the first iteration always completes, the search stops
+    // within the time limit of any deeper iteration
+    let start = Instant::now();
+    let mut first_iteration = None;
+    let result = Search::new(limits).run(&game, |iteration| {
+        if iteration.depth == 1 {
+            first_iteration = Some(start.elapsed());
+        }
+    });
+    let first_iteration = first_iteration.unwrap();
+
+    assert!(start.elapsed() < first_iteration.max(limits.time) + Duration::from_secs(2));
+    assert!(result.depth >= 1 && result.depth < 64);
+    assert!(game
+        .position()
+        .legal_moves()
+        .contains(&result.best_move.unwrap()));
+
+    let result = search(&game, 64, Duration::ZERO);
+    assert_eq!(result.depth, 1);
+    assert!(result.best_move.is_some());
+}
+
+#[test]
+fn bot_levels() {
+    assert_eq!(BotLevel::new(0), None);
+    assert_eq!(BotLevel::new(BotLevel::MAX + 1), None);
+    assert_eq!(BotLevel::default(), BotLevel::new(3).unwrap());
+
+    // stronger levels search deeper, for longer and with less noise
+    let limits: Vec<SearchLimits> = (1..=BotLevel::MAX)
+        .map(|level| BotLevel::new(level).unwrap().limits())
+        .collect();
+    for pair in limits.windows(2) {
+        assert!(pair[0].depth < pair[1].depth);
+        assert!(pair[0].time < pair[1].time);
+        assert!(pair[0].randomness >= pair[1].randomness);
+    }
+
+    // the weakest levels are held to their depth
+    let game = from_fen(KIWIPETE);
+    for level in 1..=3 {
+        let limits = BotLevel::new(level).unwrap().limits();
+        let result = Search::new(limits).run(&game, |_| {});
+        assert!(result.depth <= limits.depth, "level {level}");
+        assert!(game
+            .position()
+            .legal_moves()
+            .contains(&result.best_move.unwrap()));
+    }
+}
@@ -0,0 +1,510 @@
+//! Games played over the server, who may move and the order
+//! the errors of a rejected move are reported in
+
+use std::thread;
+use std::time::Duration;
+
+use chess_api::chess::pgn::game_from_pgn;
+use chess_api::chess::{Color, Game, GameEndReason, GameResult, Position};
+use chess_api::clock::{CorrespondenceClock, IncrementMode, TimeControl, TimeStage};
+use chess_api::game::{GameError, GameManager, NewGameOptions, SessionGame};
+
+const WHITE: usize = 1;
+const BLACK: usize = 2;
+const SPECTATOR: usize = 3;
+const CAROL: usize = 4;
+
+/// a game alice created with white and bob joined with black
+fn started_game() -> SessionGame {
+    let mut game = SessionGame::new("game".to_string(), WHITE, "alice", Game::default());
+    game.join_game(BLACK, "bob");
+    game
+}
+
+/// play moves alternating between the players, white first
+fn play(game: &mut SessionGame, moves: &[&str]) {
+    for mv in moves {
+        let session_id = match game.side_to_move() {
+            Color::White => WHITE,
+            Color::Black => BLACK,
+        };
+        game.play_move(session_id, mv).unwrap();
+    }
+}
+
+#[test]
+fn players_move_in_turn() {
+    let mut game = started_game();
+
+    let info = game.play_move(WHITE, "e2e4").unwrap();
+    assert_eq!(info.san, "e4");
+    assert_eq!(info.uci, "e2e4");
+    assert_eq!(
+        info.fen,
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+
+    assert_eq!(
+        game.play_move(WHITE, "d4").unwrap_err(),
+        GameError::NotYourTurn(Color::Black)
+    );
+
+    let info = game.play_move(BLACK, "e5").unwrap();
+    assert_eq!(info.uci, "e7e5");
+    assert_eq!(game.side_to_move(), Color::White);
+
+    assert_eq!(
+        game.play_move(BLACK, "d5").unwrap_err(),
+        GameError::NotYourTurn(Color::White)
+    );
+}
+
+#[test]
+fn black_cannot_move_first() {
+    let mut game = started_game();
+
+    assert_eq!(
+        game.play_move(BLACK, "e5").unwrap_err(),
+        GameError::NotYourTurn(Color::White)
+    );
+    assert_eq!(game.fen(), Game::default().position().to_fen());
+}
+
+#[test]
+fn only_players_move() {
+    let mut game = started_game();
+
+    assert_eq!(
+        game.play_move(SPECTATOR, "e4").unwrap_err(),
+        GameError::NotAPlayer("game".to_string())
+    );
+    // a spectator is not told whose turn it is
+    play(&mut game, &["e4"]);
+    assert_eq!(
+        game.play_move(SPECTATOR, "e5").unwrap_err(),
+        GameError::NotAPlayer("game".to_string())
+    );
+}
+
+#[test]
+fn no_moves_before_the_opponent_joins() {
+    let mut game = SessionGame::new("game".to_string(), WHITE, "alice", Game::default());
+
+    assert_eq!(
+        game.play_move(WHITE, "e4").unwrap_err(),
+        GameError::GameNotStarted("game".to_string())
+    );
+    // not being a player comes first
+    assert_eq!(
+        game.play_move(SPECTATOR, "e4").unwrap_err(),
+        GameError::NotAPlayer("game".to_string())
+    );
+}
+
+#[test]
+fn illegal_moves_after_turn_checks() {
+    let mut game = started_game();
+
+    assert!(matches!(
+        game.play_move(WHITE, "e5").unwrap_err(),
+        GameError::InvalidMove(_)
+    ));
+    assert!(matches!(
+        game.play_move(WHITE, "xyz").unwrap_err(),
+        GameError::InvalidMove(_)
+    ));
+    // the turn is checked before the move itself
+    assert_eq!(
+        game.play_move(BLACK, "xyz").unwrap_err(),
+        GameError::NotYourTurn(Color::White)
+    );
+
+    // a rejected move changes nothing
+    play(&mut game, &["e4"]);
+    assert_eq!(game.side_to_move(), Color::Black);
+}
+
+#[test]
+fn no_moves_once_the_game_is_over() {
+    let mut game = started_game();
+    play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+
+    let info = game.game_over_info().unwrap();
+    assert_eq!(info.result, GameResult::BlackWins);
+    assert_eq!(info.reason, GameEndReason::Checkmate);
+
+    // the game being over comes before anything else
+    for session_id in [WHITE, BLACK, SPECTATOR] {
+        assert_eq!(
+            game.play_move(session_id, "e4").unwrap_err(),
+            GameError::GameOver("game".to_string())
+        );
+    }
+}
+
+/// a game alice created with white from `fen` and bob joined
+fn started_from(fen: &str) -> SessionGame {
+    let chess = Game::new(Position::from_fen(fen).unwrap());
+    let mut game = SessionGame::new("game".to_string(), WHITE, "alice", chess);
+    game.join_game(BLACK, "bob");
+    game
+}
+
+#[test]
+fn draws_claimed_by_repetition() {
+    let mut game = started_game();
+    play(
+        &mut game,
+        &["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"],
+    );
+    assert_eq!(
+        game.claim_draw(BLACK).unwrap_err(),
+        GameError::NoDrawToClaim
+    );
+
+    // the starting position occurs a third time
+    play(&mut game, &["Ng8"]);
+    assert_eq!(
+        game.claim_draw(SPECTATOR).unwrap_err(),
+        GameError::NotAPlayer("game".to_string())
+    );
+    let info = game.claim_draw(WHITE).unwrap();
+    assert_eq!(info.result, GameResult::Draw);
+    assert_eq!(info.reason, GameEndReason::ThreefoldRepetition);
+    assert_eq!(
+        game.claim_draw(BLACK).unwrap_err(),
+        GameError::GameOver("game".to_string())
+    );
+}
+
+#[test]
+fn draws_claimed_by_the_fifty_move_rule() {
+    let mut game = started_from("4k3/8/8/8/8/8/8/R3K3 w - - 98 80");
+    play(&mut game, &["Ra2"]);
+    assert_eq!(
+        game.claim_draw(WHITE).unwrap_err(),
+        GameError::NoDrawToClaim
+    );
+
+    play(&mut game, &["Kd7"]);
+    let info = game.claim_draw(WHITE).unwrap();
+    assert_eq!(info.reason, GameEndReason::FiftyMoveRule);
+
+    // a capture or pawn move starts the count again
+    let mut game = started_from("4k3/8/8/8/8/8/p7/R3K3 w - - 99 80");
+    play(&mut game, &["Rxa2"]);
+    assert_eq!(
+        game.claim_draw(BLACK).unwrap_err(),
+        GameError::NoDrawToClaim
+    );
+}
+
+#[test]
+fn no_claims_before_the_opponent_joins() {
+    let chess = Game::new(Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 100 80").unwrap());
+    let mut game = SessionGame::new("game".to_string(), WHITE, "alice", chess);
+    assert_eq!(
+        game.claim_draw(WHITE).unwrap_err(),
+        GameError::GameNotStarted("game".to_string())
+    );
+
+    game.join_game(BLACK, "bob");
+    assert_eq!(
+        game.claim_draw(WHITE).unwrap().reason,
+        GameEndReason::FiftyMoveRule
+    );
+}
+
+#[test]
+fn insufficient_material_ends_the_game() {
+    let mut game = started_from("4k3/8/8/8/8/8/4r3/3BK3 w - - 0 1");
+    play(&mut game, &["Kxe2"]);
+
+    // king and bishop against king is drawn without a claim
+    let info = game.game_over_info().unwrap();
+    assert_eq!(info.result, GameResult::Draw);
+    assert_eq!(info.reason, GameEndReason::InsufficientMaterial);
+}
+
+#[test]
+fn moves_after_the_flag_lose_on_time() {
+    let control = TimeControl {
+        stages: vec![TimeStage {
+            moves: None,
+            time: Duration::from_millis(20),
+        }],
+        increment: Duration::ZERO,
+        mode: IncrementMode::Fischer,
+    };
+    let mut game =
+        SessionGame::new("game".to_string(), WHITE, "alice", Game::default()).with_clock(control);
+    game.join_game(BLACK, "bob");
+
+    thread::sleep(Duration::from_millis(40));
+
+    // running out of time is reported to anyone who tries to move
+    assert_eq!(
+        game.play_move(SPECTATOR, "e4").unwrap_err(),
+        GameError::OutOfTime(Color::White)
+    );
+    let info = game.game_over_info().unwrap();
+    assert_eq!(info.result, GameResult::BlackWins);
+    assert_eq!(info.reason, GameEndReason::Timeout);
+
+    assert_eq!(
+        game.play_move(WHITE, "e4").unwrap_err(),
+        GameError::GameOver("game".to_string())
+    );
+}
+
+#[test]
+fn join_only_waiting_games() {
+    let mut manager = GameManager::new();
+    assert!(!manager.join_game("no-such-game", BLACK, "bob"));
+
+    let game_id = manager.new_game("alice", WHITE, Game::default(), NewGameOptions::default());
+    assert_eq!(manager.available_games().len(), 1);
+    assert!(manager.join_game(&game_id, BLACK, "bob"));
+
+    // a started game keeps its players
+    assert!(!manager.join_game(&game_id, CAROL, "carol"));
+    let game = manager.find_game(&game_id).unwrap();
+    assert_eq!(game.player_id(Color::Black), BLACK);
+    assert_eq!(game.player_name(Color::Black), Some("bob"));
+    assert!(manager.available_games().is_empty());
+}
+
+#[test]
+fn resuming_takes_the_seat_of_the_token() {
+    const RETURNING: usize = 4;
+
+    let mut game = SessionGame::new("game".to_string(), WHITE, "alice", Game::default())
+        .with_correspondence(CorrespondenceClock::new(3).unwrap());
+    game.join_game(BLACK, "bob");
+    play(&mut game, &["e4"]);
+
+    let white_token = game.resume_token(Color::White).to_string();
+    let black_token = game.resume_token(Color::Black).to_string();
+    assert_ne!(white_token, black_token);
+
+    // a username is not enough to take a seat
+    for token in ["", "bob", "alice"] {
+        assert_eq!(
+            game.resume(RETURNING, token).unwrap_err(),
+            GameError::InvalidResumeToken("game".to_string())
+        );
+    }
+    assert_eq!(game.player_id(Color::Black), BLACK);
+
+    assert_eq!(game.resume(RETURNING, &black_token), Ok(Color::Black));
+    assert_eq!(game.player_id(Color::Black), RETURNING);
+    assert_eq!(game.player_name(Color::Black), Some("bob"));
+    assert!(!game.is_player(BLACK));
+    game.play_move(RETURNING, "e5").unwrap();
+
+    // games with clocks or without can not be resumed
+    let mut game = started_game();
+    let token = game.resume_token(Color::White).to_string();
+    assert_eq!(
+        game.resume(RETURNING, &token).unwrap_err(),
+        GameError::NotCorrespondence("game".to_string())
+    );
+}
+
+#[test]
+fn resigning_ends_the_game() {
+    let mut game = started_game();
+    play(&mut game, &["e4", "e5", "Nf3"]);
+    game.offer_draw(BLACK).unwrap();
+    game.request_takeback(WHITE).unwrap();
+
+    assert_eq!(
+        game.resign(SPECTATOR).unwrap_err(),
+        GameError::NotAPlayer("game".to_string())
+    );
+    let info = game.resign(WHITE).unwrap();
+    assert_eq!(info.color, Color::White);
+
+    let info = game.game_over_info().unwrap();
+    assert_eq!(info.result, GameResult::BlackWins);
+    assert_eq!(info.reason, GameEndReason::Resignation);
+
+    // nothing is left to answer once the game is over
+    assert_eq!(game.pending_draw_offer(), None);
+    assert_eq!(game.pending_takeback(), None);
+    assert_eq!(
+        game.resign(BLACK).unwrap_err(),
+        GameError::GameOver("game".to_string())
+    );
+}
+
+#[test]
+fn draws_by_agreement() {
+    let mut game = started_game();
+    play(&mut game, &["e4"]);
+
+    assert_eq!(
+        game.answer_draw(BLACK, true).unwrap_err(),
+        GameError::NoDrawOffer
+    );
+    game.offer_draw(WHITE).unwrap();
+    assert_eq!(game.pending_draw_offer(), Some(Color::White));
+    assert_eq!(
+        game.offer_draw(BLACK).unwrap_err(),
+        GameError::DrawOfferPending
+    );
+    // a player does not answer their own offer
+    assert_eq!(
+        game.answer_draw(WHITE, true).unwrap_err(),
+        GameError::NoDrawOffer
+    );
+
+    assert_eq!(
+        game.request_takeback(BLACK).unwrap_err(),
+        GameError::NoMoveToTakeBack
+    );
+    game.request_takeback(WHITE).unwrap();
+    let info = game.answer_draw(BLACK, true).unwrap();
+    assert_eq!(info.color, Color::Black);
+
+    let info = game.game_over_info().unwrap();
+    assert_eq!(info.result, GameResult::Draw);
+    assert_eq!(info.reason, GameEndReason::Agreement);
+    assert_eq!(game.pending_takeback(), None);
+}
+
+#[test]
+fn declined_and_lapsed_draw_offers() {
+    let mut game = started_game();
+    play(&mut game, &["e4"]);
+
+    game.offer_draw(WHITE).unwrap();
+    game.answer_draw(BLACK, false).unwrap();
+    assert_eq!(game.pending_draw_offer(), None);
+    assert!(game.game_over_info().is_none());
+
+    // an offer lapses once a move is made
+    game.offer_draw(BLACK).unwrap();
+    play(&mut game, &["e5"]);
+    assert_eq!(game.pending_draw_offer(), None);
+    assert_eq!(game.expire_draw_offer().unwrap().color, Color::Black);
+    assert!(game.expire_draw_offer().is_none());
+    assert_eq!(
+        game.answer_draw(WHITE, true).unwrap_err(),
+        GameError::NoDrawOffer
+    );
+}
+
+#[test]
+fn draw_offers_cool_down() {
+    let mut game = started_game();
+
+    game.offer_draw(WHITE).unwrap();
+    game.answer_draw(BLACK, false).unwrap();
+    play(&mut game, &["e4", "e5"]);
+
+    // each player has their own cooldown
+    assert!(matches!(
+        game.offer_draw(WHITE).unwrap_err(),
+        GameError::DrawOfferTooSoon(59..=60)
+    ));
+    game.offer_draw(BLACK).unwrap();
+}
+
+#[test]
+fn takebacks_restore_the_clocks() {
+    // a minute for the first move, five more for the rest
+    let control = TimeControl {
+        stages: vec![
+            TimeStage {
+                moves: Some(1),
+                time: Duration::from_secs(60),
+            },
+            TimeStage {
+                moves: None,
+                time: Duration::from_secs(300),
+            },
+        ],
+        increment: Duration::from_secs(10),
+        mode: IncrementMode::Fischer,
+    };
+    let mut game =
+        SessionGame::new("game".to_string(), WHITE, "alice", Game::default()).with_clock(control);
+    game.join_game(BLACK, "bob");
+    play(&mut game, &["e4", "e5", "Nf3", "Nc6"]);
+
+    let clock = game.clock_info().unwrap();
+    assert!(clock.white > 360_000 && clock.black > 360_000);
+
+    // back to before the second moves, the increments stay
+    game.request_takeback(WHITE).unwrap();
+    game.answer_takeback(BLACK, true).unwrap();
+    let clock = game.clock_info().unwrap();
+    assert!(clock.white > 369_000 && clock.white <= 370_000);
+    assert!(clock.black > 369_000 && clock.black <= 370_000);
+    assert_eq!(clock.running, Some(Color::White));
+
+    // the next moves are timed like the ones taken back
+    play(&mut game, &["Nf3", "Nc6"]);
+    let clock = game.clock_info().unwrap();
+    assert!(clock.white > 379_000 && clock.white <= 380_000);
+    assert!(clock.black > 379_000 && clock.black <= 380_000);
+}
+
+#[test]
+fn seeded_moves_are_not_taken_back() {
+    let chess = game_from_pgn("end", "1. e4 e5 *").unwrap();
+    let mut game = SessionGame::new("game".to_string(), WHITE, "alice", chess);
+    game.join_game(BLACK, "bob");
+
+    for session_id in [WHITE, BLACK] {
+        assert_eq!(
+            game.request_takeback(session_id).unwrap_err(),
+            GameError::NoMoveToTakeBack
+        );
+    }
+
+    play(&mut game, &["Nf3"]);
+    assert_eq!(
+        game.request_takeback(BLACK).unwrap_err(),
+        GameError::NoMoveToTakeBack
+    );
+    game.request_takeback(WHITE).unwrap();
+    let info = game.answer_takeback(BLACK, true).unwrap();
+    assert_eq!(info.plies, 1);
+    assert_eq!(
+        info.fen.as_deref(),
+        Some("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2")
+    );
+}
+
+#[test]
+fn takebacks_do_not_make_the_game_abortable() {
+    let mut game = started_game();
+
+    // before both sides moved the game can be aborted anyway
+    play(&mut game, &["e4"]);
+    game.request_takeback(WHITE).unwrap();
+    game.answer_takeback(BLACK, true).unwrap();
+    assert_eq!(game.fen(), Game::default().position().to_fen());
+
+    play(&mut game, &["e4", "e5"]);
+    for session_id in [WHITE, BLACK] {
+        assert_eq!(
+            game.request_takeback(session_id).unwrap_err(),
+            GameError::NoMoveToTakeBack
+        );
+    }
+
+    play(&mut game, &["Nf3"]);
+    assert_eq!(
+        game.request_takeback(BLACK).unwrap_err(),
+        GameError::NoMoveToTakeBack
+    );
+    game.request_takeback(WHITE).unwrap();
+    game.answer_takeback(BLACK, true).unwrap();
+    assert_eq!(
+        game.abort(WHITE).unwrap_err(),
+        GameError::AbortTooLate("game".to_string())
+    );
+}
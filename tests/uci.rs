@@ -0,0 +1,170 @@
+//! The UCI bridge against `fake-engine.sh`, a shell script
+//! standing in for a real engine such as Stockfish
+
+use std::time::{Duration, Instant};
+
+use chess_api::chess::{Game, Position, Variant};
+use chess_api::uci::{UciEngine, UciError, UciInfo, UciLimits, UciScore};
+
+const FAKE_ENGINE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fake-engine.sh");
+
+const LIMITS: UciLimits = UciLimits {
+    movetime: Duration::from_millis(100),
+    depth: None,
+};
+
+fn start(mode: &str, best_move: &str) -> Result<UciEngine, UciError> {
+    UciEngine::start("sh", &[FAKE_ENGINE, mode, best_move])
+}
+
+#[test]
+fn handshake() {
+    let engine = start("play", "e2e4").expect("engine starts");
+
+    assert_eq!(engine.name(), "Fake Engine");
+    assert!(engine.has_option("uci_chess960"));
+    assert!(!engine.has_option("UCI_Variant"));
+}
+
+#[test]
+fn best_move_and_info() {
+    let mut engine = start("play", "e2e4").expect("engine starts");
+
+    let mut infos = Vec::new();
+    let search = engine
+        .go(&Game::default(), LIMITS, |info| infos.push(info.clone()))
+        .expect("engine searches");
+
+    assert_eq!(
+        search.best_move.map(|mv| mv.to_string()).as_deref(),
+        Some("e2e4")
+    );
+    assert_eq!(infos.len(), 2);
+
+    let info = search.info.expect("engine sent info");
+    assert_eq!(info.depth, Some(2));
+    assert_eq!(info.score, Some(UciScore::Cp(35)));
+    assert_eq!(info.pv, ["e2e4", "e7e5"]);
+}
+
+#[test]
+fn position_with_moves() {
+    let mut engine = start("play", "g1f3").expect("engine starts");
+
+    let mut game = Game::default();
+    game.play("e4").unwrap();
+    game.play("e5").unwrap();
+
+    let search = engine.go(&game, LIMITS, |_| {}).expect("engine searches");
+    assert_eq!(
+        search.best_move.map(|mv| mv.to_string()).as_deref(),
+        Some("g1f3")
+    );
+
+    // the engine stays usable for the next search
+    game.play("Nf3").unwrap();
+    let search = engine.go(&game, LIMITS, |_| {});
+    assert_eq!(search, Err(UciError::IllegalMove("g1f3".to_string())));
+}
+
+#[test]
+fn illegal_move() {
+    let mut engine = start("play", "e2e5").expect("engine starts");
+
+    let search = engine.go(&Game::default(), LIMITS, |_| {});
+    assert_eq!(search, Err(UciError::IllegalMove("e2e5".to_string())));
+}
+
+#[test]
+fn no_move() {
+    let mut engine = start("play", "(none)").expect("engine starts");
+
+    let search = engine
+        .go(&Game::default(), LIMITS, |_| {})
+        .expect("engine searches");
+    assert_eq!(search.best_move, None);
+}
+
+#[test]
+fn crash() {
+    let mut engine = start("crash", "e2e4").expect("engine starts");
+
+    let search = engine.go(&Game::default(), LIMITS, |_| {});
+    assert!(matches!(search, Err(UciError::Crashed(_))), "{search:?}");
+}
+
+#[test]
+fn stopped_when_slow() {
+    let mut engine = start("slow", "d2d4").expect("engine starts");
+
+    let search = engine
+        .go(&Game::default(), LIMITS, |_| {})
+        .expect("engine moves once stopped");
+    assert_eq!(
+        search.best_move.map(|mv| mv.to_string()).as_deref(),
+        Some("d2d4")
+    );
+}
+
+#[test]
+fn timeout() {
+    let mut engine = start("hang", "e2e4").expect("engine starts");
+
+    let started = Instant::now();
+    let search = engine.go(&Game::default(), LIMITS, |_| {});
+    assert_eq!(search, Err(UciError::Timeout("go".to_string())));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn handshake_timeout() {
+    let engine = start("silent", "e2e4");
+    assert!(matches!(engine, Err(UciError::Timeout(_))));
+}
+
+#[test]
+fn missing_engine() {
+    let engine = UciEngine::start::<&str>("/nonexistent/engine", &[]);
+    assert!(matches!(engine, Err(UciError::Spawn(..))));
+}
+
+#[test]
+fn unsupported_variant() {
+    let mut engine = start("play", "e2e4").expect("engine starts");
+
+    let game = Game::new(Position::starting().with_variant(Variant::Atomic));
+    let search = engine.go(&game, LIMITS, |_| {});
+    assert_eq!(search, Err(UciError::UnsupportedVariant(Variant::Atomic)));
+}
+
+#[test]
+fn chess960_castling() {
+    let mut engine = start("play", "b1h1").expect("engine starts");
+
+    let start = Position::from_fen("1k5r/8/8/8/8/8/8/1K5R w Hh - 0 1").unwrap();
+    let search = engine
+        .go(&Game::new(start), LIMITS, |_| {})
+        .expect("engine searches");
+    let castle = search.best_move.expect("engine castles");
+    assert_eq!(start.uci(castle), "b1h1");
+}
+
+#[test]
+fn parse_info() {
+    let info = UciInfo::parse(
+        "info depth 12 seldepth 18 multipv 1 score mate -3 nodes 123456 nps 1000 time 55 pv e2e4 e7e5",
+    )
+    .expect("info line");
+
+    assert_eq!(info.depth, Some(12));
+    assert_eq!(info.seldepth, Some(18));
+    assert_eq!(info.score, Some(UciScore::Mate(-3)));
+    assert_eq!(info.nodes, Some(123456));
+    assert_eq!(info.time, Some(55));
+    assert_eq!(info.pv, ["e2e4", "e7e5"]);
+
+    let info = UciInfo::parse("info depth 5 score cp 12 lowerbound pv d2d4").expect("info line");
+    assert_eq!(info.score, Some(UciScore::Cp(12)));
+
+    assert_eq!(UciInfo::parse("bestmove e2e4"), None);
+}
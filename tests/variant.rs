@@ -0,0 +1,108 @@
+//! Win conditions of the variants and starting positions in which
+//! a game would already be over
+
+use chess_api::chess::fen::FenError;
+use chess_api::chess::{Color, Game, GameEndReason, GameResult, Position, Variant};
+use chess_api::game::{GameError, NewGameOptions};
+
+fn variant_game(fen: &str, variant: Variant) -> Game {
+    Game::new(
+        Position::from_fen(fen)
+            .expect("valid FEN")
+            .with_variant(variant),
+    )
+}
+
+fn assert_won(game: &Game, color: Color, reason: GameEndReason) {
+    let outcome = game.outcome().expect("game over");
+    assert_eq!(outcome.result, GameResult::win_for(color));
+    assert_eq!(outcome.reason, reason);
+}
+
+#[test]
+fn king_of_the_hill() {
+    let mut game = variant_game("4k3/8/8/8/8/4K3/8/8 w - - 0 1", Variant::KingOfTheHill);
+    game.play("Kd3").unwrap();
+    assert!(game.outcome().is_none());
+    game.play("Kd7").unwrap();
+    game.play("Kd4").unwrap();
+    assert_won(&game, Color::White, GameEndReason::KingOfTheHill);
+}
+
+#[test]
+fn three_check() {
+    let mut game = variant_game("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0", Variant::ThreeCheck);
+    assert_eq!(game.position().checks(Color::White), 2);
+    game.play("Ra8+").unwrap();
+    assert_won(&game, Color::White, GameEndReason::ThreeChecks);
+}
+
+#[test]
+fn atomic_captures_explode() {
+    let mut game = variant_game("4k3/4p3/8/8/8/8/8/4QK2 w - - 0 1", Variant::Atomic);
+    game.play("Qxe7").unwrap();
+    assert_eq!(game.position().king_square(Color::Black), None);
+    assert_won(&game, Color::White, GameEndReason::KingExploded);
+}
+
+#[test]
+fn crazyhouse_drops() {
+    let mut game = Game::new(Position::starting().with_variant(Variant::Crazyhouse));
+    for san in ["e4", "d5", "exd5", "Qxd5"] {
+        game.play(san).unwrap();
+    }
+    assert_eq!(game.position().pockets().total(), 2);
+
+    // a captured piece is dropped for the side which took it
+    game.play("P@e6").unwrap();
+    assert_eq!(game.position().pockets().total(), 1);
+    assert!(game.play("P@e1").is_err());
+}
+
+#[test]
+fn check_counters_are_bounded() {
+    for checks in ["+4+0", "+0+9", "+255+0", "+1"] {
+        assert!(
+            matches!(
+                Position::from_fen(&format!("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 {checks}")),
+                Err(FenError::Checks(_))
+            ),
+            "{checks}"
+        );
+    }
+    assert!(Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +3+2").is_ok());
+}
+
+#[test]
+fn decided_positions_are_rejected() {
+    let decided = [
+        // king already on the hill
+        "koth 4k3/8/8/8/3K4/8/8/8 w - - 0 1",
+        // three checks given
+        "threecheck 4k3/8/8/8/8/8/8/R3K3 b - - 0 1 +3+0",
+        // checkmate, stalemate and bare kings
+        "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+        "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        // the seventy-five-move rule
+        "4k3/8/8/8/8/8/8/R3K3 w - - 150 100",
+    ];
+    for args in decided {
+        let options = NewGameOptions::parse(args).unwrap();
+        assert_eq!(
+            options.to_game().unwrap_err(),
+            GameError::PositionDecided,
+            "{args}"
+        );
+    }
+
+    let playable = [
+        "koth 4k3/8/8/8/8/3K4/8/8 w - - 0 1",
+        "threecheck 4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+2",
+        "atomic 4k3/4p3/8/8/8/8/8/4QK2 w - - 0 1",
+    ];
+    for args in playable {
+        let options = NewGameOptions::parse(args).unwrap();
+        assert!(options.to_game().is_ok(), "{args}");
+    }
+}